@@ -0,0 +1,164 @@
+//! Inspects captures (see [protocol::capture]) and replays them.
+
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use protocol::{
+    capture::{CaptureReader, CaptureRecord},
+    packet::PacketDirection,
+    state::State,
+};
+
+pub mod replay;
+
+#[derive(Parser)]
+#[command(
+    name = "jam-capture",
+    about = "Lists, decodes and replays packet captures"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints one line per packet.
+    List {
+        file: PathBuf,
+        #[command(flatten)]
+        filter: Filter,
+    },
+    /// Prints every packet with all of its fields.
+    Show {
+        file: PathBuf,
+        #[command(flatten)]
+        filter: Filter,
+    },
+    /// Sends the serverbound packets of a capture to a server, as the client did.
+    Replay {
+        file: PathBuf,
+        /// The server to replay against, which has to be in offline mode.
+        #[arg(short, long, default_value = "127.0.0.1:25565")]
+        server: String,
+        /// Waits between packets as long as the client did, instead of sending them right away.
+        #[arg(long)]
+        realtime: bool,
+        /// How long to keep reading from the server after the last packet, in milliseconds.
+        #[arg(long, default_value_t = 1000)]
+        linger: u64,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DirectionArg {
+    Serverbound,
+    Clientbound,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StateArg {
+    Handshaking,
+    Status,
+    Login,
+    Play,
+}
+
+#[derive(Args)]
+struct Filter {
+    /// Only shows packets with these names, e.g. `ChatMessage`.
+    #[arg(short, long, value_delimiter = ',')]
+    name: Vec<String>,
+    #[arg(short, long)]
+    direction: Option<DirectionArg>,
+    #[arg(short, long)]
+    state: Option<StateArg>,
+}
+
+impl Filter {
+    fn matches(&self, record: &CaptureRecord, name: Option<&str>) -> bool {
+        let direction = match self.direction {
+            Some(DirectionArg::Serverbound) => record.direction == PacketDirection::Serverbound,
+            Some(DirectionArg::Clientbound) => record.direction == PacketDirection::Clientbound,
+            None => true,
+        };
+        let state = match self.state {
+            Some(StateArg::Handshaking) => record.state == State::Handshaking,
+            Some(StateArg::Status) => record.state == State::Status,
+            Some(StateArg::Login) => record.state == State::Login,
+            Some(StateArg::Play) => record.state == State::Play,
+            None => true,
+        };
+        let name = self.name.is_empty()
+            || name.is_some_and(|name| self.name.iter().any(|n| n.eq_ignore_ascii_case(name)));
+
+        direction && state && name
+    }
+}
+
+fn open(path: &PathBuf) -> anyhow::Result<CaptureReader<BufReader<File>>> {
+    CaptureReader::new(BufReader::new(File::open(path)?))
+}
+
+/// The header of a record, e.g. `#3 +120ms C->S Play 0x01 4B`.
+fn header(index: usize, start: u64, record: &CaptureRecord) -> String {
+    format!(
+        "#{} +{}ms {} {:?} {:#04x} {}B",
+        index,
+        record.time.saturating_sub(start),
+        match record.direction {
+            PacketDirection::Serverbound => "C->S",
+            PacketDirection::Clientbound => "S->C",
+        },
+        record.state,
+        record.frame.id,
+        record.frame_len(),
+    )
+}
+
+/// Prints the records passing the filter, either one per line or with all of their fields.
+fn print(path: &PathBuf, filter: &Filter, verbose: bool) -> anyhow::Result<()> {
+    let mut start = None;
+
+    for (index, record) in open(path)?.enumerate() {
+        let record = record?;
+        let start = *start.get_or_insert(record.time);
+        let packet = record.decode();
+
+        if !filter.matches(&record, packet.as_ref().ok().map(|packet| packet.name())) {
+            continue;
+        }
+
+        let header = header(index, start, &record);
+        match (packet, verbose) {
+            (Ok(packet), false) => println!("{} {}", header, packet.name()),
+            (Ok(packet), true) => println!("{}\n{:#?}\n", header, packet),
+            (Err(error), _) => println!("{} ? ({})", header, error),
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    match Cli::parse().command {
+        Command::List { file, filter } => print(&file, &filter, false),
+        Command::Show { file, filter } => print(&file, &filter, true),
+        Command::Replay {
+            file,
+            server,
+            realtime,
+            linger,
+        } => {
+            let records = open(&file)?.collect::<anyhow::Result<Vec<_>>>()?;
+            let summary = replay::replay(&records, &server, realtime, linger).await?;
+
+            println!(
+                "replayed {} packets, received {}",
+                summary.sent, summary.received
+            );
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Replays the client's half of a capture against a server.
+//!
+//! The serverbound packets are sent in order, each once the server has moved the connection into
+//! the state it was captured in. Keep alives can't be replayed, as the server picks new IDs, so
+//! they're answered as they come in instead. Encrypted sessions can't be replayed either, which
+//! limits replays to offline mode servers.
+
+use std::time::Duration;
+
+use anyhow::bail;
+use bytes::BytesMut;
+use protocol::{
+    capture::{CaptureRecord, CapturedPacket},
+    packet::{
+        clientbound::{ClientboundLoginPacket, ClientboundPacket, ClientboundPlayPacket},
+        serverbound::{self, ServerboundLoginPacket, ServerboundPacket, ServerboundPlayPacket},
+    },
+    session::ClientSession,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::{self, Instant},
+};
+
+/// How long to wait for the server to reach the state of the next packet.
+const STATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a replay did.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub sent: usize,
+    pub received: usize,
+}
+
+struct Replay {
+    stream: TcpStream,
+    session: ClientSession,
+    buf: BytesMut,
+    summary: Summary,
+}
+
+impl Replay {
+    /// Reads from the server until `done` holds, or the deadline passes. Returns whether `done`
+    /// held.
+    async fn read_until(
+        &mut self,
+        deadline: Instant,
+        done: impl Fn(&ClientSession) -> bool,
+    ) -> anyhow::Result<bool> {
+        loop {
+            while let Some(packet) = self.session.next_packet()? {
+                self.summary.received += 1;
+
+                match packet {
+                    ClientboundPacket::Play(ClientboundPlayPacket::KeepAlive(keep_alive)) => {
+                        self.session
+                            .send(serverbound::play::KeepAlivePacket { id: keep_alive.id })?;
+                    }
+                    ClientboundPacket::Play(ClientboundPlayPacket::Disconnect(disconnect)) => {
+                        bail!("disconnected: {:?}", disconnect.reason);
+                    }
+                    ClientboundPacket::Login(ClientboundLoginPacket::Disconnect(disconnect)) => {
+                        bail!("disconnected while logging in: {:?}", disconnect.reason);
+                    }
+                    ClientboundPacket::Login(ClientboundLoginPacket::EncryptionRequest(_)) => {
+                        bail!("the server requested encryption, which can't be replayed");
+                    }
+                    _ => {}
+                }
+            }
+
+            self.flush().await?;
+
+            if done(&self.session) {
+                return Ok(true);
+            }
+
+            tokio::select! {
+                _ = time::sleep_until(deadline) => return Ok(false),
+                read = self.stream.read_buf(&mut self.buf) => {
+                    if read? == 0 {
+                        bail!("the server closed the connection");
+                    }
+
+                    self.session.receive_bytes(self.buf.split());
+                }
+            }
+        }
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if self.session.has_outbound() {
+            self.stream.write_all(&self.session.take_outbound()).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Replays the serverbound packets of `records` against the server at `address`.
+///
+/// With `realtime`, packets are sent as far apart as they were captured. Afterwards, the server
+/// is read from for `linger` milliseconds, so a disconnect caused by the last packets is noticed.
+pub async fn replay(
+    records: &[CaptureRecord],
+    address: &str,
+    realtime: bool,
+    linger: u64,
+) -> anyhow::Result<Summary> {
+    let stream = TcpStream::connect(address).await?;
+    stream.set_nodelay(true)?;
+
+    let mut replay = Replay {
+        stream,
+        session: ClientSession::new(),
+        buf: BytesMut::new(),
+        summary: Summary::default(),
+    };
+
+    let start = Instant::now();
+    let first = records.first().map_or(0, |record| record.time);
+
+    for record in records {
+        let packet = match record.decode()? {
+            CapturedPacket::Serverbound(packet) => packet,
+            CapturedPacket::Clientbound(_) => continue,
+        };
+
+        match &packet {
+            ServerboundPacket::Play(ServerboundPlayPacket::KeepAlive(_)) => continue,
+            ServerboundPacket::Login(ServerboundLoginPacket::EncryptionResponse(_)) => {
+                bail!("the capture is of an encrypted session, which can't be replayed");
+            }
+            _ => {}
+        }
+
+        if realtime {
+            let at = start + Duration::from_millis(record.time.saturating_sub(first));
+            replay.read_until(at, |_| false).await?;
+        }
+
+        let state = record.state;
+        if !replay
+            .read_until(Instant::now() + STATE_TIMEOUT, |session| {
+                session.state() == state
+            })
+            .await?
+        {
+            bail!(
+                "the server didn't move to the {:?} state in time (it's in {:?})",
+                state,
+                replay.session.state()
+            );
+        }
+
+        replay.session.send_any(packet)?;
+        replay.summary.sent += 1;
+    }
+
+    replay.flush().await?;
+    replay
+        .read_until(Instant::now() + Duration::from_millis(linger), |_| false)
+        .await?;
+
+    Ok(replay.summary)
+}
+
+#[cfg(test)]
+mod test {
+    use protocol::{
+        framed::{PacketReader, PacketWriter},
+        packet::{
+            clientbound,
+            serverbound::{
+                self, ServerboundHandshakingPacket, ServerboundLoginPacket, ServerboundPlayPacket,
+            },
+        },
+        session::{ClientSession, ServerSession},
+        varint::VarInt,
+    };
+    use tokio::net::TcpListener;
+
+    use super::{replay, Summary};
+
+    /// Captures a client logging in and chatting, as well as what the server sent back.
+    fn capture() -> Vec<protocol::capture::CaptureRecord> {
+        let mut client = ClientSession::new();
+        let mut server = ServerSession::new();
+        client.start_capture();
+
+        client
+            .send(serverbound::handshaking::HandshakePacket {
+                protocol_version: VarInt(47),
+                server_address: "localhost".to_string(),
+                server_port: 25565,
+                next_state: VarInt(2),
+            })
+            .unwrap();
+        client
+            .send(serverbound::login::LoginStartPacket {
+                name: "jam".to_string(),
+            })
+            .unwrap();
+        server.receive(&client.take_outbound());
+        while server.next_packet().unwrap().is_some() {}
+
+        server
+            .send(clientbound::login::LoginSuccessPacket {
+                uuid: "00000000-0000-0000-0000-000000000000".to_string(),
+                username: "jam".to_string(),
+            })
+            .unwrap();
+        server
+            .send(clientbound::play::KeepAlivePacket { id: VarInt(1) })
+            .unwrap();
+        client.receive(&server.take_outbound());
+        while client.next_packet().unwrap().is_some() {}
+
+        client
+            .send(serverbound::play::KeepAlivePacket { id: VarInt(1) })
+            .unwrap();
+        client
+            .send(serverbound::play::ChatMessagePacket {
+                message: "hi".to_string(),
+            })
+            .unwrap();
+
+        client.take_captured()
+    }
+
+    /// A server that sends a keep alive with a new ID, and expects it answered before the chat.
+    async fn serve(listener: TcpListener) -> anyhow::Result<()> {
+        let (stream, _) = listener.accept().await?;
+        let (reader, writer) = stream.into_split();
+        let (mut reader, mut writer) = (PacketReader::new(reader), PacketWriter::new(writer));
+
+        reader.read_packet::<ServerboundHandshakingPacket>().await?;
+        reader.read_packet::<ServerboundLoginPacket>().await?;
+        writer
+            .send(&clientbound::login::LoginSuccessPacket {
+                uuid: "00000000-0000-0000-0000-000000000000".to_string(),
+                username: "jam".to_string(),
+            })
+            .await?;
+
+        writer
+            .send(&clientbound::play::KeepAlivePacket { id: VarInt(42) })
+            .await?;
+
+        let Some(ServerboundPlayPacket::KeepAlive(keep_alive)) = reader.read_packet().await? else {
+            panic!("expected keep alive");
+        };
+        assert_eq!(VarInt(42), keep_alive.id);
+
+        let Some(ServerboundPlayPacket::ChatMessage(chat)) = reader.read_packet().await? else {
+            panic!("expected chat message");
+        };
+        assert_eq!("hi", chat.message);
+
+        // stays open until the replay is done
+        let _ = reader.read_frame().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn replay_login_and_chat() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(serve(listener));
+
+        // the captured keep alive is skipped, and the server's answered instead
+        let summary = replay(&capture(), &address, false, 100).await.unwrap();
+        assert_eq!(
+            Summary {
+                sent: 3,
+                received: 2
+            },
+            summary
+        );
+
+        server.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,34 @@
+//! The client's half of the login encryption.
+//!
+//! The client picks a random shared secret, and sends it (along with the server's verify token)
+//! encrypted with the server's RSA public key. Both ends then encrypt the connection with AES,
+//! using the shared secret.
+
+use anyhow::Context;
+pub use protocol::auth::server_hash;
+use rand::RngCore;
+use rsa::{pkcs8::DecodePublicKey, Pkcs1v15Encrypt, PublicKey, RsaPublicKey};
+
+/// Generates a new shared secret.
+pub fn shared_secret() -> [u8; 16] {
+    let mut secret = [0; 16];
+    rand::thread_rng().fill_bytes(&mut secret);
+
+    secret
+}
+
+/// Encrypts the shared secret and the verify token with the server's public key, which is sent
+/// as an X.509 `SubjectPublicKeyInfo` in DER.
+pub fn encrypt(
+    public_key: &[u8],
+    shared_secret: &[u8],
+    verify_token: &[u8],
+) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let key = RsaPublicKey::from_public_key_der(public_key).context("invalid public key")?;
+    let mut rng = rand::thread_rng();
+
+    Ok((
+        key.encrypt(&mut rng, Pkcs1v15Encrypt, shared_secret)?,
+        key.encrypt(&mut rng, Pkcs1v15Encrypt, verify_token)?,
+    ))
+}
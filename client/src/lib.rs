@@ -0,0 +1,556 @@
+//! A client for servers speaking the 1.8 protocol, for bots and integration tests.
+//!
+//! [Client::connect] logs in, handling compression and encryption along the way. Afterwards, the
+//! connection is driven by a background task, which answers keep alives by itself:
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! use client::{Client, ClientOptions};
+//! use protocol::packet::{clientbound::ClientboundPlayPacket, serverbound::play::ChatMessagePacket};
+//!
+//! let options = ClientOptions::builder().username("jam".to_string()).build();
+//! let mut client = Client::connect("localhost:25565", options).await?;
+//!
+//! while let Some(packet) = client.next_packet().await {
+//!     if let ClientboundPlayPacket::JoinGame(_) = packet {
+//!         client.send(ChatMessagePacket {
+//!             message: "hello".to_string(),
+//!         })?;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fs::File,
+    future::Future,
+    io::BufWriter,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, bail};
+use bytes::{Bytes, BytesMut};
+use protocol::{
+    capture::{CaptureRecord, CaptureWriter},
+    packet::{
+        clientbound::{ClientboundLoginPacket, ClientboundPacket, ClientboundPlayPacket},
+        serverbound::{self, ServerboundPacket, ServerboundPlayPacket},
+        AnyStatePacket, StatePacket,
+    },
+    session::ClientSession,
+    varint::VarInt,
+    version::ProtocolVersion,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::mpsc,
+    task::{self, JoinHandle},
+};
+use typed_builder::TypedBuilder;
+
+pub mod encryption;
+
+/// How many bytes are read from the connection at once.
+const READ_BUF_SIZE: usize = 4096;
+
+/// The port servers listen on, unless told otherwise.
+const DEFAULT_PORT: u16 = 25565;
+
+/// Joins the session of an online mode server, on behalf of the player.
+///
+/// Offline mode servers don't ask clients to join a session, so this is only needed for servers
+/// in online mode.
+pub trait Authenticator: Send + Sync {
+    /// Joins the session of the server with the given hash (see [encryption::server_hash]).
+    fn join<'a>(
+        &'a self,
+        server_hash: &'a str,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>;
+}
+
+#[derive(TypedBuilder)]
+pub struct ClientOptions {
+    username: String,
+
+    /// The version to connect with. Only 1.8 gets past the login; older versions are for testing
+    /// how servers turn them away.
+    #[builder(default)]
+    version: ProtocolVersion,
+
+    /// Joins sessions for online mode servers. Without one, the session isn't joined, and online
+    /// mode servers reject the client after encryption.
+    #[builder(default, setter(strip_option))]
+    authenticator: Option<Arc<dyn Authenticator>>,
+
+    /// Records every packet sent and received into a capture at this path.
+    #[builder(default, setter(strip_option))]
+    capture: Option<PathBuf>,
+}
+
+/// The capture a client writes to, if any. The records are written by a blocking task, so a slow
+/// disk doesn't hold the connection up.
+struct Capture(Option<CaptureTask>);
+
+struct CaptureTask {
+    records: std::sync::mpsc::Sender<CaptureRecord>,
+    writer: JoinHandle<anyhow::Result<()>>,
+}
+
+impl Capture {
+    async fn open(path: Option<&PathBuf>, session: &mut ClientSession) -> anyhow::Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self(None));
+        };
+
+        let path = path.clone();
+        let file = task::spawn_blocking(move || File::create(path)).await??;
+        let (sender, records) = std::sync::mpsc::channel();
+        let writer = task::spawn_blocking(move || {
+            CaptureWriter::new(BufWriter::new(file))?.write_from(records)
+        });
+
+        session.start_capture();
+        Ok(Self(Some(CaptureTask {
+            records: sender,
+            writer,
+        })))
+    }
+
+    /// Hands what the session captured so far to the capture's task.
+    fn write(&self, session: &mut ClientSession) {
+        if let Some(task) = &self.0 {
+            for record in session.take_captured() {
+                // the task only stops early if writing failed, which `finish` returns
+                let _ = task.records.send(record);
+            }
+        }
+    }
+
+    /// Waits for everything captured to be written.
+    async fn finish(self) -> anyhow::Result<()> {
+        match self.0 {
+            Some(CaptureTask { records, writer }) => {
+                drop(records);
+                writer.await?
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// A client in the play state.
+///
+/// Dropping the client (and every [ClientSender]) closes the connection.
+pub struct Client {
+    uuid: String,
+    username: String,
+    sender: ClientSender,
+    packets: mpsc::UnboundedReceiver<ClientboundPlayPacket<'static>>,
+    task: JoinHandle<anyhow::Result<()>>,
+}
+
+/// Sends packets through a [Client], from anywhere.
+#[derive(Clone)]
+pub struct ClientSender {
+    outbound: mpsc::UnboundedSender<ServerboundPlayPacket<'static>>,
+}
+
+impl ClientSender {
+    /// Sends a play packet to the server.
+    pub fn send<P>(&self, packet: P) -> anyhow::Result<()>
+    where
+        P: StatePacket<'static>,
+        ServerboundPlayPacket<'static>: From<P::Set>,
+    {
+        self.outbound
+            .send(P::Set::from(packet).into())
+            .map_err(|_| anyhow!("the connection is closed"))
+    }
+}
+
+impl Client {
+    /// Connects to `address` (`host` or `host:port`, with IPv6 addresses in brackets when there's
+    /// a port), and logs in.
+    pub async fn connect(address: &str, options: ClientOptions) -> anyhow::Result<Self> {
+        let (host, port) = split_address(address)?;
+        let host = host.as_str();
+
+        let mut stream = TcpStream::connect((host, port)).await?;
+        stream.set_nodelay(true)?;
+
+        let mut session = ClientSession::new();
+        let capture = Capture::open(options.capture.as_ref(), &mut session).await?;
+        session.send(serverbound::handshaking::HandshakePacket {
+            protocol_version: VarInt(options.version.protocol()),
+            server_address: host.to_string(),
+            server_port: port,
+            next_state: VarInt(2),
+        })?;
+        session.send(serverbound::login::LoginStartPacket {
+            name: options.username.clone(),
+        })?;
+        stream.write_all(&session.take_outbound()).await?;
+
+        let (uuid, username) = login(&mut stream, &mut session, &capture, &options).await?;
+
+        let (outbound, outbound_rx) = mpsc::unbounded_channel();
+        let (packets_tx, packets) = mpsc::unbounded_channel();
+        let task = tokio::spawn(play(stream, session, capture, outbound_rx, packets_tx));
+
+        Ok(Self {
+            uuid,
+            username,
+            sender: ClientSender { outbound },
+            packets,
+            task,
+        })
+    }
+
+    /// The UUID the server assigned, as a hyphenated string.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// The username the server assigned.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Sends a play packet to the server.
+    pub fn send<P>(&self, packet: P) -> anyhow::Result<()>
+    where
+        P: StatePacket<'static>,
+        ServerboundPlayPacket<'static>: From<P::Set>,
+    {
+        self.sender.send(packet)
+    }
+
+    /// A handle to send packets with, e.g. from another task.
+    pub fn sender(&self) -> ClientSender {
+        self.sender.clone()
+    }
+
+    /// Receives the next play packet, or `None` once the connection is closed.
+    ///
+    /// Packets are buffered until they're received, so a client that isn't read from still
+    /// answers keep alives.
+    pub async fn next_packet(&mut self) -> Option<ClientboundPlayPacket<'static>> {
+        self.packets.recv().await
+    }
+
+    /// Waits for the server to close the connection, returning why it failed if it did.
+    pub async fn closed(self) -> anyhow::Result<()> {
+        self.task.await?
+    }
+}
+
+/// Splits an address into the host and the port, which defaults to 25565.
+fn split_address(address: &str) -> anyhow::Result<(String, u16)> {
+    if let Ok(address) = address.parse::<SocketAddr>() {
+        return Ok((address.ip().to_string(), address.port()));
+    }
+
+    // an IP address without a port, IPv6 ones possibly in brackets
+    let ip = address.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = ip.parse::<IpAddr>() {
+        return Ok((ip.to_string(), DEFAULT_PORT));
+    }
+
+    match address.rsplit_once(':') {
+        Some((host, port)) => Ok((host.to_string(), port.parse()?)),
+        None => Ok((address.to_string(), DEFAULT_PORT)),
+    }
+}
+
+/// Reads the login packets up to [LoginSuccessPacket](protocol::packet::clientbound::login::LoginSuccessPacket),
+/// and returns the UUID and username in it.
+async fn login(
+    stream: &mut TcpStream,
+    session: &mut ClientSession,
+    capture: &Capture,
+    options: &ClientOptions,
+) -> anyhow::Result<(String, String)> {
+    let mut buf = BytesMut::new();
+
+    loop {
+        while let Some(packet) = session.next_packet()? {
+            capture.write(session);
+
+            match packet {
+                ClientboundPacket::Login(ClientboundLoginPacket::EncryptionRequest(request)) => {
+                    let secret = encryption::shared_secret();
+
+                    if let Some(authenticator) = &options.authenticator {
+                        let hash = encryption::server_hash(
+                            &request.server_id,
+                            &secret,
+                            &request.public_key,
+                        );
+                        authenticator.join(&hash).await?;
+                    }
+
+                    let (shared_secret, verify_token) =
+                        encryption::encrypt(&request.public_key, &secret, &request.verify_token)?;
+
+                    session.send(serverbound::login::EncryptionResponsePacket {
+                        shared_secret: Bytes::from(shared_secret),
+                        verify_token: Bytes::from(verify_token),
+                    })?;
+                    session.enable_encryption(&secret);
+                }
+                ClientboundPacket::Login(ClientboundLoginPacket::LoginSuccess(success)) => {
+                    return Ok((success.uuid, success.username));
+                }
+                ClientboundPacket::Login(ClientboundLoginPacket::Disconnect(disconnect)) => {
+                    bail!("disconnected while logging in: {:?}", disconnect.reason);
+                }
+                // compression is handled by the session
+                ClientboundPacket::Login(ClientboundLoginPacket::SetCompression(_)) => {}
+                packet => bail!("unexpected {:?} packet while logging in", packet.state()),
+            }
+        }
+
+        capture.write(session);
+        if session.has_outbound() {
+            stream.write_all(&session.take_outbound()).await?;
+        }
+
+        buf.reserve(READ_BUF_SIZE);
+        if stream.read_buf(&mut buf).await? == 0 {
+            bail!("connection closed while logging in");
+        }
+
+        session.receive_bytes(buf.split());
+    }
+}
+
+/// Drives the connection once in the play state, until either end closes it, then waits for the
+/// capture to be written.
+async fn play(
+    mut stream: TcpStream,
+    mut session: ClientSession,
+    capture: Capture,
+    outbound: mpsc::UnboundedReceiver<ServerboundPlayPacket<'static>>,
+    packets: mpsc::UnboundedSender<ClientboundPlayPacket<'static>>,
+) -> anyhow::Result<()> {
+    let result = play_loop(&mut stream, &mut session, &capture, outbound, packets).await;
+
+    capture.write(&mut session);
+    result.and(capture.finish().await)
+}
+
+async fn play_loop(
+    stream: &mut TcpStream,
+    session: &mut ClientSession,
+    capture: &Capture,
+    mut outbound: mpsc::UnboundedReceiver<ServerboundPlayPacket<'static>>,
+    packets: mpsc::UnboundedSender<ClientboundPlayPacket<'static>>,
+) -> anyhow::Result<()> {
+    let mut buf = BytesMut::new();
+
+    loop {
+        // packets that came along with the login success are already queued
+        while let Some(packet) = session.next_packet()? {
+            let ClientboundPacket::Play(packet) = packet else {
+                bail!("unexpected {:?} packet while playing", packet.state());
+            };
+
+            if let ClientboundPlayPacket::KeepAlive(keep_alive) = &packet {
+                session.send(serverbound::play::KeepAlivePacket { id: keep_alive.id })?;
+            }
+
+            let disconnected = matches!(packet, ClientboundPlayPacket::Disconnect(_));
+
+            // the packets are dropped if nobody is receiving them anymore
+            let _ = packets.send(packet);
+
+            if disconnected {
+                return Ok(());
+            }
+        }
+
+        capture.write(session);
+        if session.has_outbound() {
+            stream.write_all(&session.take_outbound()).await?;
+        }
+
+        buf.reserve(READ_BUF_SIZE);
+
+        tokio::select! {
+            read = stream.read_buf(&mut buf) => {
+                if read? == 0 {
+                    return Ok(());
+                }
+
+                session.receive_bytes(buf.split());
+            }
+            packet = outbound.recv() => match packet {
+                Some(packet) => session.send_any(ServerboundPacket::Play(packet))?,
+                None => return Ok(()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::File, time::Duration};
+
+    use bytes::Bytes;
+    use protocol::{
+        capture::CaptureReader,
+        chat::ChatComponent,
+        framed::{PacketReader, PacketWriter},
+        packet::{
+            clientbound::{self, ClientboundPlayPacket},
+            serverbound::{
+                ServerboundHandshakingPacket, ServerboundLoginPacket, ServerboundPlayPacket,
+            },
+        },
+        varint::VarInt,
+    };
+    use rsa::{pkcs8::EncodePublicKey, Pkcs1v15Encrypt, RsaPrivateKey};
+    use tokio::{net::TcpListener, time::timeout};
+
+    use super::{split_address, Client, ClientOptions};
+
+    /// A server that logs the client in with compression and encryption, and plays along a bit.
+    async fn serve(listener: TcpListener) -> anyhow::Result<()> {
+        let (stream, _) = listener.accept().await?;
+        let (reader, writer) = stream.into_split();
+        let (mut reader, mut writer) = (PacketReader::new(reader), PacketWriter::new(writer));
+
+        reader.read_packet::<ServerboundHandshakingPacket>().await?;
+        let Some(ServerboundLoginPacket::LoginStart(start)) = reader.read_packet().await? else {
+            panic!("expected login start");
+        };
+
+        let key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024)?;
+        writer
+            .send(&clientbound::login::EncryptionRequestPacket {
+                server_id: String::new(),
+                public_key: Bytes::from(key.to_public_key().to_public_key_der()?.into_vec()),
+                verify_token: Bytes::from_static(&[1, 2, 3, 4]),
+            })
+            .await?;
+        let Some(ServerboundLoginPacket::EncryptionResponse(response)) =
+            reader.read_packet().await?
+        else {
+            panic!("expected encryption response");
+        };
+        assert_eq!(
+            [1, 2, 3, 4],
+            &key.decrypt(Pkcs1v15Encrypt, &response.verify_token)?[..]
+        );
+
+        let secret: [u8; 16] = key
+            .decrypt(Pkcs1v15Encrypt, &response.shared_secret)?
+            .try_into()
+            .unwrap();
+        reader.enable_encryption(&secret);
+        writer.enable_encryption(&secret);
+
+        writer
+            .send(&clientbound::login::SetCompressionPacket {
+                threshold: VarInt(64),
+            })
+            .await?;
+        reader.set_compression(Some(64));
+        writer.set_compression(Some(64));
+        writer
+            .send(&clientbound::login::LoginSuccessPacket {
+                uuid: "00000000-0000-0000-0000-000000000000".to_string(),
+                username: start.name,
+            })
+            .await?;
+
+        writer
+            .send(&clientbound::play::KeepAlivePacket { id: VarInt(7) })
+            .await?;
+        let Some(ServerboundPlayPacket::KeepAlive(keep_alive)) = reader.read_packet().await? else {
+            panic!("expected keep alive");
+        };
+        assert_eq!(VarInt(7), keep_alive.id);
+
+        let Some(ServerboundPlayPacket::ChatMessage(chat)) = reader.read_packet().await? else {
+            panic!("expected chat message");
+        };
+        writer
+            .send(&clientbound::play::DisconnectPacket {
+                reason: ChatComponent::builder().text(chat.message).build(),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn next(client: &mut Client) -> Option<ClientboundPlayPacket<'static>> {
+        timeout(Duration::from_secs(5), client.next_packet())
+            .await
+            .expect("timed out")
+    }
+
+    #[tokio::test]
+    pub async fn login_and_play() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(serve(listener));
+
+        let capture =
+            std::env::temp_dir().join(format!("jam-client-{}.jamcap", std::process::id()));
+        let options = ClientOptions::builder()
+            .username("jam".to_string())
+            .capture(capture.clone())
+            .build();
+        let mut client = Client::connect(&address, options).await.unwrap();
+        assert_eq!("jam", client.username());
+
+        // the keep alive is answered, and passed on as well
+        assert!(matches!(
+            next(&mut client).await,
+            Some(ClientboundPlayPacket::KeepAlive(_))
+        ));
+
+        client
+            .send(protocol::packet::serverbound::play::ChatMessagePacket {
+                message: "bye".to_string(),
+            })
+            .unwrap();
+
+        assert!(matches!(
+            next(&mut client).await,
+            Some(ClientboundPlayPacket::Disconnect(_))
+        ));
+        assert!(next(&mut client).await.is_none());
+
+        client.closed().await.unwrap();
+        server.await.unwrap().unwrap();
+
+        // the capture is written by the time the client is closed
+        let reader = CaptureReader::new(File::open(&capture).unwrap()).unwrap();
+        let names = reader
+            .map(|record| record.unwrap().decode().unwrap().name())
+            .collect::<Vec<_>>();
+        assert_eq!(Some(&"Handshake"), names.first());
+        assert_eq!(Some(&"Disconnect"), names.last());
+        std::fs::remove_file(&capture).unwrap();
+    }
+
+    #[test]
+    pub fn addresses() {
+        let split = |address| split_address(address).unwrap();
+
+        assert_eq!(("localhost".to_string(), 25565), split("localhost"));
+        assert_eq!(("localhost".to_string(), 25566), split("localhost:25566"));
+        assert_eq!(("127.0.0.1".to_string(), 25566), split("127.0.0.1:25566"));
+        assert_eq!(("::1".to_string(), 25566), split("[::1]:25566"));
+        assert_eq!(("::1".to_string(), 25565), split("[::1]"));
+        assert_eq!(("::1".to_string(), 25565), split("::1"));
+        assert!(split_address("localhost:port").is_err());
+    }
+}
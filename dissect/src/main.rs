@@ -0,0 +1,143 @@
+//! Decodes a single frame from a hex or base64 dump, field by field, showing where every field
+//! lies and where decoding went wrong.
+
+use std::{
+    io::{self, Read},
+    process,
+};
+
+use anyhow::{bail, Context};
+use base64::Engine;
+use clap::{Parser, ValueEnum};
+use protocol::{packet::PacketDirection, state::State, version::ProtocolVersion};
+
+use crate::render::render;
+
+pub mod render;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DirectionArg {
+    Serverbound,
+    Clientbound,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StateArg {
+    Handshaking,
+    Status,
+    Login,
+    Play,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum VersionArg {
+    #[value(name = "1.7.10")]
+    V1_7_10,
+    #[value(name = "1.8")]
+    V1_8,
+}
+
+#[derive(Parser)]
+#[command(
+    name = "jam-dissect",
+    about = "Decodes a packet from a hex or base64 dump, field by field"
+)]
+struct Args {
+    /// The frame: the packet ID and body, optionally preceded by the length. Read from stdin if
+    /// left out.
+    input: Option<String>,
+    #[arg(short, long)]
+    state: StateArg,
+    #[arg(short, long)]
+    direction: DirectionArg,
+    #[arg(short, long, default_value = "1.8")]
+    version: VersionArg,
+    /// Reads the input as base64, instead of hex.
+    #[arg(long)]
+    base64: bool,
+    /// Never treats the first VarInt as the frame's length.
+    #[arg(long)]
+    no_length: bool,
+}
+
+/// Reads hex, ignoring whitespace, `0x` prefixes and separators between bytes.
+fn parse_hex(input: &str) -> anyhow::Result<Vec<u8>> {
+    let digits = input
+        .split(|c: char| c.is_whitespace() || c == ':' || c == ',')
+        .map(|part| part.trim_start_matches("0x"))
+        .collect::<String>();
+
+    if !digits.is_ascii() {
+        bail!("invalid hex");
+    }
+    if digits.len() % 2 != 0 {
+        bail!("odd number of hex digits");
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).context("invalid hex"))
+        .collect()
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let input = match args.input {
+        Some(input) => input,
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
+
+    let bytes = if args.base64 {
+        let input = input.split_whitespace().collect::<String>();
+        base64::engine::general_purpose::STANDARD
+            .decode(input)
+            .context("invalid base64")?
+    } else {
+        parse_hex(&input)?
+    };
+
+    let state = match args.state {
+        StateArg::Handshaking => State::Handshaking,
+        StateArg::Status => State::Status,
+        StateArg::Login => State::Login,
+        StateArg::Play => State::Play,
+    };
+    let direction = match args.direction {
+        DirectionArg::Serverbound => PacketDirection::Serverbound,
+        DirectionArg::Clientbound => PacketDirection::Clientbound,
+    };
+    let version = match args.version {
+        VersionArg::V1_7_10 => ProtocolVersion::V1_7_10,
+        VersionArg::V1_8 => ProtocolVersion::V1_8,
+    };
+
+    let rendered = render(&bytes, !args.no_length, version, state, direction)?;
+    print!("{}", rendered.text);
+
+    if !rendered.complete {
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_hex;
+
+    #[test]
+    pub fn hex() {
+        assert_eq!(vec![0x04, 0x01, 0xab], parse_hex("04 01 AB\n").unwrap());
+        assert_eq!(vec![0x04, 0x01, 0xab], parse_hex("0x04,0x01,0xab").unwrap());
+        assert_eq!(vec![0x04, 0x01, 0xab], parse_hex("04:01:ab").unwrap());
+        assert_eq!(vec![0x04, 0x01], parse_hex("0401").unwrap());
+        assert!(parse_hex("040").is_err());
+        assert!(parse_hex("zz").is_err());
+        assert!(parse_hex("é0").is_err());
+    }
+}
@@ -0,0 +1,223 @@
+//! Lays out a dissected frame, one line per field.
+
+use std::fmt::Write;
+
+use protocol::{
+    dissect::Dissection,
+    packet::{
+        clientbound::ClientboundPacket, serverbound::ServerboundPacket, AnyStatePacket,
+        PacketDirection,
+    },
+    state::State,
+    varint::VarInt,
+    version::ProtocolVersion,
+};
+
+/// How many bytes of a field are shown before they're cut off.
+const MAX_BYTES: usize = 12;
+
+/// The outcome of dissecting a frame.
+pub struct Rendered {
+    pub text: String,
+    /// Whether the whole frame decoded, without anything left over.
+    pub complete: bool,
+}
+
+/// Bytes in hex, cut off after [MAX_BYTES].
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = bytes
+        .iter()
+        .take(MAX_BYTES)
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if bytes.len() > MAX_BYTES {
+        hex.push_str(" ..");
+    }
+
+    hex
+}
+
+/// One line of the table: the offset, the bytes, and what they are.
+fn line(text: &mut String, offset: usize, bytes: &[u8], label: &str) {
+    let _ = writeln!(
+        text,
+        "{:04x}  {:<width$}  {}",
+        offset,
+        hex(bytes),
+        label,
+        width = MAX_BYTES * 3 + 2
+    );
+}
+
+/// Dissects `input`, which is a packet ID and body. With `detect_length`, a length prefix in front
+/// is stripped if it matches the length of the rest.
+pub fn render(
+    input: &[u8],
+    detect_length: bool,
+    version: ProtocolVersion,
+    state: State,
+    direction: PacketDirection,
+) -> anyhow::Result<Rendered> {
+    let mut text = String::new();
+    let mut offset = 0;
+
+    if let Some((length, len)) = VarInt::decode_partial(input)?.filter(|_| detect_length) {
+        if length.0 >= 0 && length.0 as usize == input.len() - len {
+            line(
+                &mut text,
+                0,
+                &input[..len],
+                &format!("length = {}", length.0),
+            );
+            offset = len;
+        }
+    }
+
+    let Some((id, id_len)) = VarInt::decode_partial(&input[offset..])? else {
+        anyhow::bail!("the input ends within the packet ID");
+    };
+    let body_offset = offset + id_len;
+    let body = &input[body_offset..];
+
+    let dissected = match direction {
+        PacketDirection::Serverbound => {
+            ServerboundPacket::dissect_by_state(version, state, id.0, body)
+        }
+        PacketDirection::Clientbound => {
+            ClientboundPacket::dissect_by_state(version, state, id.0, body)
+        }
+    };
+
+    let Some((name, dissection)) = dissected else {
+        line(
+            &mut text,
+            offset,
+            &input[offset..body_offset],
+            &format!("packet id = {:#04x} (unknown)", id.0),
+        );
+        line(&mut text, body_offset, body, "body");
+        let _ = writeln!(
+            text,
+            "\nno {:?} {:?} packet has the id {:#04x} in {}",
+            direction,
+            state,
+            id.0,
+            version.name()
+        );
+
+        return Ok(Rendered {
+            text,
+            complete: false,
+        });
+    };
+
+    line(
+        &mut text,
+        offset,
+        &input[offset..body_offset],
+        &format!("packet id = {:#04x} ({})", id.0, name),
+    );
+    fields(&mut text, body_offset, body, &dissection);
+
+    Ok(Rendered {
+        text,
+        complete: dissection.is_complete(),
+    })
+}
+
+fn fields(text: &mut String, body_offset: usize, body: &[u8], dissection: &Dissection) {
+    for field in &dissection.fields {
+        line(
+            text,
+            body_offset + field.offset,
+            &body[field.offset..field.offset + field.len],
+            &format!("{}: {} = {}", field.name, field.ty, field.value),
+        );
+    }
+
+    if let Some(error) = &dissection.error {
+        line(
+            text,
+            body_offset + error.offset,
+            &body[error.offset..],
+            &format!("{}: {} = ?", error.name, error.ty),
+        );
+        let _ = writeln!(
+            text,
+            "\ndecoding {} failed at {:#06x}: {}",
+            error.name,
+            body_offset + error.offset,
+            error.message
+        );
+    } else if dissection.trailing() > 0 {
+        line(
+            text,
+            body_offset + dissection.consumed,
+            &body[dissection.consumed..],
+            "(unconsumed)",
+        );
+        let _ = writeln!(
+            text,
+            "\n{} trailing bytes at {:#06x} weren't consumed",
+            dissection.trailing(),
+            body_offset + dissection.consumed
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use protocol::{packet::PacketDirection, state::State, version::ProtocolVersion};
+
+    use super::render;
+
+    #[test]
+    pub fn chat_message() {
+        let rendered = render(
+            &[0x04, 0x01, 0x02, b'h', b'i'],
+            true,
+            ProtocolVersion::V1_8,
+            State::Play,
+            PacketDirection::Serverbound,
+        )
+        .unwrap();
+
+        assert!(rendered.complete);
+        let lines = rendered.text.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("0000  04 ") && lines[0].ends_with("length = 4"));
+        assert!(lines[1].starts_with("0001  01 ") && lines[1].ends_with("(ChatMessage)"));
+        assert!(lines[2].starts_with("0002  02 68 69 "));
+        assert!(lines[2].ends_with("message: String = \"hi\""));
+    }
+
+    #[test]
+    pub fn failure_and_trailing() {
+        // the message claims 5 bytes, but has 2
+        let rendered = render(
+            &[0x01, 0x05, b'h', b'i'],
+            true,
+            ProtocolVersion::V1_8,
+            State::Play,
+            PacketDirection::Serverbound,
+        )
+        .unwrap();
+        assert!(!rendered.complete);
+        assert!(rendered.text.contains("decoding message failed at 0x0001"));
+
+        // a keep alive with a byte too many
+        let rendered = render(
+            &[0x00, 0x07, 0xff],
+            true,
+            ProtocolVersion::V1_8,
+            State::Play,
+            PacketDirection::Clientbound,
+        )
+        .unwrap();
+        assert!(!rendered.complete);
+        assert!(rendered
+            .text
+            .contains("1 trailing bytes at 0x0002 weren't consumed"));
+    }
+}
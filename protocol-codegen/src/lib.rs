@@ -0,0 +1,494 @@
+//! Generates the packet definitions of the `protocol` crate, from the minecraft-data protocol specs
+//! vendored in `data/`.
+//!
+//! Every state and direction gets a file in `protocol/src/packet`, with a struct per packet and the
+//! `PacketSet` enum of the state. The names, docs and type overrides that minecraft-data doesn't
+//! have live in `data/packets.toml`. Fields whose type can't be expressed yet (switches,
+//! containers, slots, NBT and entity metadata) are left in a trailing `rest` field, along with
+//! everything after them.
+//!
+//! The files are formatted with rustfmt, which has to be installed. Run
+//! `cargo run -p protocol-codegen` after changing the data.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs,
+    io::Write as _,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context};
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use serde_json::Value;
+
+use crate::{
+    meta::{FieldMeta, Meta, PacketMeta},
+    spec::{Spec, SpecPacket},
+};
+
+pub mod meta;
+pub mod spec;
+
+/// The version the packets are laid out for.
+pub const LATEST: &str = "1.8";
+
+/// The version packets are compared with, to find out which are new (or changed) in [LATEST].
+pub const OLDEST: &str = "1.7";
+
+const STATES: [&str; 4] = ["handshaking", "status", "login", "play"];
+const DIRECTIONS: [(&str, &str); 2] = [("toClient", "clientbound"), ("toServer", "serverbound")];
+
+pub struct GeneratedFile {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// Generates every packet file, without writing them.
+pub fn generate() -> anyhow::Result<Vec<GeneratedFile>> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let latest = Spec::load(&root.join("data").join(LATEST).join("protocol.json"))?;
+    let oldest = Spec::load(&root.join("data").join(OLDEST).join("protocol.json"))?;
+
+    let meta = fs::read_to_string(root.join("data/packets.toml"))?;
+    let meta: Meta = toml::from_str(&meta).context("parsing packets.toml")?;
+
+    let mut files = Vec::new();
+
+    for (direction, module) in DIRECTIONS {
+        for state in STATES {
+            let packets = latest.packets(state, direction)?;
+            let old_packets = oldest.packets(state, direction)?;
+
+            let packet_meta = meta.get(state).and_then(|meta| meta.get(direction));
+            check_meta(state, direction, &packets, packet_meta)?;
+
+            let packets = packets
+                .iter()
+                .filter_map(|packet| {
+                    let meta = packet_meta.and_then(|meta| meta.get(&packet.name));
+
+                    match meta {
+                        Some(meta) if meta.skip => None,
+                        _ => Some(Packet::new(packet, meta, &old_packets)),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            if packets.is_empty() {
+                continue;
+            }
+
+            files.push(GeneratedFile {
+                path: root
+                    .join("../protocol/src/packet")
+                    .join(module)
+                    .join(format!("{}.rs", state)),
+                contents: rustfmt(render(state, module, &packets)?)?,
+            });
+        }
+    }
+
+    Ok(files)
+}
+
+/// Makes sure every packet and field in `packets.toml` exists, so that typos and renames in the
+/// spec don't go unnoticed.
+fn check_meta(
+    state: &str,
+    direction: &str,
+    packets: &[SpecPacket],
+    meta: Option<&BTreeMap<String, PacketMeta>>,
+) -> anyhow::Result<()> {
+    for (name, packet_meta) in meta.into_iter().flatten() {
+        let packet = match packets.iter().find(|packet| &packet.name == name) {
+            Some(packet) => packet,
+            None => bail!(
+                "unknown packet {}.{}.{} in packets.toml",
+                state,
+                direction,
+                name
+            ),
+        };
+
+        for field in packet_meta.fields.keys() {
+            if !packet.fields.iter().any(|spec| &spec.name == field) {
+                bail!(
+                    "unknown field {}.{}.{}.{} in packets.toml",
+                    state,
+                    direction,
+                    name,
+                    field
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+struct Packet {
+    name: String,
+    doc: Option<String>,
+    id: i32,
+    versioned: bool,
+
+    /// Whether the packet is missing from (or laid out differently in) [OLDEST].
+    new: bool,
+
+    fields: Vec<Field>,
+
+    /// The fields that can't be typed, from the first one on, as `(name, type, doc)`.
+    rest: Vec<(String, String, Option<String>)>,
+}
+
+struct Field {
+    name: String,
+    doc: Option<String>,
+    ty: String,
+}
+
+impl Packet {
+    fn new(packet: &SpecPacket, meta: Option<&PacketMeta>, old_packets: &[SpecPacket]) -> Self {
+        let default = PacketMeta::default();
+        let meta = meta.unwrap_or(&default);
+
+        let mut fields = Vec::new();
+        let mut rest = Vec::new();
+
+        for field in &packet.fields {
+            let field_meta = meta.fields.get(&field.name);
+            let name = field_meta
+                .and_then(|meta| meta.name.clone())
+                .unwrap_or_else(|| field_name(&field.name));
+            let doc = field_meta.and_then(|meta| meta.doc.clone());
+
+            let ty = field_meta
+                .and_then(|meta: &FieldMeta| meta.ty.clone())
+                .or_else(|| rust_type(&field.ty));
+
+            match ty {
+                Some(ty) if rest.is_empty() => fields.push(Field { name, doc, ty }),
+                _ => rest.push((name, describe(&field.ty), doc)),
+            }
+        }
+
+        let new = !old_packets.iter().any(|old| {
+            old.name == packet.name && old.id == packet.id && old.fields == packet.fields
+        });
+
+        Self {
+            name: meta
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{}Packet", packet.name.to_upper_camel_case())),
+            doc: meta.doc.clone(),
+            id: packet.id,
+            versioned: meta.versioned,
+            new,
+            fields,
+            rest,
+        }
+    }
+
+    fn lifetime(&self) -> &'static str {
+        if self.fields.iter().any(|field| field.ty.contains("'a")) {
+            "<'a>"
+        } else {
+            ""
+        }
+    }
+
+    fn variant(&self) -> &str {
+        self.name.strip_suffix("Packet").unwrap_or(&self.name)
+    }
+}
+
+fn field_name(name: &str) -> String {
+    match name.to_snake_case().as_str() {
+        "type" => "type_".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// The Rust type of a field, or `None` if no type with a matching `Encode` implementation exists
+/// yet.
+fn rust_type(ty: &Value) -> Option<String> {
+    if let Some(ty) = ty.as_str() {
+        return Some(
+            match ty {
+                "varint" => "VarInt",
+                "string" => "String",
+                "bool" | "i8" | "u8" | "i16" | "u16" | "i32" | "i64" | "f32" | "f64" => ty,
+                "UUID" => "Uuid",
+                "position" => "Position",
+                "restBuffer" => "Remaining<Bytes>",
+                _ => return None,
+            }
+            .to_string(),
+        );
+    }
+
+    let (kind, options) = (ty[0].as_str()?, &ty[1]);
+    let counted_by_varint = options["countType"] == "varint";
+
+    match kind {
+        "buffer" if counted_by_varint => Some("Bytes".to_string()),
+        "array" if counted_by_varint => {
+            let element = rust_type(&options["type"])?;
+
+            (!element.contains("Remaining")).then(|| format!("Vec<{}>", element))
+        }
+        "option" => {
+            let element = rust_type(options)?;
+
+            (!element.contains("Remaining")).then(|| format!("Option<{}>", element))
+        }
+        _ => None,
+    }
+}
+
+/// A short description of a type that can't be generated, for docs.
+fn describe(ty: &Value) -> String {
+    match ty {
+        Value::String(ty) => ty.clone(),
+        ty => ty[0].as_str().unwrap_or("unknown").to_string(),
+    }
+}
+
+fn render(state: &str, module: &str, packets: &[Packet]) -> anyhow::Result<String> {
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "// Generated by protocol-codegen from minecraft-data's {} protocol. Don't edit this file;",
+        LATEST
+    )?;
+    writeln!(
+        out,
+        "// change protocol-codegen/data instead, and run `cargo run -p protocol-codegen`."
+    )?;
+    writeln!(out)?;
+
+    render_imports(&mut out, packets)?;
+
+    for packet in packets {
+        writeln!(out)?;
+        render_packet(&mut out, packet)?;
+    }
+
+    let set_lifetime = if packets.iter().any(|packet| !packet.lifetime().is_empty()) {
+        "<'a>"
+    } else {
+        ""
+    };
+    let direction = module.to_upper_camel_case();
+    let state = state.to_upper_camel_case();
+
+    writeln!(out)?;
+    writeln!(out, "packet_set! {{")?;
+    writeln!(
+        out,
+        "    /// Every {} packet in the {} state.",
+        module,
+        state.to_lowercase()
+    )?;
+    writeln!(
+        out,
+        "    {}{}Packet{} ({}, {}) {{",
+        direction, state, set_lifetime, state, direction
+    )?;
+    for packet in packets {
+        writeln!(
+            out,
+            "        {}({}{}),",
+            packet.variant(),
+            packet.name,
+            packet.lifetime()
+        )?;
+    }
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+
+    Ok(out)
+}
+
+/// Writes the `use` declarations, leaving the wrapping to [rustfmt].
+fn render_imports(out: &mut String, packets: &[Packet]) -> anyhow::Result<()> {
+    let types = packets
+        .iter()
+        .flat_map(|packet| {
+            packet
+                .fields
+                .iter()
+                .map(|field| field.ty.as_str())
+                .chain((!packet.rest.is_empty()).then_some("Remaining<Bytes>"))
+        })
+        .collect::<Vec<_>>();
+    let uses = |name: &str| {
+        types.iter().any(|ty| {
+            ty.split(|c: char| !c.is_alphanumeric() && c != '_')
+                .any(|part| part == name)
+        })
+    };
+
+    let mut external = BTreeSet::new();
+    let mut internal = BTreeSet::from(["packet::packet_set"]);
+
+    external.insert("protocol_macro::PacketDef");
+    external.insert("serde::Serialize");
+    if uses("Bytes") {
+        external.insert("bytes::Bytes");
+    }
+    if uses("Uuid") {
+        external.insert("uuid::Uuid");
+    }
+    if uses("ChatComponent") {
+        internal.insert("chat::ChatComponent");
+    }
+    if uses("Remaining") {
+        internal.insert("encoding::byte_array::Remaining");
+    }
+    if uses("Angle") && uses("Position") {
+        internal.insert("position::{Angle, Position}");
+    } else if uses("Angle") {
+        internal.insert("position::Angle");
+    } else if uses("Position") {
+        internal.insert("position::Position");
+    }
+    if uses("VarInt") {
+        internal.insert("varint::VarInt");
+    }
+
+    for path in external {
+        writeln!(out, "use {};", path)?;
+    }
+    writeln!(out)?;
+
+    let internal = internal.into_iter().collect::<Vec<_>>();
+    if internal.len() == 1 {
+        writeln!(out, "use crate::{};", internal[0])?;
+    } else {
+        writeln!(out, "use crate::{{{}}};", internal.join(", "))?;
+    }
+
+    Ok(())
+}
+
+/// Formats a generated file the way `cargo fmt` would, so it stays formatted in the tree.
+fn rustfmt(source: String) -> anyhow::Result<String> {
+    let mut rustfmt = Command::new("rustfmt")
+        .args(["--edition", "2021", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("couldn't run rustfmt")?;
+
+    rustfmt
+        .stdin
+        .take()
+        .context("rustfmt has no stdin")?
+        .write_all(source.as_bytes())?;
+    let output = rustfmt.wait_with_output()?;
+
+    if !output.status.success() {
+        bail!("rustfmt failed on the generated code");
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+fn render_packet(out: &mut String, packet: &Packet) -> anyhow::Result<()> {
+    if let Some(doc) = &packet.doc {
+        render_doc(out, "", doc)?;
+    }
+
+    let mut options = format!("id = {:#04X}", packet.id).replace("0X", "0x");
+    if packet.versioned {
+        options.push_str(", versioned");
+    } else if packet.new {
+        write!(options, ", since = \"V{}\"", LATEST.replace('.', "_"))?;
+    }
+
+    writeln!(
+        out,
+        "#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]"
+    )?;
+    writeln!(out, "#[packet({})]", options)?;
+
+    if packet.fields.is_empty() && packet.rest.is_empty() {
+        writeln!(out, "pub struct {}{} {{}}", packet.name, packet.lifetime())?;
+        return Ok(());
+    }
+
+    writeln!(out, "pub struct {}{} {{", packet.name, packet.lifetime())?;
+
+    for (i, field) in packet.fields.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        if let Some(doc) = &field.doc {
+            render_doc(out, "    ", doc)?;
+        }
+        writeln!(out, "    pub {}: {},", field.name, field.ty)?;
+    }
+
+    if !packet.rest.is_empty() {
+        if !packet.fields.is_empty() {
+            writeln!(out)?;
+        }
+
+        let mut doc = String::from("The rest of the packet, which isn't typed yet:\n");
+        for (name, ty, field_doc) in &packet.rest {
+            write!(doc, "\n- `{}` ({})", name, ty)?;
+
+            if let Some(field_doc) = field_doc {
+                let mut lines = field_doc.lines();
+                write!(doc, ": {}", lines.next().unwrap_or_default())?;
+                for line in lines {
+                    write!(doc, "\n  {}", line)?;
+                }
+            }
+        }
+
+        render_doc(out, "    ", &doc)?;
+        writeln!(out, "    pub rest: Remaining<Bytes>,")?;
+    }
+
+    writeln!(out, "}}")?;
+
+    Ok(())
+}
+
+fn render_doc(out: &mut String, indent: &str, doc: &str) -> anyhow::Result<()> {
+    for line in doc.lines() {
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            writeln!(out, "{}///", indent)?;
+        } else {
+            writeln!(out, "{}/// {}", indent, line)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    #[test]
+    pub fn generated_files_are_up_to_date() {
+        for file in super::generate().unwrap() {
+            let contents = fs::read_to_string(&file.path).unwrap();
+
+            assert!(
+                contents == file.contents,
+                "{} is out of date, run `cargo run -p protocol-codegen`",
+                file.path.display()
+            );
+        }
+    }
+}
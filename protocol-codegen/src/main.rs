@@ -0,0 +1,10 @@
+use std::fs;
+
+fn main() -> anyhow::Result<()> {
+    for file in protocol_codegen::generate()? {
+        fs::write(&file.path, &file.contents)?;
+        println!("wrote {}", file.path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+//! Reading `packets.toml`, which names and documents the generated packets.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+/// Packets by state, direction and minecraft-data name.
+pub type Meta = BTreeMap<String, BTreeMap<String, BTreeMap<String, PacketMeta>>>;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PacketMeta {
+    /// The struct name, if it isn't the minecraft-data name in UpperCamelCase and `Packet`.
+    pub name: Option<String>,
+
+    pub doc: Option<String>,
+
+    /// Whether the `VersionedPacket` implementation is written by hand, in `protocol::version`.
+    #[serde(default)]
+    pub versioned: bool,
+
+    /// Whether to leave this packet out.
+    #[serde(default)]
+    pub skip: bool,
+
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldMeta>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldMeta {
+    /// The field name, if it isn't the minecraft-data name in snake_case.
+    pub name: Option<String>,
+
+    pub doc: Option<String>,
+
+    /// The Rust type, if it isn't the one derived from the spec, e.g. `ChatComponent<'a>` for
+    /// fields minecraft-data only knows as strings.
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+}
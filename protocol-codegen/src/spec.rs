@@ -0,0 +1,88 @@
+//! Reading minecraft-data's `protocol.json`.
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+
+/// The protocol spec of one version.
+pub struct Spec {
+    json: Value,
+}
+
+pub struct SpecPacket {
+    /// The name minecraft-data uses, e.g. `keep_alive`.
+    pub name: String,
+    pub id: i32,
+    pub fields: Vec<SpecField>,
+}
+
+#[derive(PartialEq)]
+pub struct SpecField {
+    pub name: String,
+
+    /// The type, in minecraft-data's notation (e.g. `"varint"`, or
+    /// `["buffer", { "countType": "varint" }]`.)
+    pub ty: Value,
+}
+
+impl Spec {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let json = fs::read_to_string(path).with_context(|| format!("reading {:?}", path))?;
+
+        Ok(Self {
+            json: serde_json::from_str(&json).with_context(|| format!("parsing {:?}", path))?,
+        })
+    }
+
+    /// Every packet in `state` (e.g. `play`) and `direction` (`toClient` or `toServer`), ordered
+    /// by ID.
+    pub fn packets(&self, state: &str, direction: &str) -> anyhow::Result<Vec<SpecPacket>> {
+        let types = &self.json[state][direction]["types"];
+
+        // `packet` is a container of a `name` mapped from the ID, and a `params` switch on it.
+        let mappings = match types["packet"][1][0]["type"][1]["mappings"].as_object() {
+            Some(mappings) => mappings,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut packets = mappings
+            .iter()
+            .map(|(id, name)| {
+                let name = name
+                    .as_str()
+                    .ok_or_else(|| anyhow!("invalid packet name for {}", id))?;
+                let id = i32::from_str_radix(id.trim_start_matches("0x"), 16)?;
+
+                Ok(SpecPacket {
+                    name: name.to_string(),
+                    id,
+                    fields: fields(&types[format!("packet_{}", name)][1])
+                        .with_context(|| format!("reading {}.{}.{}", state, direction, name))?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        packets.sort_by_key(|packet| packet.id);
+        Ok(packets)
+    }
+}
+
+fn fields(container: &Value) -> anyhow::Result<Vec<SpecField>> {
+    let fields = container
+        .as_array()
+        .ok_or_else(|| anyhow!("packet is not a container"))?;
+
+    fields
+        .iter()
+        .map(|field| {
+            Ok(SpecField {
+                name: field["name"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("anonymous field {}", field))?
+                    .to_string(),
+                ty: field["type"].clone(),
+            })
+        })
+        .collect()
+}
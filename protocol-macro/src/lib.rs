@@ -1,13 +1,73 @@
 use proc_macro::TokenStream;
 use proc_macro2::Ident;
 use quote::{quote, ToTokens};
-use syn::{parse_macro_input, Data, DeriveInput, Fields, Type};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, Lit, Meta, NestedMeta, Type};
 
-#[proc_macro_derive(PacketDef)]
+/// The options of a `#[packet(id = 0x00, ..)]` attribute.
+struct PacketOptions {
+    id: Lit,
+
+    /// The first protocol version the packet exists in, e.g. `since = "V1_8"`.
+    since: Option<Ident>,
+
+    /// `versioned` packets implement `VersionedPacket` by hand.
+    versioned: bool,
+}
+
+/// Reads the packet options out of a `#[packet(id = 0x00)]` attribute.
+fn packet_options(attrs: &[Attribute]) -> PacketOptions {
+    let mut id = None;
+    let mut since = None;
+    let mut versioned = false;
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("packet")) {
+        if let Ok(Meta::List(list)) = attr.parse_meta() {
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("id") => {
+                        id = Some(value.lit);
+                    }
+                    NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("since") => {
+                        match value.lit {
+                            Lit::Str(version) => since = Some(version.parse().unwrap()),
+                            _ => panic!("Expected a version, e.g. since = \"V1_8\""),
+                        }
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("versioned") => {
+                        versioned = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    PacketOptions {
+        id: id.expect("Packets need an ID, e.g. #[packet(id = 0x00)]"),
+        since,
+        versioned,
+    }
+}
+
+/// A field type as it reads in the source, e.g. `Option<String>`.
+fn type_name(field_type: &Type) -> String {
+    field_type
+        .to_token_stream()
+        .to_string()
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace("& '", "&'")
+}
+
+#[proc_macro_derive(PacketDef, attributes(packet))]
 pub fn define_packet(input: TokenStream) -> TokenStream {
     // Parse the input token stream and extract the struct name and fields
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
+    let options = packet_options(&input.attrs);
+    let id = options.id;
     let fields = match input.data {
         Data::Struct(data) => match data.fields {
             Fields::Named(fields) => fields
@@ -24,59 +84,108 @@ pub fn define_packet(input: TokenStream) -> TokenStream {
         _ => panic!("Unsupported data format for Packet derive macro"),
     };
 
+    // this is only for lifetimes!
+    let type_params = input.generics;
+
     let mut encode_expand = quote! {};
+    let mut encoded_len_expand = quote! {};
     let mut decode_expand = quote! {};
+    let mut arbitrary_expand = quote! {};
+    let mut dissect_expand = quote! {};
 
-    let mut segments_combined = String::from("");
+    for (field_name, field_type) in fields {
+        decode_expand.extend(quote! {
+            #field_name: <#field_type as crate::encoding::Decode>::decode(reader)?,
+        });
 
-    // this is only for lifetimes!
-    let type_params = input.generics;
+        encode_expand.extend(quote! {
+            <#field_type as crate::encoding::Encode>::encode(&self.#field_name, writer)?;
+        });
 
-    for (field_name, field_type) in fields {
-        if let Type::Path(path) = &field_type {
-            let segments = path.path.segments.to_token_stream().to_string();
-            segments_combined = segments_combined + &segments;
-
-            // this is for types that contain a generic type, e.g. Option<T>. this will then
-            // call the decode function through
-            // Option::<T>::decode(R);
-            if segments.contains("<") {
-                let types = segments.split("<").collect::<Vec<&str>>();
-
-                let enum_ty = types[0].parse::<proc_macro2::TokenStream>().unwrap();
-                let data_ty = types[1]
-                    .replace(">", "")
-                    .parse::<proc_macro2::TokenStream>()
-                    .unwrap();
-
-                decode_expand.extend(quote! {
-                    #field_name: <#enum_ty::<#data_ty> as crate::encoding::Encodable>::decode(reader)?,
-                });
-            } else {
-                decode_expand.extend(quote! {
-                    #field_name: <#field_type as crate::encoding::Encodable>::decode(reader)?,
-                });
-            }
-            encode_expand.extend(quote! {
-                <#field_type as crate::encoding::Encodable>::encode(&self.#field_name, writer)?;
+        encoded_len_expand.extend(quote! {
+            + <#field_type as crate::encoding::Encode>::encoded_len(&self.#field_name)
+        });
+
+        arbitrary_expand.extend(quote! {
+            #field_name: <#field_type as crate::arbitrary::Arbitrary>::arbitrary(rng),
+        });
+
+        let type_name = type_name(&field_type);
+
+        dissect_expand.extend(quote! {
+            dissector.field(stringify!(#field_name), #type_name, |reader| {
+                <#field_type as crate::encoding::Decode>::decode(reader)
             });
-        }
+        });
     }
 
-    // Generate the implementation of the encode and decode methods
-    let expanded = quote! {
-        impl #type_params crate::packet::Packet for #name #type_params {
-            fn decode(reader: &mut dyn bytes::Buf) -> anyhow::Result<Self> {
+    let decode_impl = quote! {
+        impl #type_params crate::encoding::Decode for #name #type_params {
+            fn decode<B: bytes::Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
                 Ok(Self {
                     #decode_expand
                 })
             }
+        }
+    };
+
+    // packets that are laid out the same in every version they exist in get the default
+    // `VersionedPacket` impl. the others implement it by hand.
+    let versioned_impl = match (options.versioned, options.since) {
+        (true, _) => quote! {},
+        (false, Some(since)) => quote! {
+            impl #type_params crate::version::VersionedPacket for #name #type_params {
+                fn id_for(version: crate::version::ProtocolVersion) -> Option<i32> {
+                    (version >= crate::version::ProtocolVersion::#since).then_some(#id)
+                }
+            }
+        },
+        (false, None) => quote! {
+            impl #type_params crate::version::VersionedPacket for #name #type_params {}
+        },
+    };
+
+    let arbitrary_impl = quote! {
+        #[cfg(test)]
+        impl #type_params crate::arbitrary::Arbitrary for #name #type_params {
+            fn arbitrary(rng: &mut crate::arbitrary::Rng) -> Self {
+                Self {
+                    #arbitrary_expand
+                }
+            }
+        }
+    };
 
-            fn encode(&self, writer: &mut dyn bytes::BufMut) -> anyhow::Result<()> {
+    // Generate the implementation of the encode and decode methods
+    let expanded = quote! {
+        #versioned_impl
+
+        impl #type_params crate::packet::Packet for #name #type_params {
+            const ID: i32 = #id;
+        }
+
+        #decode_impl
+
+        impl #type_params crate::encoding::Encode for #name #type_params {
+            fn encode<B: bytes::BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
                 #encode_expand
                 Ok(())
             }
+
+            fn encoded_len(&self) -> usize {
+                0 #encoded_len_expand
+            }
+        }
+
+        impl #type_params crate::dissect::Dissect for #name #type_params {
+            fn dissect(body: &[u8]) -> crate::dissect::Dissection {
+                let mut dissector = crate::dissect::Dissector::new(body);
+                #dissect_expand
+                dissector.finish()
+            }
         }
+
+        #arbitrary_impl
     };
 
     expanded.into()
@@ -0,0 +1,227 @@
+//! Compares the `Buf`/`BufMut` based encoding against dynamic dispatch and against the old
+//! `std::io` adapter approach.
+//!
+//! Run with `cargo bench -p protocol`.
+
+use std::io::{Read, Write};
+
+use bytes::{Buf, BufMut, BytesMut};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use protocol::{
+    encoding::{Decode, Encode},
+    packet::serverbound::play::PlayerPositionAndLookPacket,
+    varint::VarInt,
+};
+
+const VARINTS: [i32; 6] = [0, 127, 128, 25565, 2147483647, -1];
+
+/// How VarInts used to be decoded, kept here as a reference point.
+fn legacy_decode(reader: &mut dyn Buf) -> anyhow::Result<VarInt> {
+    let mut result = 0;
+    let mut shift = 0;
+
+    loop {
+        let mut byte = [0];
+        reader.reader().read_exact(&mut byte)?;
+
+        result |= ((byte[0] & 0b01111111) as i32) << shift;
+        shift += 7;
+
+        if byte[0] & 0b10000000 == 0 {
+            break;
+        }
+    }
+
+    Ok(VarInt(result))
+}
+
+/// How VarInts used to be encoded, kept here as a reference point.
+fn legacy_encode(value: VarInt, writer: &mut dyn BufMut) -> anyhow::Result<()> {
+    let mut remaining = value.0 as u32;
+    while remaining >= 0b10000000 {
+        writer
+            .writer()
+            .write_all(&[(remaining as u8) | 0b10000000])?;
+        remaining >>= 7;
+    }
+
+    writer.writer().write_all(&[remaining as u8])?;
+    Ok(())
+}
+
+/// How the movement packet used to be encoded, field by field through the `std::io` adapters.
+fn legacy_encode_movement(
+    packet: &PlayerPositionAndLookPacket,
+    writer: &mut dyn BufMut,
+) -> anyhow::Result<()> {
+    writer.writer().write_all(&packet.x.to_be_bytes())?;
+    writer.writer().write_all(&packet.y.to_be_bytes())?;
+    writer.writer().write_all(&packet.z.to_be_bytes())?;
+    writer.writer().write_all(&packet.yaw.to_be_bytes())?;
+    writer.writer().write_all(&packet.pitch.to_be_bytes())?;
+    writer.writer().write_all(&[packet.on_ground as u8])?;
+    Ok(())
+}
+
+/// How the movement packet used to be decoded, kept here as a reference point.
+fn legacy_decode_movement(reader: &mut dyn Buf) -> anyhow::Result<PlayerPositionAndLookPacket> {
+    fn read<const N: usize>(reader: &mut dyn Buf) -> anyhow::Result<[u8; N]> {
+        let mut bytes = [0; N];
+        reader.reader().read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    Ok(PlayerPositionAndLookPacket {
+        x: f64::from_be_bytes(read(reader)?),
+        y: f64::from_be_bytes(read(reader)?),
+        z: f64::from_be_bytes(read(reader)?),
+        yaw: f32::from_be_bytes(read(reader)?),
+        pitch: f32::from_be_bytes(read(reader)?),
+        on_ground: read::<1>(reader)?[0] != 0,
+    })
+}
+
+fn encoded_varints() -> Vec<u8> {
+    let mut buf = BytesMut::new();
+    for value in VARINTS {
+        VarInt(value).encode(&mut buf).unwrap();
+    }
+    buf.to_vec()
+}
+
+fn varint(c: &mut Criterion) {
+    let encoded = encoded_varints();
+
+    let mut group = c.benchmark_group("varint");
+    group.throughput(Throughput::Elements(VARINTS.len() as u64));
+
+    group.bench_function(BenchmarkId::new("decode", "generic"), |b| {
+        b.iter(|| {
+            let mut reader = encoded.as_slice();
+            for _ in VARINTS {
+                black_box(VarInt::decode(&mut reader).unwrap());
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("decode", "dyn"), |b| {
+        b.iter(|| {
+            let mut slice = encoded.as_slice();
+            let reader: &mut dyn Buf = &mut slice;
+            for _ in VARINTS {
+                black_box(VarInt::decode(reader).unwrap());
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("decode", "legacy"), |b| {
+        b.iter(|| {
+            let mut reader = encoded.as_slice();
+            for _ in VARINTS {
+                black_box(legacy_decode(&mut reader).unwrap());
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("encode", "generic"), |b| {
+        let mut buf = BytesMut::with_capacity(64);
+        b.iter(|| {
+            buf.clear();
+            for value in VARINTS {
+                VarInt(value).encode(&mut buf).unwrap();
+            }
+            black_box(&buf);
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("encode", "dyn"), |b| {
+        let mut buf = BytesMut::with_capacity(64);
+        b.iter(|| {
+            buf.clear();
+            let writer: &mut dyn BufMut = &mut buf;
+            for value in VARINTS {
+                VarInt(value).encode(writer).unwrap();
+            }
+            black_box(&buf);
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("encode", "legacy"), |b| {
+        let mut buf = BytesMut::with_capacity(64);
+        b.iter(|| {
+            buf.clear();
+            for value in VARINTS {
+                legacy_encode(VarInt(value), &mut buf).unwrap();
+            }
+            black_box(&buf);
+        })
+    });
+
+    group.finish();
+}
+
+fn movement(c: &mut Criterion) {
+    const PACKETS: usize = 1000;
+
+    let packet = PlayerPositionAndLookPacket {
+        x: 128.5,
+        y: 64.0,
+        z: -312.25,
+        yaw: 90.0,
+        pitch: 12.5,
+        on_ground: true,
+    };
+
+    let mut encoded = BytesMut::new();
+    for _ in 0..PACKETS {
+        packet.encode(&mut encoded).unwrap();
+    }
+
+    let mut group = c.benchmark_group("movement");
+    group.throughput(Throughput::Bytes(encoded.len() as u64));
+
+    group.bench_function(BenchmarkId::new("encode", "generic"), |b| {
+        let mut buf = BytesMut::with_capacity(encoded.len());
+        b.iter(|| {
+            buf.clear();
+            for _ in 0..PACKETS {
+                packet.encode(&mut buf).unwrap();
+            }
+            black_box(&buf);
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("encode", "legacy"), |b| {
+        let mut buf = BytesMut::with_capacity(encoded.len());
+        b.iter(|| {
+            buf.clear();
+            for _ in 0..PACKETS {
+                legacy_encode_movement(&packet, &mut buf).unwrap();
+            }
+            black_box(&buf);
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("decode", "generic"), |b| {
+        b.iter(|| {
+            let mut reader = &encoded[..];
+            for _ in 0..PACKETS {
+                black_box(PlayerPositionAndLookPacket::decode(&mut reader).unwrap());
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("decode", "legacy"), |b| {
+        b.iter(|| {
+            let mut reader = &encoded[..];
+            for _ in 0..PACKETS {
+                black_box(legacy_decode_movement(&mut reader).unwrap());
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, varint, movement);
+criterion_main!(benches);
@@ -0,0 +1,133 @@
+//! Random values for property tests.
+//!
+//! Every packet derived through `PacketDef` implements [Arbitrary], so every packet can be
+//! round-tripped without writing a sample by hand. The generator is seeded, which keeps failures
+//! reproducible.
+
+use bytes::Bytes;
+use uuid::Uuid;
+
+pub use crate::rng::Rng;
+use crate::{
+    chat::ChatComponent,
+    encoding::byte_array::Remaining,
+    position::{Angle, Position},
+    varint::VarInt,
+};
+
+pub trait Arbitrary {
+    fn arbitrary(rng: &mut Rng) -> Self;
+}
+
+macro_rules! gen_int_arbitrary {
+    ($($ty:ty),*) => {
+        $(
+            impl Arbitrary for $ty {
+                fn arbitrary(rng: &mut Rng) -> Self {
+                    rng.next_u64() as $ty
+                }
+            }
+        )*
+    };
+}
+
+gen_int_arbitrary!(u8, i8, u16, i16, u32, i32, u64, i64);
+
+impl Arbitrary for u128 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        (rng.next_u64() as u128) << 64 | rng.next_u64() as u128
+    }
+}
+
+// NaN isn't equal to itself, so floats stay finite.
+impl Arbitrary for f32 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        (rng.next_u64() as i32) as f32 / 1024.0
+    }
+}
+
+impl Arbitrary for f64 {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        (rng.next_u64() as i64) as f64 / 1024.0
+    }
+}
+
+impl Arbitrary for bool {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        rng.below(2) == 1
+    }
+}
+
+impl Arbitrary for VarInt {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        VarInt(i32::arbitrary(rng))
+    }
+}
+
+impl Arbitrary for String {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        // mostly ASCII, with the odd multi-byte character
+        (0..rng.below(16))
+            .map(|_| match rng.below(8) {
+                0 => 'é',
+                1 => '§',
+                _ => (b'a' + rng.below(26) as u8) as char,
+            })
+            .collect()
+    }
+}
+
+impl Arbitrary for Bytes {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        (0..rng.below(16)).map(|_| rng.next_u64() as u8).collect()
+    }
+}
+
+impl Arbitrary for Remaining<Bytes> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        Remaining(Bytes::arbitrary(rng))
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Option<T> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        bool::arbitrary(rng).then(|| T::arbitrary(rng))
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Vec<T> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        (0..rng.below(4)).map(|_| T::arbitrary(rng)).collect()
+    }
+}
+
+impl Arbitrary for Uuid {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        Uuid::from_u128(u128::arbitrary(rng))
+    }
+}
+
+impl Arbitrary for Position {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        // x and z take 26 bits, and y takes 12
+        Position {
+            x: (rng.next_u64() as i32) >> 6,
+            y: (rng.next_u64() as i16) >> 4,
+            z: (rng.next_u64() as i32) >> 6,
+        }
+    }
+}
+
+impl Arbitrary for Angle {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        Angle(u8::arbitrary(rng))
+    }
+}
+
+impl<'a> Arbitrary for ChatComponent<'a> {
+    fn arbitrary(rng: &mut Rng) -> Self {
+        ChatComponent::builder()
+            .text(String::arbitrary(rng))
+            .build()
+    }
+}
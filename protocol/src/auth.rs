@@ -0,0 +1,93 @@
+//! The identities players log in with.
+//!
+//! Offline mode servers make up a UUID from the player's name, the same way vanilla does. Online
+//! mode servers ask the session server instead, which the client joined with the hash from
+//! [server_hash].
+
+use md5::Md5;
+use sha1::{Digest, Sha1};
+use uuid::Uuid;
+
+/// The UUID vanilla gives a player in offline mode: a version 3 UUID of `OfflinePlayer:<name>`.
+///
+/// Unlike [Uuid::new_v3], Java's `UUID.nameUUIDFromBytes` doesn't hash a namespace in.
+pub fn offline_uuid(username: &str) -> Uuid {
+    let mut bytes: [u8; 16] = Md5::new()
+        .chain_update(b"OfflinePlayer:")
+        .chain_update(username.as_bytes())
+        .finalize()
+        .into();
+
+    bytes[6] = (bytes[6] & 0x0f) | 0x30;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    Uuid::from_bytes(bytes)
+}
+
+/// The hash clients and servers join and check sessions with.
+///
+/// This is a SHA-1 digest, printed as a signed number in hex (like Java's `BigInteger`) instead of
+/// the usual unsigned hex digest.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut digest: [u8; 20] = Sha1::new()
+        .chain_update(server_id.as_bytes())
+        .chain_update(shared_secret)
+        .chain_update(public_key)
+        .finalize()
+        .into();
+
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        // two's complement
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            (*byte, carry) = (!*byte).overflowing_add(carry as u8);
+        }
+    }
+
+    let hex = digest
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    let hex = hex.trim_start_matches('0');
+
+    if negative {
+        format!("-{}", hex)
+    } else {
+        hex.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{offline_uuid, server_hash};
+
+    #[test]
+    pub fn offline() {
+        assert_eq!(
+            "b50ad385-829d-3141-a216-7e7d7539ba7f",
+            offline_uuid("Notch").hyphenated().to_string()
+        );
+        assert_ne!(offline_uuid("Notch"), offline_uuid("notch"));
+    }
+
+    /// The examples from wiki.vg, which hash the name alone.
+    #[test]
+    pub fn minecraft_hex_digest() {
+        assert_eq!(
+            "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48",
+            server_hash("Notch", &[], &[])
+        );
+        assert_eq!(
+            "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1",
+            server_hash("jeb_", &[], &[])
+        );
+        assert_eq!(
+            "88e16a1019277b15d58faf0541e11910eb756f6",
+            server_hash("simon", &[], &[])
+        );
+
+        // the parts are hashed as if they were one
+        assert_eq!(server_hash("jeb_", &[], &[]), server_hash("je", b"b", b"_"));
+    }
+}
@@ -0,0 +1,406 @@
+//! A file format for recording connections, packet by packet.
+//!
+//! A capture starts with the magic bytes `JAMCAP` and a format version, followed by one record per
+//! frame. Every record has a fixed-size header: the time it was sent or received (milliseconds
+//! since the Unix epoch, as a `u64`), the direction and state (a byte each), the protocol number
+//! (an `i32`) and the frame's length (a `u32`). The frame follows, as the packet ID (a VarInt) and
+//! the body. Frames are stored decompressed and decrypted, so a capture can be decoded without
+//! following the login sequence.
+
+use std::{
+    io::{Read, Write},
+    sync::mpsc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::{
+    codec::{Frame, MAX_DATA_LEN},
+    encoding::{Decode, Encode},
+    packet::{
+        clientbound::ClientboundPacket, serverbound::ServerboundPacket, AnyStatePacket,
+        PacketDirection,
+    },
+    state::State,
+    varint::VarInt,
+    version::ProtocolVersion,
+};
+
+const MAGIC: &[u8; 6] = b"JAMCAP";
+
+const FORMAT_VERSION: u8 = 1;
+
+/// The size of a record's header, before the frame.
+const HEADER_LEN: usize = 8 + 1 + 1 + 4 + 4;
+
+/// One frame of a capture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureRecord {
+    /// Milliseconds since the Unix epoch.
+    pub time: u64,
+    pub direction: PacketDirection,
+    /// The state the frame was sent in.
+    pub state: State,
+    /// The protocol number of the connection. Before the handshake, the latest version's.
+    pub protocol: i32,
+    pub frame: Frame,
+}
+
+/// A packet decoded from a [CaptureRecord].
+#[derive(Debug, Clone, PartialEq)]
+pub enum CapturedPacket {
+    Serverbound(ServerboundPacket<'static>),
+    Clientbound(ClientboundPacket<'static>),
+}
+
+impl CapturedPacket {
+    /// The name of the packet, e.g. `ChatMessage`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CapturedPacket::Serverbound(packet) => packet.name(),
+            CapturedPacket::Clientbound(packet) => packet.name(),
+        }
+    }
+}
+
+impl CaptureRecord {
+    /// Records a frame sent or received now.
+    pub fn now(direction: PacketDirection, state: State, protocol: i32, frame: Frame) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_millis() as u64)
+            .unwrap_or_default();
+
+        Self {
+            time,
+            direction,
+            state,
+            protocol,
+            frame,
+        }
+    }
+
+    /// Decodes the frame into the packet it holds.
+    pub fn decode(&self) -> anyhow::Result<CapturedPacket> {
+        let version = ProtocolVersion::from_protocol(self.protocol).unwrap_or_default();
+        let mut body = self.frame.body.clone();
+
+        Ok(match self.direction {
+            PacketDirection::Serverbound => CapturedPacket::Serverbound(
+                ServerboundPacket::decode_by_state(version, self.state, self.frame.id, &mut body)?,
+            ),
+            PacketDirection::Clientbound => CapturedPacket::Clientbound(
+                ClientboundPacket::decode_by_state(version, self.state, self.frame.id, &mut body)?,
+            ),
+        })
+    }
+
+    /// The size of the packet ID and body.
+    pub fn frame_len(&self) -> usize {
+        VarInt(self.frame.id).encoded_len() + self.frame.body.len()
+    }
+}
+
+fn state_to_byte(state: State) -> u8 {
+    match state {
+        State::Handshaking => 0,
+        State::Status => 1,
+        State::Login => 2,
+        State::Play => 3,
+        State::Closed => 4,
+    }
+}
+
+fn state_from_byte(byte: u8) -> anyhow::Result<State> {
+    Ok(match byte {
+        0 => State::Handshaking,
+        1 => State::Status,
+        2 => State::Login,
+        3 => State::Play,
+        4 => State::Closed,
+        _ => bail!("invalid state {} in capture", byte),
+    })
+}
+
+/// Writes records to a capture.
+pub struct CaptureWriter<W: Write> {
+    writer: W,
+    buf: BytesMut,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Starts a capture, writing the header right away.
+    pub fn new(mut writer: W) -> anyhow::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+
+        Ok(Self {
+            writer,
+            buf: BytesMut::new(),
+        })
+    }
+
+    pub fn write(&mut self, record: &CaptureRecord) -> anyhow::Result<()> {
+        self.buf.clear();
+
+        self.buf.put_u64(record.time);
+        self.buf.put_u8(match record.direction {
+            PacketDirection::Serverbound => 0,
+            PacketDirection::Clientbound => 1,
+        });
+        self.buf.put_u8(state_to_byte(record.state));
+        self.buf.put_i32(record.protocol);
+        self.buf.put_u32(record.frame_len() as u32);
+        VarInt(record.frame.id).encode(&mut self.buf)?;
+        self.buf.put_slice(&record.frame.body);
+
+        self.writer.write_all(&self.buf)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the records sent through `records` until every sender is gone, flushing whenever it
+    /// has caught up with them.
+    ///
+    /// This blocks, so it's meant to run on a thread of its own, where a slow disk doesn't hold up
+    /// the connection being captured.
+    pub fn write_from(&mut self, records: mpsc::Receiver<CaptureRecord>) -> anyhow::Result<()> {
+        while let Ok(record) = records.recv() {
+            self.write(&record)?;
+            for record in records.try_iter() {
+                self.write(&record)?;
+            }
+
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads the records of a capture, in order.
+pub struct CaptureReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Opens a capture, checking its header.
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let mut header = [0; MAGIC.len() + 1];
+        reader
+            .read_exact(&mut header)
+            .context("not a capture: too short")?;
+
+        if &header[..MAGIC.len()] != MAGIC {
+            bail!("not a capture: invalid magic");
+        }
+        if header[MAGIC.len()] != FORMAT_VERSION {
+            bail!("unsupported capture format {}", header[MAGIC.len()]);
+        }
+
+        Ok(Self { reader })
+    }
+
+    /// Reads the next record, or `None` at the end of the capture.
+    pub fn next_record(&mut self) -> anyhow::Result<Option<CaptureRecord>> {
+        let mut header = [0; HEADER_LEN];
+
+        // a capture may end anywhere between records, but not within one
+        let read = read_fully(&mut self.reader, &mut header)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if read < HEADER_LEN {
+            bail!("capture ends within a record");
+        }
+
+        let mut header = &header[..];
+        let time = header.get_u64();
+        let direction = match header.get_u8() {
+            0 => PacketDirection::Serverbound,
+            1 => PacketDirection::Clientbound,
+            direction => bail!("invalid direction {} in capture", direction),
+        };
+        let state = state_from_byte(header.get_u8())?;
+        let protocol = header.get_i32();
+        let len = header.get_u32() as usize;
+
+        if len > MAX_DATA_LEN {
+            bail!("frame of {} bytes in capture is too long", len);
+        }
+
+        let mut frame = vec![0; len];
+        self.reader
+            .read_exact(&mut frame)
+            .context("capture ends within a record")?;
+
+        let mut frame = Bytes::from(frame);
+        let id = VarInt::decode(&mut frame)?.0;
+
+        Ok(Some(CaptureRecord {
+            time,
+            direction,
+            state,
+            protocol,
+            frame: Frame { id, body: frame },
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = anyhow::Result<CaptureRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+/// Like `read_exact`, but returns how much was read if the reader ends early.
+fn read_fully<R: Read>(reader: &mut R, mut buf: &mut [u8]) -> anyhow::Result<usize> {
+    let mut read = 0;
+
+    while !buf.is_empty() {
+        match reader.read(buf)? {
+            0 => break,
+            len => {
+                read += len;
+                buf = &mut buf[len..];
+            }
+        }
+    }
+
+    Ok(read)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, thread};
+
+    use crate::{
+        packet::{serverbound, PacketDirection},
+        session::{ClientSession, ServerSession},
+        state::State,
+        varint::VarInt,
+    };
+
+    use super::{CaptureReader, CaptureWriter, CapturedPacket};
+
+    #[test]
+    pub fn session_capture() {
+        let mut client = ClientSession::new();
+        client.start_capture();
+
+        client
+            .send(serverbound::handshaking::HandshakePacket {
+                protocol_version: VarInt(5),
+                server_address: "localhost".to_string(),
+                server_port: 25565,
+                next_state: VarInt(2),
+            })
+            .unwrap();
+        client
+            .send(serverbound::login::LoginStartPacket {
+                name: "jam".to_string(),
+            })
+            .unwrap();
+
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for record in client.take_captured() {
+            writer.write(&record).unwrap();
+        }
+
+        let bytes = writer.into_inner();
+        let records = CaptureReader::new(&bytes[..])
+            .unwrap()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(2, records.len());
+        assert!(records
+            .iter()
+            .all(|record| record.direction == PacketDirection::Serverbound));
+        assert_eq!(State::Login, records[1].state);
+        // the login start is recorded in the version from the handshake (1.7.10)
+        assert_eq!(5, records[1].protocol);
+
+        // and decodes the same on the other end
+        let mut server = ServerSession::new();
+        server.receive(&client.take_outbound());
+        server.next_packet().unwrap();
+        let login_start = server.next_packet().unwrap().unwrap();
+        assert_eq!(
+            CapturedPacket::Serverbound(login_start),
+            records[1].decode().unwrap()
+        );
+    }
+
+    #[test]
+    pub fn write_from() {
+        let (sender, records) = mpsc::channel();
+        let writer = thread::spawn(move || {
+            let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+            writer.write_from(records).unwrap();
+            writer.into_inner()
+        });
+
+        let mut client = ClientSession::new();
+        client.start_capture();
+        client
+            .send(serverbound::handshaking::HandshakePacket {
+                protocol_version: VarInt(47),
+                server_address: "localhost".to_string(),
+                server_port: 25565,
+                next_state: VarInt(1),
+            })
+            .unwrap();
+        for record in client.take_captured() {
+            sender.send(record).unwrap();
+        }
+        drop(sender);
+
+        // the writer returns once the sender is gone
+        let bytes = writer.join().unwrap();
+        let records = CaptureReader::new(&bytes[..])
+            .unwrap()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(1, records.len());
+    }
+
+    #[test]
+    pub fn truncated() {
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer
+            .write(&super::CaptureRecord {
+                time: 1,
+                direction: PacketDirection::Clientbound,
+                state: State::Play,
+                protocol: 47,
+                frame: crate::codec::Frame {
+                    id: 0x00,
+                    body: bytes::Bytes::from_static(&[1]),
+                },
+            })
+            .unwrap();
+        let bytes = writer.into_inner();
+
+        let mut reader = CaptureReader::new(&bytes[..]).unwrap();
+        assert!(reader.next_record().unwrap().is_some());
+        assert!(reader.next_record().unwrap().is_none());
+
+        let mut reader = CaptureReader::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(reader.next_record().is_err());
+
+        assert!(CaptureReader::new(&b"JAMCAX\x01"[..]).is_err());
+    }
+}
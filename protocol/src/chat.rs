@@ -3,62 +3,90 @@ use std::marker::PhantomData;
 use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 
-#[derive(TypedBuilder, Serialize, Deserialize, Clone)]
+#[derive(TypedBuilder, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct ChatComponent<'a> {
     text: String,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     bold: Option<bool>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     italic: Option<bool>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     underlined: Option<bool>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     strikethrough: Option<bool>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     obfuscated: Option<bool>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     font: Option<String>,
 
     // #[builder(default)]
     // color: Option<Color>,
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     insertion: Option<String>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     click_event: Option<ClickEvent>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     hover_event: Option<HoverEvent<'a>>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     extra: Option<Vec<ChatComponent<'a>>>,
 }
 
-#[derive(TypedBuilder, Serialize, Deserialize, Clone)]
+impl ChatComponent<'_> {
+    /// The text of the component and its extra components, without formatting.
+    pub fn plain_text(&self) -> String {
+        let mut text = self.text.clone();
+        for extra in self.extra.iter().flatten() {
+            text.push_str(&extra.plain_text());
+        }
+        text
+    }
+}
+
+#[derive(TypedBuilder, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct ClickEvent {
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub open_url: Option<String>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub run_command: Option<String>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub suggest_command: Option<String>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub change_page: Option<String>,
 
     #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub copy_to_clipboard: Option<String>,
 }
 
-#[derive(TypedBuilder, Serialize, Deserialize, Clone)]
+#[derive(TypedBuilder, Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct HoverEvent<'a> {
     #[builder(default)]
     // pub show_text: Option<&'a ChatComponent<'a>>,
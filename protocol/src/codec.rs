@@ -0,0 +1,419 @@
+//! Turns packets into frames and back, independent of how the bytes are sent or received.
+//!
+//! A frame is a VarInt length, followed by the packet ID and body. Once compression is enabled,
+//! every frame carries the uncompressed length as well (0 for frames under the threshold, which
+//! are sent as-is). Once encryption is enabled, the whole stream is encrypted with AES/CFB8, using
+//! the shared secret as both key and IV.
+
+use std::io::{Read, Write};
+
+use aes::Aes128;
+use anyhow::{anyhow, bail};
+use bytes::{Buf, Bytes, BytesMut};
+use cfb8::cipher::{generic_array::GenericArray, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use crate::{
+    encoding::{byte_array::Remaining, Decode, Encode},
+    packet::{AnyStatePacket, Packet, PacketSet},
+    varint::VarInt,
+    version::{ProtocolVersion, VersionedPacket},
+};
+
+type Encryptor = cfb8::Encryptor<Aes128>;
+type Decryptor = cfb8::Decryptor<Aes128>;
+
+/// The largest frame the vanilla server accepts (the biggest length that fits in 3 VarInt bytes.)
+pub const MAX_FRAME_LEN: usize = 2097151;
+
+/// The largest size a compressed packet may have once decompressed.
+pub const MAX_DATA_LEN: usize = 2097152;
+
+/// A single packet, with its ID read but its body not decoded yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub id: i32,
+    pub body: Bytes,
+}
+
+impl Frame {
+    /// Encodes the body of `packet` into a new frame.
+    pub fn new<P: Packet>(packet: &P) -> anyhow::Result<Self> {
+        let mut body = BytesMut::new();
+        packet.encode(&mut body)?;
+
+        Ok(Frame {
+            id: P::ID,
+            body: body.freeze(),
+        })
+    }
+
+    /// Decodes the body into one of the packets of the set `S`, picked by ID.
+    ///
+    /// Any fields borrowing from the frame (e.g. `Bytes`) point into [Frame::body].
+    pub fn decode<S: PacketSet>(&self) -> anyhow::Result<S> {
+        S::decode_by_id(self.id, &mut self.body.clone())
+    }
+
+    /// Like [Frame::decode], for a frame sent in `version`.
+    pub fn decode_for<S: PacketSet>(&self, version: ProtocolVersion) -> anyhow::Result<S> {
+        S::decode_for(version, self.id, &mut self.body.clone())
+    }
+}
+
+/// Encodes packets into frames, ready to be written to a connection.
+#[derive(Default)]
+pub struct PacketEncoder {
+    buf: BytesMut,
+    scratch: BytesMut,
+    compress_buf: Vec<u8>,
+    threshold: Option<usize>,
+    cipher: Option<Encryptor>,
+    version: ProtocolVersion,
+
+    /// How many bytes at the start of `buf` were appended before encryption was enabled.
+    plain_len: usize,
+}
+
+impl PacketEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compresses every packet from now on that is at least `threshold` bytes long. `None`
+    /// disables compression.
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.threshold = threshold;
+    }
+
+    /// Encrypts everything appended from now on. Frames that are already appended but not taken
+    /// yet are still sent in plain text.
+    pub fn enable_encryption(&mut self, key: &[u8; 16]) {
+        self.plain_len = self.buf.len();
+        self.cipher = Some(Encryptor::new(key.into(), key.into()));
+    }
+
+    /// Lays out every packet appended from now on as in `version`. The latest version is used
+    /// until this is called.
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.version = version;
+    }
+
+    pub fn append_packet<P: VersionedPacket>(&mut self, packet: &P) -> anyhow::Result<()> {
+        let version = self.version;
+        let id = P::id_for(version)
+            .ok_or_else(|| anyhow!("packet {:#04x} doesn't exist in {:?}", P::ID, version))?;
+
+        self.append_raw(id, |buf| packet.encode_for(version, buf))
+    }
+
+    pub fn append_set<S: PacketSet>(&mut self, packet: &S) -> anyhow::Result<()> {
+        let version = self.version;
+        let id = packet
+            .id_for(version)
+            .ok_or_else(|| anyhow!("packet {:#04x} doesn't exist in {:?}", packet.id(), version))?;
+
+        self.append_raw(id, |buf| packet.encode_for(version, buf))
+    }
+
+    pub fn append_any<A: AnyStatePacket>(&mut self, packet: &A) -> anyhow::Result<()> {
+        let version = self.version;
+        let id = packet
+            .id_for(version)
+            .ok_or_else(|| anyhow!("packet {:#04x} doesn't exist in {:?}", packet.id(), version))?;
+
+        self.append_raw(id, |buf| packet.encode_for(version, buf))
+    }
+
+    /// Appends a frame that was decoded before, without touching its body.
+    pub fn append_frame(&mut self, frame: &Frame) -> anyhow::Result<()> {
+        self.append_raw(frame.id, |buf| Remaining(frame.body.clone()).encode(buf))
+    }
+
+    fn append_raw(
+        &mut self,
+        id: i32,
+        body: impl FnOnce(&mut BytesMut) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        self.scratch.clear();
+        VarInt(id).encode(&mut self.scratch)?;
+        body(&mut self.scratch)?;
+
+        let data_len = self.scratch.len();
+
+        match self.threshold {
+            Some(threshold) if data_len >= threshold => {
+                let mut encoder = ZlibEncoder::new(
+                    std::mem::take(&mut self.compress_buf),
+                    Compression::default(),
+                );
+                encoder.write_all(&self.scratch)?;
+                let compressed = encoder.finish()?;
+
+                let data_len = VarInt(data_len as i32);
+                let frame_len = data_len.encoded_len() + compressed.len();
+                Self::check_frame_len(frame_len)?;
+
+                VarInt(frame_len as i32).encode(&mut self.buf)?;
+                data_len.encode(&mut self.buf)?;
+                self.buf.extend_from_slice(&compressed);
+
+                self.compress_buf = compressed;
+                self.compress_buf.clear();
+            }
+            Some(_) => {
+                // a data length of 0 marks the frame as uncompressed
+                Self::check_frame_len(data_len + 1)?;
+
+                VarInt(data_len as i32 + 1).encode(&mut self.buf)?;
+                VarInt(0).encode(&mut self.buf)?;
+                self.buf.extend_from_slice(&self.scratch);
+            }
+            None => {
+                Self::check_frame_len(data_len)?;
+
+                VarInt(data_len as i32).encode(&mut self.buf)?;
+                self.buf.extend_from_slice(&self.scratch);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_frame_len(len: usize) -> anyhow::Result<()> {
+        if len > MAX_FRAME_LEN {
+            bail!("frame is too long: {} bytes (max {})", len, MAX_FRAME_LEN);
+        }
+
+        Ok(())
+    }
+
+    /// Whether there are any frames waiting to be taken.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Takes all frames appended so far, encrypted if needed.
+    pub fn take(&mut self) -> BytesMut {
+        if let Some(cipher) = &mut self.cipher {
+            for byte in &mut self.buf[self.plain_len..] {
+                cipher.encrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(byte)));
+            }
+        }
+
+        self.plain_len = 0;
+        self.buf.split()
+    }
+}
+
+/// Decodes frames out of the bytes received from a connection.
+#[derive(Default)]
+pub struct PacketDecoder {
+    buf: BytesMut,
+    threshold: Option<usize>,
+    cipher: Option<Decryptor>,
+}
+
+impl PacketDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expects every frame from now on to be in the compressed format. `None` disables
+    /// compression.
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.threshold = threshold;
+    }
+
+    /// Decrypts everything received from now on, including bytes that are queued but not decoded
+    /// yet (the other side switches right after the packet that enabled encryption.)
+    pub fn enable_encryption(&mut self, key: &[u8; 16]) {
+        let mut cipher = Decryptor::new(key.into(), key.into());
+        Self::decrypt(&mut cipher, &mut self.buf);
+
+        self.cipher = Some(cipher);
+    }
+
+    fn decrypt(cipher: &mut Decryptor, buf: &mut [u8]) {
+        for byte in buf {
+            cipher.decrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(byte)));
+        }
+    }
+
+    /// Queues bytes received from the connection.
+    pub fn queue_slice(&mut self, bytes: &[u8]) {
+        let start = self.buf.len();
+        self.buf.extend_from_slice(bytes);
+
+        if let Some(cipher) = &mut self.cipher {
+            Self::decrypt(cipher, &mut self.buf[start..]);
+        }
+    }
+
+    /// Queues bytes received from the connection, avoiding a copy where possible.
+    pub fn queue_bytes(&mut self, mut bytes: BytesMut) {
+        if let Some(cipher) = &mut self.cipher {
+            Self::decrypt(cipher, &mut bytes);
+        }
+
+        self.buf.unsplit(bytes);
+    }
+
+    /// Whether there are any bytes left that weren't decoded into a frame.
+    pub fn has_remaining(&self) -> bool {
+        !self.buf.is_empty()
+    }
+
+    /// Decodes the next frame, or returns `None` if it hasn't been fully received yet.
+    pub fn try_next_frame(&mut self) -> anyhow::Result<Option<Frame>> {
+        let (frame_len, len_size) = match VarInt::decode_partial(&self.buf)? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+
+        if frame_len.0 < 0 || frame_len.0 as usize > MAX_FRAME_LEN {
+            bail!("invalid frame length: {}", frame_len.0);
+        }
+
+        let frame_len = frame_len.0 as usize;
+        if self.buf.len() < len_size + frame_len {
+            return Ok(None);
+        }
+
+        self.buf.advance(len_size);
+        let mut data = self.buf.split_to(frame_len).freeze();
+
+        if let Some(threshold) = self.threshold {
+            let data_len = VarInt::decode(&mut data)?.0;
+
+            if data_len != 0 {
+                if data_len < 0 || data_len as usize > MAX_DATA_LEN {
+                    bail!("invalid uncompressed length: {}", data_len);
+                }
+
+                let data_len = data_len as usize;
+                if data_len < threshold {
+                    bail!(
+                        "badly compressed frame: {} bytes is below the threshold of {}",
+                        data_len,
+                        threshold
+                    );
+                }
+
+                let mut decompressed = Vec::with_capacity(data_len);
+                ZlibDecoder::new(&data[..])
+                    .take(data_len as u64)
+                    .read_to_end(&mut decompressed)?;
+
+                if decompressed.len() != data_len {
+                    bail!(
+                        "uncompressed length mismatch: expected {}, got {}",
+                        data_len,
+                        decompressed.len()
+                    );
+                }
+
+                data = Bytes::from(decompressed);
+            }
+        }
+
+        let id = VarInt::decode(&mut data)?;
+
+        Ok(Some(Frame {
+            id: id.0,
+            body: data,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Frame, PacketDecoder, PacketEncoder};
+    use crate::packet::{
+        clientbound::{status::ResponsePacket, ClientboundStatusPacket},
+        serverbound::{status::PingPacket, ServerboundStatusPacket},
+    };
+
+    fn round_trip(encoder: &mut PacketEncoder, decoder: &mut PacketDecoder) {
+        let response = ResponsePacket {
+            response: "jam ".repeat(100),
+        };
+
+        encoder.append_packet(&PingPacket { payload: 42 }).unwrap();
+        encoder.append_packet(&response).unwrap();
+
+        // feed the bytes one at a time, frames may be split anywhere
+        for byte in encoder.take() {
+            decoder.queue_slice(&[byte]);
+        }
+
+        let frame = decoder.try_next_frame().unwrap().unwrap();
+        match frame.decode::<ServerboundStatusPacket>().unwrap() {
+            ServerboundStatusPacket::Ping(ping) => assert_eq!(42, ping.payload),
+            _ => panic!("expected ping"),
+        }
+
+        let frame = decoder.try_next_frame().unwrap().unwrap();
+        match frame.decode::<ClientboundStatusPacket>().unwrap() {
+            ClientboundStatusPacket::Response(packet) => {
+                assert_eq!(response.response, packet.response)
+            }
+            _ => panic!("expected response"),
+        }
+
+        assert!(decoder.try_next_frame().unwrap().is_none());
+        assert!(!decoder.has_remaining());
+    }
+
+    #[test]
+    pub fn plain() {
+        round_trip(&mut PacketEncoder::new(), &mut PacketDecoder::new());
+    }
+
+    #[test]
+    pub fn compressed() {
+        let mut encoder = PacketEncoder::new();
+        let mut decoder = PacketDecoder::new();
+
+        // the ping stays under the threshold, the response doesn't
+        encoder.set_compression(Some(64));
+        decoder.set_compression(Some(64));
+
+        round_trip(&mut encoder, &mut decoder);
+    }
+
+    #[test]
+    pub fn encrypted() {
+        let mut encoder = PacketEncoder::new();
+        let mut decoder = PacketDecoder::new();
+
+        encoder.set_compression(Some(64));
+        decoder.set_compression(Some(64));
+        encoder.enable_encryption(&[7; 16]);
+        decoder.enable_encryption(&[7; 16]);
+
+        round_trip(&mut encoder, &mut decoder);
+        round_trip(&mut encoder, &mut decoder);
+    }
+
+    #[test]
+    pub fn append_frame() {
+        let frame = Frame::new(&PingPacket { payload: 7 }).unwrap();
+
+        let mut encoder = PacketEncoder::new();
+        encoder.append_frame(&frame).unwrap();
+
+        let mut decoder = PacketDecoder::new();
+        decoder.queue_bytes(encoder.take());
+
+        assert_eq!(Some(frame), decoder.try_next_frame().unwrap());
+    }
+
+    #[test]
+    pub fn reject_long_frame() {
+        let mut decoder = PacketDecoder::new();
+        decoder.queue_slice(&[0xff, 0xff, 0xff, 0x7f]);
+
+        assert!(decoder.try_next_frame().is_err());
+    }
+}
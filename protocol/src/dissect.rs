@@ -0,0 +1,199 @@
+//! Field-by-field decoding of packet bodies, for debugging.
+//!
+//! Decoding a packet either succeeds or fails as a whole, which doesn't help much with a frame
+//! from a bug report. A [Dissection] instead records where every field lies in the body and what
+//! it decoded to, up to the field that failed (if any), and how many bytes were left over.
+//!
+//! Every packet derived through `PacketDef` implements [Dissect], and packets laid out
+//! differently in older versions dissect through
+//! [VersionedPacket::dissect_for](crate::version::VersionedPacket::dissect_for).
+
+use std::fmt::Debug;
+
+/// A field that was decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DissectedField {
+    pub name: &'static str,
+    /// The field's type, as written in the packet definition.
+    pub ty: &'static str,
+    /// Where the field starts, from the start of the body.
+    pub offset: usize,
+    pub len: usize,
+    /// The decoded value, formatted with `Debug`.
+    pub value: String,
+}
+
+/// A field that couldn't be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub name: &'static str,
+    pub ty: &'static str,
+    pub offset: usize,
+    pub message: String,
+}
+
+/// A packet body, decoded field by field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dissection {
+    /// The fields decoded before any error.
+    pub fields: Vec<DissectedField>,
+    /// The first field that couldn't be decoded. The fields after it aren't decoded.
+    pub error: Option<FieldError>,
+    /// The length of the body.
+    pub len: usize,
+    /// How many bytes of the body were decoded.
+    pub consumed: usize,
+}
+
+impl Dissection {
+    /// The bytes no field was decoded from, if every field was.
+    pub fn trailing(&self) -> usize {
+        match self.error {
+            Some(_) => 0,
+            None => self.len - self.consumed,
+        }
+    }
+
+    /// Whether every field was decoded, and the whole body consumed.
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none() && self.consumed == self.len
+    }
+}
+
+/// Builds a [Dissection], decoding one field after the other.
+pub struct Dissector<'a> {
+    reader: &'a [u8],
+    dissection: Dissection,
+}
+
+impl<'a> Dissector<'a> {
+    pub fn new(body: &'a [u8]) -> Self {
+        Self {
+            reader: body,
+            dissection: Dissection {
+                fields: Vec::new(),
+                error: None,
+                len: body.len(),
+                consumed: 0,
+            },
+        }
+    }
+
+    /// Decodes the next field through `decode`. Nothing is decoded once a field has failed.
+    pub fn field<T: Debug>(
+        &mut self,
+        name: &'static str,
+        ty: &'static str,
+        decode: impl FnOnce(&mut &'a [u8]) -> anyhow::Result<T>,
+    ) {
+        if self.dissection.error.is_some() {
+            return;
+        }
+
+        let offset = self.dissection.consumed;
+        let mut reader = self.reader;
+
+        match decode(&mut reader) {
+            Ok(value) => {
+                let len = self.reader.len() - reader.len();
+
+                self.reader = reader;
+                self.dissection.consumed += len;
+                self.dissection.fields.push(DissectedField {
+                    name,
+                    ty,
+                    offset,
+                    len,
+                    value: format!("{:?}", value),
+                });
+            }
+            Err(error) => {
+                self.dissection.error = Some(FieldError {
+                    name,
+                    ty,
+                    offset,
+                    message: error.to_string(),
+                })
+            }
+        }
+    }
+
+    pub fn finish(self) -> Dissection {
+        self.dissection
+    }
+}
+
+/// A packet that can be dissected.
+pub trait Dissect {
+    fn dissect(body: &[u8]) -> Dissection;
+}
+
+#[cfg(test)]
+mod test {
+    use super::Dissect;
+    use crate::packet::{
+        clientbound::ClientboundPlayPacket,
+        serverbound::{self, ServerboundPacket},
+        AnyStatePacket, PacketSet,
+    };
+    use crate::{state::State, version::ProtocolVersion};
+
+    #[test]
+    pub fn fields() {
+        // protocol 47, "localhost", 25565 and login
+        let body = [
+            0x2f, 0x09, b'l', b'o', b'c', b'a', b'l', b'h', b'o', b's', b't', 0x63, 0xdd, 0x02,
+        ];
+        let dissection = serverbound::handshaking::HandshakePacket::dissect(&body);
+
+        assert!(dissection.is_complete());
+        assert_eq!(
+            vec![
+                ("protocol_version", "VarInt", 0, 1, "VarInt(47)"),
+                ("server_address", "String", 1, 10, "\"localhost\""),
+                ("server_port", "u16", 11, 2, "25565"),
+                ("next_state", "VarInt", 13, 1, "VarInt(2)"),
+            ],
+            dissection
+                .fields
+                .iter()
+                .map(|field| (
+                    field.name,
+                    field.ty,
+                    field.offset,
+                    field.len,
+                    &field.value[..]
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    pub fn error_and_trailing() {
+        // the string claims 9 bytes, but only has 3
+        let dissection =
+            serverbound::handshaking::HandshakePacket::dissect(&[0x2f, 0x09, b'l', b'o', b'c']);
+        let error = dissection.error.as_ref().unwrap();
+        assert_eq!(("server_address", 1), (error.name, error.offset));
+        assert_eq!(1, dissection.fields.len());
+
+        let (name, dissection) = ServerboundPacket::dissect_by_state(
+            ProtocolVersion::V1_8,
+            State::Play,
+            0x00,
+            &[0x07, 0xff],
+        )
+        .unwrap();
+        assert_eq!("KeepAlive", name);
+        assert_eq!(1, dissection.trailing());
+
+        // 1.7.10 sends the keep alive ID as an int
+        let (_, dissection) =
+            ClientboundPlayPacket::dissect_for(ProtocolVersion::V1_7_10, 0x00, &[0, 0, 0, 7])
+                .unwrap();
+        assert!(dissection.is_complete());
+        assert_eq!("i32", dissection.fields[0].ty);
+
+        assert!(ClientboundPlayPacket::dissect_for(ProtocolVersion::V1_8, 0x7f, &[]).is_none());
+    }
+}
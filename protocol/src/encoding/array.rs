@@ -1,16 +1,16 @@
+use anyhow::bail;
 use bytes::{Buf, BufMut};
 
-use crate::array::CountedArray;
+use crate::{array::CountedArray, varint::VarInt};
 
-use super::Encodable;
+use super::{Decode, Encode};
 
-impl<K, U> Encodable for CountedArray<K, U>
+impl<K, U> Decode for CountedArray<K, U>
 where
-    usize: PartialEq<K>,
-    K: Encodable + Into<usize> + From<usize>,
-    U: Encodable,
+    K: Decode + Into<usize> + From<usize>,
+    U: Decode,
 {
-    fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self> {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
         let mut vec = Vec::<U>::new();
         let len: usize = K::decode(reader)?.into();
 
@@ -23,8 +23,15 @@ where
             arr: vec,
         })
     }
+}
 
-    fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()> {
+impl<K, U> Encode for CountedArray<K, U>
+where
+    usize: PartialEq<K>,
+    K: Encode,
+    U: Encode,
+{
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
         assert!(self.arr.len() == self.len);
         self.len.encode(writer)?;
 
@@ -34,4 +41,44 @@ where
 
         Ok(())
     }
+
+    fn encoded_len(&self) -> usize {
+        self.len.encoded_len() + self.arr.iter().map(U::encoded_len).sum::<usize>()
+    }
+}
+
+/// An array prefixed with its length as a VarInt.
+impl<T: Decode> Decode for Vec<T> {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
+        let len = VarInt::decode(reader)?;
+
+        if len.0 < 0 {
+            bail!("negative length: {}", len.0);
+        }
+
+        // Every element takes at least a byte, which bounds the allocation for bogus lengths.
+        let mut vec = Vec::with_capacity((len.0 as usize).min(reader.remaining()));
+
+        for _ in 0..len.0 {
+            vec.push(T::decode(reader)?);
+        }
+
+        Ok(vec)
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        VarInt(self.len() as i32).encode(writer)?;
+
+        for el in self.iter() {
+            el.encode(writer)?;
+        }
+
+        Ok(())
+    }
+
+    fn encoded_len(&self) -> usize {
+        VarInt(self.len() as i32).encoded_len() + self.iter().map(T::encoded_len).sum::<usize>()
+    }
 }
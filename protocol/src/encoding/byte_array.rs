@@ -0,0 +1,122 @@
+use anyhow::bail;
+use bytes::{Buf, BufMut, Bytes};
+use serde::Serialize;
+
+use crate::varint::VarInt;
+
+use super::{ensure_remaining, ensure_remaining_mut, Decode, Encode};
+
+/// Reads the VarInt length prefix of a byte array or string, and makes sure that many bytes are
+/// left in `reader`.
+pub(crate) fn decode_len<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<usize> {
+    let len = VarInt::decode(reader)?;
+
+    if len.0 < 0 {
+        bail!("negative length: {}", len.0);
+    }
+
+    let len = len.0 as usize;
+    ensure_remaining(reader, len)?;
+
+    Ok(len)
+}
+
+/// Length-prefixed byte array.
+impl Encode for [u8] {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        VarInt(self.len() as i32).encode(writer)?;
+
+        ensure_remaining_mut(writer, self.len())?;
+        writer.put_slice(self);
+
+        Ok(())
+    }
+
+    fn encoded_len(&self) -> usize {
+        VarInt(self.len() as i32).encoded_len() + self.len()
+    }
+}
+
+/// Length-prefixed byte array.
+///
+/// Decoding is zero-copy when reading from a [Bytes] buffer, since [Buf::copy_to_bytes] only
+/// slices it in that case.
+impl Encode for Bytes {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        self[..].encode(writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self[..].encoded_len()
+    }
+}
+
+impl Decode for Bytes {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
+        let len = decode_len(reader)?;
+
+        Ok(reader.copy_to_bytes(len))
+    }
+}
+
+/// Data that takes up the rest of the packet, without a length prefix of its own (e.g. the
+/// payload of a plugin message).
+///
+/// Like [Bytes], decoding it from a [Bytes] buffer doesn't copy.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct Remaining<T>(pub T);
+
+impl Encode for Remaining<Bytes> {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        ensure_remaining_mut(writer, self.0.len())?;
+        writer.put_slice(&self.0);
+
+        Ok(())
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl Decode for Remaining<Bytes> {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
+        Ok(Remaining(reader.copy_to_bytes(reader.remaining())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::{Buf, Bytes, BytesMut};
+
+    use super::Remaining;
+    use crate::encoding::{Decode, Encode};
+
+    #[test]
+    pub fn bytes_are_sliced() {
+        let frame = Bytes::from_static(&[3, 1, 2, 3]);
+        let mut reader = frame.clone();
+
+        let data = Bytes::decode(&mut reader).unwrap();
+
+        assert_eq!(frame[1..].as_ptr(), data.as_ptr());
+
+        let mut reader = frame.clone();
+        reader.advance(1);
+        let rest = Remaining::<Bytes>::decode(&mut reader).unwrap();
+
+        assert_eq!(frame[1..].as_ptr(), rest.0.as_ptr());
+    }
+
+    #[test]
+    pub fn encode() {
+        let mut buf = BytesMut::new();
+
+        Bytes::from_static(&[1, 2]).encode(&mut buf).unwrap();
+        Remaining(Bytes::from_static(&[3, 4]))
+            .encode(&mut buf)
+            .unwrap();
+
+        assert_eq!([2, 1, 2, 3, 4], &buf[..]);
+    }
+}
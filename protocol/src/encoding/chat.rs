@@ -2,17 +2,19 @@ use bytes::{Buf, BufMut};
 
 use crate::chat::ChatComponent;
 
-use super::Encodable;
+use super::{Decode, Encode};
 
-impl<'a> Encodable for ChatComponent<'a> {
-    fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self> {
+impl<'a> Decode for ChatComponent<'a> {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
         let encoded = String::decode(reader)?;
         let component = serde_json::from_str(&encoded)?;
 
-        return Ok(component);
+        Ok(component)
     }
+}
 
-    fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()> {
+impl<'a> Encode for ChatComponent<'a> {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
         let encoded = serde_json::to_string(self)?;
 
         String::encode(&encoded, writer)
@@ -1,14 +1,82 @@
+use anyhow::bail;
 use bytes::{Buf, BufMut};
 
-pub trait Encodable
+/// A type that can be written to the wire.
+///
+/// The method is generic over the buffer so that the common cases (`BytesMut`, `Vec<u8>`) are
+/// monomorphized. `&mut dyn BufMut` still works, since the buffer type is allowed to be unsized.
+pub trait Encode {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()>;
+
+    /// The number of bytes [Encode::encode] writes.
+    ///
+    /// The default encodes into a scratch buffer, so types that know their size up front override
+    /// it. If encoding fails, this is the number of bytes written before the failure.
+    fn encoded_len(&self) -> usize {
+        let mut buf = Vec::new();
+        let _ = self.encode(&mut buf);
+
+        buf.len()
+    }
+}
+
+/// A type that can be read from the wire into an owned value.
+///
+/// Like [Encode], the method is generic over the buffer, and `&mut dyn Buf` works as well.
+pub trait Decode
 where
     Self: Sized,
 {
-    fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self>;
-    fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()>;
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self>;
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        T::encode(self, writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        T::encoded_len(self)
+    }
+}
+
+/// A type that can be both encoded and decoded.
+pub trait Encodable: Encode + Decode {}
+
+impl<T: Encode + Decode> Encodable for T {}
+
+/// Makes sure that at least `len` bytes can be read from `reader`.
+///
+/// The `Buf::get_*` family panics on short buffers, so every decoder checks this first.
+pub fn ensure_remaining<B: Buf + ?Sized>(reader: &B, len: usize) -> anyhow::Result<()> {
+    if reader.remaining() < len {
+        bail!(
+            "unexpected end of buffer: needed {} bytes, {} remaining",
+            len,
+            reader.remaining()
+        );
+    }
+
+    Ok(())
+}
+
+/// Makes sure that at least `len` bytes can be written to `writer`.
+pub fn ensure_remaining_mut<B: BufMut + ?Sized>(writer: &B, len: usize) -> anyhow::Result<()> {
+    if writer.remaining_mut() < len {
+        bail!(
+            "buffer full: needed {} bytes, {} remaining",
+            len,
+            writer.remaining_mut()
+        );
+    }
+
+    Ok(())
 }
 
 pub mod array;
+pub mod byte_array;
 pub mod chat;
 pub mod numbers;
+pub mod option;
 pub mod string;
+pub mod uuid;
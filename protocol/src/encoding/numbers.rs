@@ -1,57 +1,64 @@
-use std::io::Write;
+use anyhow::bail;
+use std::mem::size_of;
 
-use byteorder::{BigEndian, ReadBytesExt};
 use bytes::{Buf, BufMut};
 
-use super::Encodable;
-
-impl Encodable for i8 {
-    fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self> {
-        let val = reader.reader().read_i8()?;
-        Ok(val)
-    }
-
-    fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()> {
-        writer.writer().write_all(&self.to_be_bytes())?;
-        Ok(())
-    }
-}
-
-impl Encodable for u8 {
-    fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self> {
-        let val = reader.reader().read_u8()?;
-        Ok(val)
-    }
-
-    fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()> {
-        writer.writer().write_all(&self.to_be_bytes())?;
-        Ok(())
-    }
-}
+use super::{ensure_remaining, ensure_remaining_mut, Decode, Encode};
 
 macro_rules! gen_num_encode {
     ($ty:ty) => {
         ::paste::paste! {
-            impl Encodable for $ty {
-                fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self> {
-                    let val = reader.reader().[<read_ $ty>]::<BigEndian>()?;
-                    Ok(val)
+            impl Decode for $ty {
+                fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
+                    ensure_remaining(reader, size_of::<$ty>())?;
+                    Ok(reader.[<get_ $ty>]())
                 }
+            }
 
-                fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()> {
-                    writer.writer().write_all(&self.to_be_bytes())?;
+            impl Encode for $ty {
+                fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+                    ensure_remaining_mut(writer, size_of::<$ty>())?;
+                    writer.[<put_ $ty>](*self);
                     Ok(())
                 }
+
+                fn encoded_len(&self) -> usize {
+                    size_of::<$ty>()
+                }
             }
         }
     };
 }
 
+gen_num_encode!(i8);
+gen_num_encode!(u8);
 gen_num_encode!(i16);
 gen_num_encode!(i32);
 gen_num_encode!(i64);
 gen_num_encode!(u16);
 gen_num_encode!(u32);
 gen_num_encode!(u64);
+gen_num_encode!(u128);
 gen_num_encode!(f32);
 gen_num_encode!(f64);
+
+/// A single byte, 0x01 for `true` and 0x00 for `false`.
+impl Decode for bool {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
+        match u8::decode(reader)? {
+            0x00 => Ok(false),
+            0x01 => Ok(true),
+            value => bail!("invalid bool: {:#04x}", value),
+        }
+    }
+}
+
+impl Encode for bool {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        (*self as u8).encode(writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        1
+    }
+}
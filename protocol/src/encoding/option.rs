@@ -0,0 +1,29 @@
+use bytes::{Buf, BufMut};
+
+use super::{Decode, Encode};
+
+/// A value prefixed with a bool telling whether it is present.
+impl<T: Decode> Decode for Option<T> {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
+        if bool::decode(reader)? {
+            Ok(Some(T::decode(reader)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        self.is_some().encode(writer)?;
+
+        match self {
+            Some(value) => value.encode(writer),
+            None => Ok(()),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        1 + self.as_ref().map_or(0, T::encoded_len)
+    }
+}
@@ -1,29 +1,59 @@
-use std::io::{Read, Write};
-
 use bytes::{Buf, BufMut};
 
 use crate::varint::VarInt;
 
-use super::Encodable;
+use super::{byte_array::decode_len, ensure_remaining_mut, Decode, Encode};
+
+impl Encode for str {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        let string_len = self.len();
+
+        VarInt(string_len as i32).encode(writer)?;
+
+        ensure_remaining_mut(writer, string_len)?;
+        writer.put_slice(self.as_bytes());
 
-impl Encodable for String {
-    fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self> {
-        let string_len = VarInt::decode(reader)?;
+        Ok(())
+    }
 
-        let mut buf = Vec::with_capacity(string_len.0 as usize);
-        buf.resize(string_len.0 as usize, 0);
+    fn encoded_len(&self) -> usize {
+        VarInt(self.len() as i32).encoded_len() + self.len()
+    }
+}
 
-        reader.reader().read(&mut buf[..])?;
+impl Encode for String {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        self.as_str().encode(writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.as_str().encoded_len()
+    }
+}
+
+impl Decode for String {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
+        let string_len = decode_len(reader)?;
+
+        let mut buf = vec![0; string_len];
+        reader.copy_to_slice(&mut buf);
 
         Ok(String::from_utf8(buf)?)
     }
+}
 
-    fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()> {
-        let string_len = self.len();
+#[cfg(test)]
+mod test {
+    use crate::encoding::Decode;
 
-        VarInt::encode(&VarInt(string_len as i32), writer)?;
-        writer.writer().write(String::as_bytes(self))?;
+    #[test]
+    pub fn decode_short() {
+        // claims 5 bytes, but only 2 follow
+        assert!(String::decode(&mut &[5, 104, 105][..]).is_err());
+    }
 
-        Ok(())
+    #[test]
+    pub fn decode_negative_length() {
+        assert!(String::decode(&mut &[0xff, 0xff, 0xff, 0xff, 0x0f][..]).is_err());
     }
 }
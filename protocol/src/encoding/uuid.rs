@@ -0,0 +1,21 @@
+use bytes::{Buf, BufMut};
+use uuid::Uuid;
+
+use super::{Decode, Encode};
+
+/// A UUID, sent as its 128 bits (most significant first.)
+impl Decode for Uuid {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
+        Ok(Uuid::from_u128(u128::decode(reader)?))
+    }
+}
+
+impl Encode for Uuid {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        self.as_u128().encode(writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        16
+    }
+}
@@ -0,0 +1,230 @@
+//! Reading and writing packets over tokio streams (requires the `tokio` feature.)
+//!
+//! [PacketReader] and [PacketWriter] wrap the two halves of a connection, and take care of the
+//! frame codec, compression and encryption through a [PacketDecoder] and [PacketEncoder].
+
+use anyhow::bail;
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    codec::{Frame, PacketDecoder, PacketEncoder},
+    packet::PacketSet,
+    version::{ProtocolVersion, VersionedPacket},
+};
+
+/// How many bytes are read from the stream at once.
+const READ_BUF_SIZE: usize = 4096;
+
+/// Reads frames and typed packets from an [AsyncRead].
+pub struct PacketReader<R> {
+    reader: R,
+    decoder: PacketDecoder,
+    buf: BytesMut,
+    version: ProtocolVersion,
+}
+
+impl<R: AsyncRead + Unpin> PacketReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            decoder: PacketDecoder::new(),
+            buf: BytesMut::new(),
+            version: ProtocolVersion::LATEST,
+        }
+    }
+
+    /// Decodes packets as sent in `version` from now on.
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.version = version;
+    }
+
+    /// See [PacketDecoder::set_compression].
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.decoder.set_compression(threshold);
+    }
+
+    /// See [PacketDecoder::enable_encryption].
+    pub fn enable_encryption(&mut self, key: &[u8; 16]) {
+        self.decoder.enable_encryption(key);
+    }
+
+    /// Reads the next frame. Returns `None` once the stream is closed between two frames.
+    pub async fn read_frame(&mut self) -> anyhow::Result<Option<Frame>> {
+        loop {
+            if let Some(frame) = self.decoder.try_next_frame()? {
+                return Ok(Some(frame));
+            }
+
+            self.buf.reserve(READ_BUF_SIZE);
+
+            if self.reader.read_buf(&mut self.buf).await? == 0 {
+                if self.decoder.has_remaining() {
+                    bail!("stream closed in the middle of a frame");
+                }
+
+                return Ok(None);
+            }
+
+            self.decoder.queue_bytes(self.buf.split());
+        }
+    }
+
+    /// Reads the next packet, out of the packets of the set `S` (e.g.
+    /// [ServerboundLoginPacket](crate::packet::serverbound::ServerboundLoginPacket) while in the
+    /// login state.)
+    pub async fn read_packet<S: PacketSet>(&mut self) -> anyhow::Result<Option<S>> {
+        match self.read_frame().await? {
+            Some(frame) => Ok(Some(frame.decode_for(self.version)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Writes packets to an [AsyncWrite].
+///
+/// Packets can either be sent right away, or queued up and sent together with [PacketWriter::flush].
+pub struct PacketWriter<W> {
+    writer: W,
+    encoder: PacketEncoder,
+}
+
+impl<W: AsyncWrite + Unpin> PacketWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            encoder: PacketEncoder::new(),
+        }
+    }
+
+    /// See [PacketEncoder::set_compression].
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.encoder.set_compression(threshold);
+    }
+
+    /// See [PacketEncoder::set_version].
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.encoder.set_version(version);
+    }
+
+    /// See [PacketEncoder::enable_encryption].
+    pub fn enable_encryption(&mut self, key: &[u8; 16]) {
+        self.encoder.enable_encryption(key);
+    }
+
+    /// Queues a packet, to be sent on the next [PacketWriter::flush].
+    pub fn queue<P: VersionedPacket>(&mut self, packet: &P) -> anyhow::Result<()> {
+        self.encoder.append_packet(packet)
+    }
+
+    /// Queues a packet out of a [PacketSet], to be sent on the next [PacketWriter::flush].
+    pub fn queue_set<S: PacketSet>(&mut self, packet: &S) -> anyhow::Result<()> {
+        self.encoder.append_set(packet)
+    }
+
+    /// Queues a frame as-is, to be sent on the next [PacketWriter::flush].
+    pub fn queue_frame(&mut self, frame: &Frame) -> anyhow::Result<()> {
+        self.encoder.append_frame(frame)
+    }
+
+    /// Sends a packet, along with anything queued before it.
+    pub async fn send<P: VersionedPacket>(&mut self, packet: &P) -> anyhow::Result<()> {
+        self.queue(packet)?;
+        self.flush().await
+    }
+
+    /// Sends a packet out of a [PacketSet], along with anything queued before it.
+    pub async fn send_set<S: PacketSet>(&mut self, packet: &S) -> anyhow::Result<()> {
+        self.queue_set(packet)?;
+        self.flush().await
+    }
+
+    /// Writes everything queued in a single write.
+    pub async fn flush(&mut self) -> anyhow::Result<()> {
+        if self.encoder.is_empty() {
+            return Ok(());
+        }
+
+        let bytes = self.encoder.take();
+
+        self.writer.write_all(&bytes).await?;
+        self.writer.flush().await?;
+
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PacketReader, PacketWriter};
+    use crate::packet::serverbound::{
+        handshaking::HandshakePacket, login::LoginStartPacket, ServerboundHandshakingPacket,
+        ServerboundLoginPacket,
+    };
+    use crate::varint::VarInt;
+
+    #[tokio::test]
+    pub async fn handshake_and_login() {
+        let (client, server) = tokio::io::duplex(64);
+
+        let mut writer = PacketWriter::new(client);
+        let mut reader = PacketReader::new(server);
+
+        writer
+            .send(&HandshakePacket {
+                protocol_version: VarInt(47),
+                server_address: "localhost".to_string(),
+                server_port: 25565,
+                next_state: VarInt(2),
+            })
+            .await
+            .unwrap();
+
+        writer.set_compression(Some(256));
+        writer.enable_encryption(&[1; 16]);
+        writer
+            .send(&LoginStartPacket {
+                name: "jam".to_string(),
+            })
+            .await
+            .unwrap();
+        drop(writer);
+
+        match reader.read_packet().await.unwrap() {
+            Some(ServerboundHandshakingPacket::Handshake(packet)) => {
+                assert_eq!(VarInt(2), packet.next_state)
+            }
+            None => panic!("expected handshake"),
+        }
+
+        reader.set_compression(Some(256));
+        reader.enable_encryption(&[1; 16]);
+
+        match reader.read_packet().await.unwrap() {
+            Some(ServerboundLoginPacket::LoginStart(packet)) => assert_eq!("jam", packet.name),
+            _ => panic!("expected login start"),
+        }
+
+        assert!(reader
+            .read_packet::<ServerboundLoginPacket>()
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,7 +1,19 @@
+#[cfg(test)]
+mod arbitrary;
 pub mod array;
+pub mod auth;
+pub mod capture;
 pub mod chat;
+pub mod codec;
+pub mod dissect;
 pub mod encoding;
+#[cfg(feature = "tokio")]
+pub mod framed;
 pub mod packet;
 pub mod position;
+#[cfg(test)]
+mod rng;
+pub mod session;
 pub mod state;
 pub mod varint;
+pub mod version;
@@ -1,29 +1,33 @@
+// Generated by protocol-codegen from minecraft-data's 1.8 protocol. Don't edit this file;
+// change protocol-codegen/data instead, and run `cargo run -p protocol-codegen`.
+
+use bytes::Bytes;
 use protocol_macro::PacketDef;
+use serde::Serialize;
 
-use crate::{chat::ChatComponent, varint::VarInt};
+use crate::{chat::ChatComponent, packet::packet_set, varint::VarInt};
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x00)]
 pub struct DisconnectPacket<'a> {
     pub reason: ChatComponent<'a>,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x01, versioned)]
 pub struct EncryptionRequestPacket {
     pub server_id: String,
 
-    /// Length of shared secret.
-    pub shared_secret_length: VarInt,
+    /// The public key of the server, encoded in ASN.1 DER.
+    pub public_key: Bytes,
 
-    // /// Shared secret.
-    // pub shared_secret: Vec<i8>,
-    /// Length of verify token.
-    pub verify_token_length: VarInt,
-    // /// Verify token.
-    // pub verify_token: Vec<i8>,
+    /// A sequence of random bytes generated by the server.
+    pub verify_token: Bytes,
 }
 
 /// This packet switches the connection state to play.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x02)]
 pub struct LoginSuccessPacket {
     /// Unlike in other packets, this field contains the UUID as a string with hyphens.
     pub uuid: String,
@@ -32,8 +36,19 @@ pub struct LoginSuccessPacket {
     pub username: String,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x03, since = "V1_8")]
 pub struct SetCompressionPacket {
     /// Maximum size of a packet before its compressed.
     pub threshold: VarInt,
 }
+
+packet_set! {
+    /// Every clientbound packet in the login state.
+    ClientboundLoginPacket<'a> (Login, Clientbound) {
+        Disconnect(DisconnectPacket<'a>),
+        EncryptionRequest(EncryptionRequestPacket),
+        LoginSuccess(LoginSuccessPacket),
+        SetCompression(SetCompressionPacket),
+    }
+}
@@ -1,3 +1,143 @@
+use anyhow::bail;
+use bytes::{Buf, BufMut};
+use serde::Serialize;
+
+use crate::{
+    dissect::Dissection,
+    encoding::Encode,
+    packet::{AnyStatePacket, PacketDirection, PacketSet},
+    state::State,
+    version::ProtocolVersion,
+};
+
 pub mod login;
 pub mod play;
 pub mod status;
+
+pub use login::ClientboundLoginPacket;
+pub use play::ClientboundPlayPacket;
+pub use status::ClientboundStatusPacket;
+
+/// A clientbound packet from any state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+pub enum ClientboundPacket<'a> {
+    Status(ClientboundStatusPacket),
+    Login(ClientboundLoginPacket<'a>),
+    Play(ClientboundPlayPacket<'a>),
+}
+
+impl<'a> AnyStatePacket for ClientboundPacket<'a> {
+    const DIRECTION: PacketDirection = PacketDirection::Clientbound;
+
+    fn state(&self) -> State {
+        match self {
+            Self::Status(_) => State::Status,
+            Self::Login(_) => State::Login,
+            Self::Play(_) => State::Play,
+        }
+    }
+
+    fn id(&self) -> i32 {
+        match self {
+            Self::Status(packet) => packet.id(),
+            Self::Login(packet) => packet.id(),
+            Self::Play(packet) => packet.id(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Status(packet) => packet.name(),
+            Self::Login(packet) => packet.name(),
+            Self::Play(packet) => packet.name(),
+        }
+    }
+
+    fn id_for(&self, version: ProtocolVersion) -> Option<i32> {
+        match self {
+            Self::Status(packet) => packet.id_for(version),
+            Self::Login(packet) => packet.id_for(version),
+            Self::Play(packet) => packet.id_for(version),
+        }
+    }
+
+    fn encode_for<B: BufMut + ?Sized>(
+        &self,
+        version: ProtocolVersion,
+        writer: &mut B,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Status(packet) => packet.encode_for(version, writer),
+            Self::Login(packet) => packet.encode_for(version, writer),
+            Self::Play(packet) => packet.encode_for(version, writer),
+        }
+    }
+
+    fn decode_by_state<B: Buf + ?Sized>(
+        version: ProtocolVersion,
+        state: State,
+        id: i32,
+        reader: &mut B,
+    ) -> anyhow::Result<Self> {
+        Ok(match state {
+            State::Status => Self::Status(PacketSet::decode_for(version, id, reader)?),
+            State::Login => Self::Login(PacketSet::decode_for(version, id, reader)?),
+            State::Play => Self::Play(PacketSet::decode_for(version, id, reader)?),
+            State::Handshaking | State::Closed => {
+                bail!("no clientbound packets exist in the {:?} state", state)
+            }
+        })
+    }
+
+    fn dissect_by_state(
+        version: ProtocolVersion,
+        state: State,
+        id: i32,
+        body: &[u8],
+    ) -> Option<(&'static str, Dissection)> {
+        match state {
+            State::Status => ClientboundStatusPacket::dissect_for(version, id, body),
+            State::Login => ClientboundLoginPacket::dissect_for(version, id, body),
+            State::Play => ClientboundPlayPacket::dissect_for(version, id, body),
+            State::Handshaking | State::Closed => None,
+        }
+    }
+}
+
+impl<'a> Encode for ClientboundPacket<'a> {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        match self {
+            Self::Status(packet) => packet.encode(writer),
+            Self::Login(packet) => packet.encode(writer),
+            Self::Play(packet) => packet.encode(writer),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            Self::Status(packet) => packet.encoded_len(),
+            Self::Login(packet) => packet.encoded_len(),
+            Self::Play(packet) => packet.encoded_len(),
+        }
+    }
+}
+
+impl From<ClientboundStatusPacket> for ClientboundPacket<'_> {
+    fn from(packet: ClientboundStatusPacket) -> Self {
+        Self::Status(packet)
+    }
+}
+
+impl<'a> From<ClientboundLoginPacket<'a>> for ClientboundPacket<'a> {
+    fn from(packet: ClientboundLoginPacket<'a>) -> Self {
+        Self::Login(packet)
+    }
+}
+
+impl<'a> From<ClientboundPlayPacket<'a>> for ClientboundPacket<'a> {
+    fn from(packet: ClientboundPlayPacket<'a>) -> Self {
+        Self::Play(packet)
+    }
+}
@@ -1,16 +1,25 @@
+// Generated by protocol-codegen from minecraft-data's 1.8 protocol. Don't edit this file;
+// change protocol-codegen/data instead, and run `cargo run -p protocol-codegen`.
+
+use bytes::Bytes;
 use protocol_macro::PacketDef;
+use serde::Serialize;
+use uuid::Uuid;
 
 use crate::{
+    chat::ChatComponent,
+    encoding::byte_array::Remaining,
+    packet::packet_set,
     position::{Angle, Position},
     varint::VarInt,
 };
-// use crate::chat::ChatComponent;
 
 /// The server will frequently send out a keep-alive, each containing a random ID. The client must
 /// respond with the same packet. If the client does not respond to them for over 30 seconds, the
 /// server kicks the client. Vice versa, if the server does not send any keep-alives for 20
 /// seconds, the client will disconnect and yields a "Timed out" exception.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x00, versioned)]
 pub struct KeepAlivePacket {
     /// The Random ID.
     pub id: VarInt,
@@ -18,14 +27,15 @@ pub struct KeepAlivePacket {
 
 /// Sends the state of the world the player is joining on on, to the player.
 ///
-/// # Gamemode Table:
+/// Gamemode Table:
 /// | ID | Gamemode  |
 /// |----|-----------|
 /// | 0  | Survival  |
 /// | 1  | Creative  |
 /// | 2  | Adventure |
 /// | 3  | Spectator |
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x01, since = "V1_8")]
 pub struct JoinGamePacket {
     /// The player's Entity ID (EID)
     pub entity_id: i32,
@@ -33,7 +43,7 @@ pub struct JoinGamePacket {
     /// ID of the gamemode.
     ///
     /// Bit 3 (0x8) is the hardcore flag.
-    pub gamemode: u16,
+    pub gamemode: u8,
 
     /// -1: Nether,
     /// 0: Overworld,
@@ -51,23 +61,25 @@ pub struct JoinGamePacket {
 
     /// default, flat, largeBiomes, amplified, default_1_1
     pub level_type: String,
-    // /// If true, a Notchian client shows reduced information on the debug screen.
-    // pub reduced_debug_info: bool,
-}
-
-// /// Identifying the difference between Chat/System Message is important as it helps respect the
-// /// user's chat visibility options. While Position 2 accepts json formatting it will not display,
-// /// old style formatting works.
-// #[derive(PacketDef)]
-// pub struct ChatMessagePacket<'a> {
-//     /// Limited to 32767 bytes
-//     pub data: ChatComponent<'a>,
-//
-//     /// 0: chat (chat box),
-//     /// 1: system message (chat box),
-//     /// 2: above hotbar.
-//     pub position: i8,
-// }
+
+    /// If true, a Notchian client shows reduced information on the debug screen.
+    pub reduced_debug_info: bool,
+}
+
+/// Identifying the difference between Chat/System Message is important as it helps respect the
+/// user's chat visibility options. While Position 2 accepts json formatting it will not display,
+/// old style formatting works.
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x02, since = "V1_8")]
+pub struct ChatMessagePacket<'a> {
+    /// Limited to 32767 bytes
+    pub message: ChatComponent<'a>,
+
+    /// 0: chat (chat box),
+    /// 1: system message (chat box),
+    /// 2: above hotbar.
+    pub position: i8,
+}
 
 /// Time is based on ticks, where 20 ticks happen every second. There are 24000 ticks in a day,
 /// making Minecraft days exactly 20 minutes long.
@@ -76,7 +88,8 @@ pub struct JoinGamePacket {
 /// sunset, and 18000 is midnight.
 ///
 /// The default SMP server increments the time by 20 every second.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x03)]
 pub struct TimeUpdatePacket {
     /// In ticks; not changed by server commands.
     pub world_age: i64,
@@ -87,7 +100,8 @@ pub struct TimeUpdatePacket {
 }
 
 /// Updates what an entity is holding in their hand.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x04, since = "V1_8")]
 pub struct EntityEquipmentPacket {
     /// Entity's EID.
     pub entity_id: VarInt,
@@ -99,14 +113,18 @@ pub struct EntityEquipmentPacket {
     ///                  3: chestplate,
     ///                  4: helmet.)
     pub slot: i16,
-    // /// Item in slot format.
-    // pub item: Slot,
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `item` (slot): Item in slot format.
+    pub rest: Remaining<Bytes>,
 }
 
 /// Sent by the server after login to specify the coordinates of the spawn point (the point at
 /// which players spawn at, and which the compass points to). It can be sent at any time to update
 /// the point compasses point at.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x05, since = "V1_8")]
 pub struct SpawnPositionPacket {
     /// Spawn location.
     pub location: Position,
@@ -116,7 +134,8 @@ pub struct SpawnPositionPacket {
 /// as a food “overcharge”. Food values will not decrease while the saturation is over zero.
 /// Players logging in automatically get a saturation of 5.0. Eating food increases the saturation
 /// as well as the food bar.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x06, since = "V1_8")]
 pub struct UpdateHealthPacket {
     /// 0 or less = dead,
     /// 20 = full HP.
@@ -133,7 +152,8 @@ pub struct UpdateHealthPacket {
 /// appropriate dimension, followed by prechunks/chunks for the new dimension, and finally a
 /// position and look packet. You do not need to unload chunks, the client will do it
 /// automatically.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x07)]
 pub struct RespawnPacket {
     /// -1: The Nether,
     /// 0: The Overworld,
@@ -176,7 +196,7 @@ pub struct RespawnPacket {
 ///
 /// The yaw and pitch of player (in degrees), standing at point (x0, y0, z0) and looking towards
 /// point (x, y, z) one can be calculated with:
-/// ```
+/// ```text
 /// dx = x-x0
 /// dy = y-y0
 /// dz = z-z0
@@ -188,12 +208,13 @@ pub struct RespawnPacket {
 /// ```
 ///
 /// You can get a unit vector from a given yaw/pitch via:
-/// ```
+/// ```text
 /// x = -cos(pitch) * sin(yaw)
 /// y = -sin(pitch)
 /// z =  cos(pitch) * cos(yaw)
 /// ```
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x08, since = "V1_8")]
 pub struct PlayerPositionAndLookPacket {
     /// Absolute or relative position, depending on Flags.
     pub x: f64,
@@ -209,23 +230,25 @@ pub struct PlayerPositionAndLookPacket {
 
     /// Absolute or relative rotation on the Y Axis, in degrees.
     pub pitch: f32,
-    // /// Bit field.
-    // ///
-    // /// ```
-    // /// <Dinnerbone> It's a bitfield, X/Y/Z/Y_ROT/X_ROT. If X is set, the x value is relative and not absolute.
-    // /// ```
-    // ///
-    // /// Field      Bit
-    // /// X          0x01
-    // /// Y          0x02
-    // /// Z          0x04
-    // /// Y_ROT      0x08
-    // /// X_ROT      0x10
-    // pub flags: BitField
+
+    /// Bit field.
+    ///
+    /// ```text
+    /// <Dinnerbone> It's a bitfield, X/Y/Z/Y_ROT/X_ROT. If X is set, the x value is relative and not absolute.
+    /// ```
+    ///
+    /// Field      Bit
+    /// X          0x01
+    /// Y          0x02
+    /// Z          0x04
+    /// Y_ROT      0x08
+    /// X_ROT      0x10
+    pub flags: i8,
 }
 
 /// Sent to change the player's slot selection.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x09)]
 pub struct HeldItemChangePacket {
     /// The slot which the player has selected (0–8.)
     pub slot: i8,
@@ -233,7 +256,8 @@ pub struct HeldItemChangePacket {
 
 /// This packet tells that a player goes to bed. The client with the matching Entity ID will go
 /// into bed mode. This Packet is sent to all nearby players including the one sent to bed.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0A, since = "V1_8")]
 pub struct UseBedPacket {
     /// Sleeping player's EID.
     pub entity_id: VarInt,
@@ -243,7 +267,8 @@ pub struct UseBedPacket {
 }
 
 /// Sent whenever an entity should change animation.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0B)]
 pub struct AnimationPacket {
     /// Player ID.
     pub entity_id: VarInt,
@@ -282,13 +307,15 @@ pub struct AnimationPacket {
 ///
 /// In an example UUID, `xxxxxxxx-xxxx-Yxxx-xxxx-xxxxxxxxxxxx`, the UUID version is specified by Y.
 /// So, for UUID v3, Y will always be 3, and for UUID v2, Y will always be 2.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0C, since = "V1_8")]
 pub struct SpawnPlayerPacket {
     /// Player's EID.
     pub entity_id: VarInt,
 
-    // /// The UUID of the player.
-    // pub uuid: UUID,
+    /// The UUID of the player.
+    pub uuid: Uuid,
+
     /// Player X as a Fixed-Point number.
     pub x: i32,
 
@@ -298,23 +325,28 @@ pub struct SpawnPlayerPacket {
     /// Player Z as a Fixed-Point number.
     pub z: i32,
 
-    // /// Player rotation on the X Axis.
-    // pub yaw: Angle,
+    /// Player rotation on the X Axis.
+    pub yaw: Angle,
+
+    /// Player rotation on the Y Axis.
+    pub pitch: Angle,
 
-    // /// Player rotation on the Y Axis.
-    // pub pitch: Angle,
     /// The item the player is currently holding. Note that this should be 0 for “no item”, unlike
     /// -1 used in other packets.
     pub current_item: i16,
-    // /// The entity metadata.
-    // pub metadata: Metadata
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `metadata` (entityMetadata): The entity metadata.
+    pub rest: Remaining<Bytes>,
 }
 
 /// Sent by the server when someone picks up an item lying on the ground — its sole purpose appears
 /// to be the animation of the item flying towards you. It doesn't destroy the entity in the client
 /// memory, and it doesn't add it to your inventory. The server only checks for items to be picked
 /// up after each Player Position (and Player Position And Look) packet sent by the client.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0D, since = "V1_8")]
 pub struct CollectItemPacket {
     /// EID of the item being collected.
     pub collected_entity_id: VarInt,
@@ -324,7 +356,8 @@ pub struct CollectItemPacket {
 }
 
 /// Sent by the server when a vehicle or other object is created.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0E)]
 pub struct SpawnObjectPacket {
     /// EID of the object.
     pub entity_id: VarInt,
@@ -341,25 +374,21 @@ pub struct SpawnObjectPacket {
     /// Z position as a Fixed-Point number.
     pub z: i32,
 
-    // /// Player rotation on the Y Axis.
-    // pub pitch: Angle,
-
-    // /// Player rotation on the X Axis.
-    // pub yaw: Angle,
-    /// Meaning dependent on the value of the Type field, see Object Data for details.
-    pub data: i32,
-    // /// Velocity on the X axis. Only sent if the Data field is nonzero.
-    // pub velocity_x: Option<i16>,
+    /// Player rotation on the Y Axis.
+    pub pitch: Angle,
 
-    // /// Velocity on the Y axis. Only sent if the Data field is nonzero.
-    // pub velocity_y: Option<i16>,
+    /// Player rotation on the X Axis.
+    pub yaw: Angle,
 
-    // /// Velocity on the Z axis. Only sent if the Data field is nonzero.
-    // pub velocity_z: Option<i16>,
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `data` (container): Meaning dependent on the value of the Type field, see Object Data for details.
+    pub rest: Remaining<Bytes>,
 }
 
 /// Sent by the server when a vehicle or other object is created.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0F)]
 pub struct SpawnMobPacket {
     /// EID of the object.
     pub entity_id: VarInt,
@@ -376,14 +405,15 @@ pub struct SpawnMobPacket {
     /// Z position as a Fixed-Point number
     pub z: i32,
 
-    // /// Player rotation on the X Axis.
-    // pub yaw: Angle,
+    /// Player rotation on the X Axis.
+    pub yaw: Angle,
+
+    /// Player rotation on the Y Axis.
+    pub pitch: Angle,
 
-    // /// Player rotation on the Y Axis.
-    // pub pitch: Angle,
+    /// Head rotation on the Y Axis.
+    pub head_pitch: Angle,
 
-    // /// Head rotation on the Y Axis.
-    // pub head_pitch: Angle,
     /// Velocity on the X Axis.
     pub velocity_x: i16,
 
@@ -392,19 +422,23 @@ pub struct SpawnMobPacket {
 
     /// Velocity on the Z Axis.
     pub velocity_z: i16,
-    // /// Entity metadata.
-    // pub metadata: Metadata,
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `metadata` (entityMetadata): Entity metadata.
+    pub rest: Remaining<Bytes>,
 }
 
 /// This packet shows location, name, and type of painting.
 ///
 /// Calculating the center of an image: given a (width x height) grid of cells, with (0, 0) being
 /// the top left corner, the center is (max(0, width / 2 - 1), height / 2). E.g.
-/// ```
+/// ```text
 /// 2x1 (1, 0)
 /// 4x4 (1, 2)
 /// ```
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x10, since = "V1_8")]
 pub struct SpawnPaintingPacket {
     /// EID of the entity.
     pub entity_id: VarInt,
@@ -427,7 +461,8 @@ pub struct SpawnPaintingPacket {
 }
 
 /// Spawns one or more experience orbs.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x11)]
 pub struct SpawnExperienceOrbPacket {
     /// EID of the entity.
     pub entity_id: VarInt,
@@ -447,7 +482,8 @@ pub struct SpawnExperienceOrbPacket {
 
 /// Velocity is believed to be in units of 1/8000 of a block per server tick (50ms); for example,
 /// -1343 would move (-1343 / 8000) = −0.167875 blocks per tick (or −3,3575 blocks per second).
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x12, since = "V1_8")]
 pub struct EntityVelocityPacket {
     /// EID of the entity.
     pub entity_id: VarInt,
@@ -463,12 +499,11 @@ pub struct EntityVelocityPacket {
 }
 
 /// Sent by the server when a list of entities is to be destroyed on the client.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x13, since = "V1_8")]
 pub struct DestroyEntitiesPacket {
-    /// Number of elements in the following array.
-    pub count: VarInt,
-    // /// The list of entities of destroy.
-    // pub entities_id: Vec<VarInt>,
+    /// The entities to destroy.
+    pub entity_ids: Vec<VarInt>,
 }
 
 /// This packet may be used to initialize an entity.
@@ -476,7 +511,8 @@ pub struct DestroyEntitiesPacket {
 /// For player entities, either this packet or any move/look packet is sent every game tick. So the
 /// meaning of this packet is basically that the entity did not move/look since the last such
 /// packet.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x14, since = "V1_8")]
 pub struct EntityPacket {
     /// EID of the Entity.
     pub entity_id: VarInt,
@@ -487,7 +523,8 @@ pub struct EntityPacket {
 ///
 /// This packet allows at most four blocks movement in any direction, because byte range is from
 /// -128 to 127.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x15, since = "V1_8")]
 pub struct EntityRelativeMovePacket {
     /// EID of the Entity.
     pub entity_id: VarInt,
@@ -500,29 +537,33 @@ pub struct EntityRelativeMovePacket {
 
     /// Change in Z position as a Fixed-Point number
     pub delta_z: i8,
-    // /// Whether the player is touching the ground or not.
-    // pub on_ground: bool,
+
+    /// Whether the player is touching the ground or not.
+    pub on_ground: bool,
 }
 
 /// This packet is sent by the server when an entity rotates.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x16, since = "V1_8")]
 pub struct EntityLookPacket {
     /// The EID of the Entity.
     pub entity_id: VarInt,
-    // /// New angle, not a delta.
-    // pub yaw: Angle,
 
-    // /// New angle, not a delta.
-    // pub pitch: Angle,
+    /// New angle, not a delta.
+    pub yaw: Angle,
+
+    /// New angle, not a delta.
+    pub pitch: Angle,
 
-    // /// Whether the player is touching the ground or not.
-    // pub on_ground: bool,
+    /// Whether the player is touching the ground or not.
+    pub on_ground: bool,
 }
 
 /// This packet is sent by the server when an entity rotates and moves. Since a byte range is
 /// limited from -128 to 127, and movement is offset of fixed-point numbers, this packet allows at
 /// most four blocks movement in any direction. (-128/32 == -4)
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x17, since = "V1_8")]
 pub struct EntityLookAndRelativeMovePacket {
     /// The EID of the Entity.
     pub entity_id: VarInt,
@@ -535,42 +576,46 @@ pub struct EntityLookAndRelativeMovePacket {
 
     /// Change in Z position as a Fixed-Point number.
     pub delta_z: i8,
-    // /// New angle, not a delta.
-    // pub yaw: Angle,
 
-    // /// New angle, not a delta.
-    // pub pitch: Angle,
+    /// New angle, not a delta.
+    pub yaw: Angle,
 
-    // /// Whether the player is touching the ground or not.
-    // pub on_ground: bool,
+    /// New angle, not a delta.
+    pub pitch: Angle,
+
+    /// Whether the player is touching the ground or not.
+    pub on_ground: bool,
 }
 
 /// This packet is sent by the server when an entity moves more than 4 blocks.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x18, since = "V1_8")]
 pub struct EntityTeleportPacket {
     /// The EID of the Entity.
     pub entity_id: VarInt,
 
     /// Player X as a Fixed-Point number.
-    pub x: i8,
+    pub x: i32,
 
     /// Player Y as a Fixed-Point number.
-    pub y: i8,
+    pub y: i32,
 
     /// Player Z as a Fixed-Point number.
-    pub z: i8,
-    // /// New angle, not a delta
-    // pub yaw: Angle,
+    pub z: i32,
 
-    // /// New angle, not a delta
-    // pub pitch: Angle,
+    /// New angle, not a delta
+    pub yaw: Angle,
 
-    // /// Whether the player is touching the ground or not.
-    // pub on_ground: bool,
+    /// New angle, not a delta
+    pub pitch: Angle,
+
+    /// Whether the player is touching the ground or not.
+    pub on_ground: bool,
 }
 
 /// Changes the direction an entity's head is facing.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x19, since = "V1_8")]
 pub struct EntityHeadLookPacket {
     /// The EID of the Entity.
     pub entity_id: VarInt,
@@ -581,7 +626,7 @@ pub struct EntityHeadLookPacket {
 
 /// Updates an Entity's status.
 ///
-/// # Entity Status Table
+/// Entity Status Table
 /// | Entity Status | Meaning
 /// |---------------|--------------------------------------------------------------------------|
 /// | 1             | Sent when resetting a mob spawn minecart's timer / Rabbit jump animation |
@@ -607,8 +652,8 @@ pub struct EntityHeadLookPacket {
 /// | 21            | Play guardian sound — works for only for guardians                       |
 /// | 22            | Enables reduced debug for players                                        |
 /// | 23            | Disables reduced debug for players
-
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x1A)]
 pub struct EntityStatusPacket {
     /// The EID of the Entity.
     pub entity_id: i32,
@@ -618,28 +663,35 @@ pub struct EntityStatusPacket {
 }
 
 /// This packet is sent when a player has been attached to an entity (e.g. Minecart.)
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x1B)]
 pub struct AttachEntityPacket {
     /// Attached Entity's EID.
     pub entity_id: i32,
 
     /// EID of the Vehicle. Set to -1 to detach.
     pub vehicle_id: i32,
-    // /// If true leashes the entity to the vehicle.
-    // pub leash: bool,
+
+    /// If true leashes the entity to the vehicle.
+    pub leash: bool,
 }
 
 /// Updates one or more metadata properties for an existing entity. Any properties not included in
 /// the Metadata field are left unchanged.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x1C, since = "V1_8")]
 pub struct EntityMetadataPacket {
     /// EID of the Entity.
     pub entity_id: VarInt,
-    // /// Metadata of Entity.
-    // pub metadata: Metadata
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `metadata` (entityMetadata): Metadata of Entity.
+    pub rest: Remaining<Bytes>,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x1D, since = "V1_8")]
 pub struct EntityEffectPacket {
     /// EID of the Entity.
     pub entity_id: VarInt,
@@ -652,11 +704,13 @@ pub struct EntityEffectPacket {
 
     /// Duration in seconds.
     pub duration: VarInt,
-    // /// Whether particles should be hidden or not.
-    // pub hide_particles: bool,
+
+    /// Whether particles should be hidden or not.
+    pub hide_particles: bool,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x1E, since = "V1_8")]
 pub struct RemoveEntityEffectPacket {
     /// EID of the Entity.
     pub entity_id: VarInt,
@@ -666,7 +720,8 @@ pub struct RemoveEntityEffectPacket {
 }
 
 /// Sent by the server when the client should change experience levels.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x1F, since = "V1_8")]
 pub struct SetExperiencePacket {
     /// Between 0 and 1.
     pub bar: f32,
@@ -681,7 +736,7 @@ pub struct SetExperiencePacket {
 
 /// Sets attributes on the given entity.
 ///
-/// # Property Table
+/// Property Table
 /// | Key                              Default              Min          Max                  Label                       |
 /// |--------------------------------|--------------------|------------|--------------------|-----------------------------|
 /// | generic.maxHealth              | 20.0               | 0.0        | Double.MaxValue    | Max Health                  |
@@ -691,14 +746,16 @@ pub struct SetExperiencePacket {
 /// | generic.attackDamage           | 2.0                | 0.0        | Double.MaxValue    |                             |
 /// | horse.jumpStrength             | 0.7                | 0.0        | 2.0                | Jump Strength               |
 /// | zombie.spawnReinforcements     | 0.0                | 0.0        | 1.0                | Spawn Reinforcements Chance |
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x20, since = "V1_8")]
 pub struct EntityPropertiesPacket {
     /// EID of the Entity.
     pub entity_id: VarInt,
 
-    /// Number of elements in the following array.
-    pub properties_amount: i32,
-    // TODO: ??
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `properties` (array)
+    pub rest: Remaining<Bytes>,
 }
 
 /// Chunks are not unloaded by the client automatically. To unload chunks, send this packet with
@@ -706,7 +763,8 @@ pub struct EntityPropertiesPacket {
 /// skylight information for nether-chunks, it's up to the client to know if the player is
 /// currently in the nether. You can also infer this information from the primary bitmask and the
 /// amount of uncompressed bytes sent.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x21, since = "V1_8")]
 pub struct ChunkDataPacket {
     /// Chunk X coordinate.
     pub chunk_x: i32,
@@ -714,19 +772,20 @@ pub struct ChunkDataPacket {
     /// Chunk Z coordinate.
     pub chunk_z: i32,
 
-    ///// This is true if the packet represents all sections in this vertical column, where the
-    ///// Primary Bit Mask specifies exactly which sections are included, and which are air
-    // pub ground_up_continuous: bool,
+    /// // This is true if the packet represents all sections in this vertical column, where the
+    /// // Primary Bit Mask specifies exactly which sections are included, and which are air
+    pub ground_up_continuous: bool,
+
     /// Bitmask with 1 for every 16x16x16 section whose data follows in the compressed data.
     pub primary_bit_mask: u16,
 
-    /// Size of Data.
-    pub size: VarInt,
-    // pub data: Chunk
+    /// Chunk data, prefixed with its size. Decoding from a `Bytes` frame doesn't copy it.
+    pub data: Bytes,
 }
 
 /// Fired whenever 2 or more blocks are changed within the render distance.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x22, since = "V1_8")]
 pub struct MultiBlockChangePacket {
     /// Chunk X coordinate.
     pub chunk_x: i32,
@@ -734,13 +793,15 @@ pub struct MultiBlockChangePacket {
     /// Chunk Z coordinate.
     pub chunk_z: i32,
 
-    /// Number of elements in the following array, i.e. the number of blocks affected.
-    pub record_count: VarInt,
-    // TODO: ??
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `records` (array)
+    pub rest: Remaining<Bytes>,
 }
 
 /// Fired whenever a block is changed within the render distance.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x23, since = "V1_8")]
 pub struct BlockChangePacket {
     /// Block Coordinates.
     pub location: Position,
@@ -756,7 +817,8 @@ pub struct BlockChangePacket {
 /// - Pistons pushing and pulling
 /// - Note blocks playing
 /// - Updating beacons
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x24, since = "V1_8")]
 pub struct BlockActionPacket {
     /// Block coordinates.
     pub location: Position,
@@ -782,7 +844,8 @@ pub struct BlockActionPacket {
 /// Also if you set the coordinates to a special block like water etc. it won't show the actual
 /// break animation but some other interesting effects. For example, water will lose its
 /// transparency.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x25, since = "V1_8")]
 pub struct BlockBreakAnimationPacket {
     /// EID for the animation.
     pub entity_id: VarInt,
@@ -798,22 +861,25 @@ pub struct BlockBreakAnimationPacket {
 ///
 /// To reduce the number of bytes, this packet is used to send chunks together for better
 /// compression results.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x26, since = "V1_8")]
 pub struct MapChunkBulkPacket {
-    // /// Whether or not Chunk Data contains light nibble arrays. This is true in the Overworld,
-    // /// false in the End + Nether.
-    // pub sky_light_sent: bool,
-    pub column_count: VarInt,
-    // TODO: ??
+    /// Whether or not Chunk Data contains light nibble arrays. This is true in the Overworld,
+    /// false in the End + Nether.
+    pub sky_light_sent: bool,
 
-    // /// Each chunk in this array corresponds to the data at the same position in Chunk Meta.
-    // pub chunk_data: Vec<Chunk>
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `meta` (array)
+    /// - `data` (restBuffer): Each chunk in this array corresponds to the data at the same position in Chunk Meta.
+    pub rest: Remaining<Bytes>,
 }
 
 /// Sent when an explosion occurs (creepers, TNT, and ghast fireballs).
 ///
 /// Each block in Records is set to air. Coordinates for each axis in record is int(X) + record.x
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x27)]
 pub struct ExplosionPacket {
     /// Explosion location on the X Axis.
     pub x: f32,
@@ -827,21 +893,14 @@ pub struct ExplosionPacket {
     /// Currently unused in the client.
     pub radius: f32,
 
-    /// Number of elements in the following array.
-    pub record_count: i32,
-
-    /// Each record is 3 signed bytes long, each bytes are the XYZ (respectively) offsets of
-    /// affected blocks.
-    // pub records: Vec<(i8, i8, i8)>,
-
-    /// X velocity of the player being pushed by the explosion
-    pub player_motion_x: f32,
-
-    /// Y velocity of the player being pushed by the explosion
-    pub player_motion_y: f32,
-
-    /// Z velocity of the player being pushed by the explosion
-    pub player_motion_z: f32,
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `records` (array): Each record is 3 signed bytes long, each bytes are the XYZ (respectively) offsets of
+    ///   affected blocks.
+    /// - `player_motion_x` (f32): X velocity of the player being pushed by the explosion
+    /// - `player_motion_y` (f32): Y velocity of the player being pushed by the explosion
+    /// - `player_motion_z` (f32): Z velocity of the player being pushed by the explosion
+    pub rest: Remaining<Bytes>,
 }
 
 /// Sent when a client is to play a sound or particle effect.
@@ -850,7 +909,8 @@ pub struct ExplosionPacket {
 /// final boolean field is used to disable this, and instead the effect is played from 2 blocks
 /// away in the correct direction. Currently this is only used for effect 1013 (mob.wither.spawn),
 /// and is ignored for any other value by the client.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x28, since = "V1_8")]
 pub struct EffectPacket {
     /// The ID of the effect.
     pub effect_id: i32,
@@ -860,13 +920,15 @@ pub struct EffectPacket {
 
     /// Extra data for certain effects, see below.
     pub data: i32,
-    // pub disable_relative_volume: bool,
+
+    pub disable_relative_volume: bool,
 }
 
 /// Used to play a sound effect on the client.
 ///
 /// Custom sounds may be added by resource packs.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x29)]
 pub struct SoundEffectPacket {
     /// All known sound effect names can be seen here:
     /// https://github.com/SirCmpwn/Craft.Net/blob/master/source/Craft.Net.Common/SoundEffect.cs
@@ -889,13 +951,15 @@ pub struct SoundEffectPacket {
 }
 
 /// Displays the named particle.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x2A, since = "V1_8")]
 pub struct ParticlePacket {
     /// The ID of the Particle.
     pub particle_id: i32,
 
-    // /// If true, particle distance increases from 256 to 65536.
-    // pub long_distance: bool,
+    /// If true, particle distance increases from 256 to 65536.
+    pub long_distance: bool,
+
     /// X position of the particle.
     pub x: f32,
 
@@ -919,14 +983,17 @@ pub struct ParticlePacket {
 
     /// The number of particles to create.
     pub particle_count: i32,
-    // /// Length depends on particle. "iconcrack" has length of 2, "blockcrack", and "blockdust" have
-    // /// lengths of 1, the rest have 0.
-    // pub data: Vec<VarInt>,
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `data` (switch): Length depends on particle. "iconcrack" has length of 2, "blockcrack", and "blockdust" have
+    ///   lengths of 1, the rest have 0.
+    pub rest: Remaining<Bytes>,
 }
 
 /// It appears when a bed can't be used as a spawn point and when the rain state changes.
 ///
-/// # Reason codes Table
+/// Reason codes Table
 /// | ID | Reason                                 | Note                                                                                                                     |
 /// |---------------------------------------------|--------------------------------------------------------------------------------------------------------------------------|
 /// | 0  | Invalid Bed                            |                                                                                                                          |
@@ -939,7 +1006,8 @@ pub struct ParticlePacket {
 /// | 7  | Fade value                             | The current darkness value. 1 = Dark, 0 = Bright, Setting the value higher causes the game to change color and freeze    |
 /// | 8  | Fade time                              | Time in ticks for the sky to fade                                                                                        |
 /// | 10 | Play mob appearance (effect and sound) | Unknown                                                                                                                  |
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x2B)]
 pub struct ChangeGameStatePacket {
     /// Reason code.
     pub reason: u8,
@@ -950,7 +1018,8 @@ pub struct ChangeGameStatePacket {
 
 /// With this packet, the server notifies the client of thunderbolts striking within a 512 block
 /// radius around the player. The coordinates specify where exactly the thunderbolt strikes.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x2C)]
 pub struct SpawnGlobalEntityPacket {
     /// The EID of the thunderbolt.
     pub entity_id: VarInt,
@@ -968,40 +1037,45 @@ pub struct SpawnGlobalEntityPacket {
     pub z: i32,
 }
 
-// /// This is sent to the client when it should open an inventory, such as a chest, workbench, or
-// /// furnace. This message is not sent anywhere for clients opening their own inventory.
-// #[derive(PacketDef)]
-// pub struct OpenWindowPacket<'a> {
-//     /// A unique id number for the window to be displayed. Notchian server implementation is a
-//     /// counter, starting at 1.
-//     pub id: u8,
-//
-//     /// The window type to use for display. See Inventory for a list.
-//     pub type_: String,
-//
-//     /// The title of the window.
-//     pub title: ChatComponent<'a>,
-//
-//     /// Number of slots in the window (excluding the number of slots in the player inventory).
-//     pub slots: u8,
-//
-//     /// EntityHorse's EID. Only sent when Window Type is “EntityHorse".
-//     pub entity_id: Option<i32>,
-// }
+/// This is sent to the client when it should open an inventory, such as a chest, workbench, or
+/// furnace. This message is not sent anywhere for clients opening their own inventory.
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x2D, since = "V1_8")]
+pub struct OpenWindowPacket<'a> {
+    /// A unique id number for the window to be displayed. Notchian server implementation is a
+    /// counter, starting at 1.
+    pub window_id: u8,
+
+    /// The window type to use for display. See Inventory for a list.
+    pub inventory_type: String,
+
+    /// The title of the window.
+    pub window_title: ChatComponent<'a>,
+
+    /// Number of slots in the window (excluding the number of slots in the player inventory).
+    pub slot_count: u8,
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `entity_id` (switch): EntityHorse's EID. Only sent when Window Type is "EntityHorse".
+    pub rest: Remaining<Bytes>,
+}
 
 /// This packet is sent from the server to the client when a window is forcibly closed, such as
 /// when a chest is destroyed while it's open.
 ///
 /// Note, notchian clients send a close window packet with Window ID 0 to close their inventory
 /// even though there is never an Open Window packet for inventory.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x2E)]
 pub struct CloseWindowPacket {
     /// This is the ID of the window that was closed. 0 for inventory.
     pub id: u8,
 }
 
 /// Sent by the server when an item in a slot (in a window) is added/removed.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x2F)]
 pub struct SetSlotPacket {
     /// The window which is being updated. 0 for player inventory. Note that all known window types
     /// include the player inventory. This packet will only be sent for the currently opened window
@@ -1012,25 +1086,32 @@ pub struct SetSlotPacket {
 
     /// The slot that should be updated.
     pub slot: i16,
-    // pub data: Slot
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `data` (slot)
+    pub rest: Remaining<Bytes>,
 }
 
 /// Sent by the server when items in multiple slots (in a window) are added/removed. This includes
 /// the main inventory, equipped armour and crafting slots.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x30)]
 pub struct WindowItemsPacket {
     /// The ID of window which items are being sent for. 0 for player inventory.
     pub id: u8,
 
-    /// Number of elements in the following array.
-    pub count: i16,
-    // pub data: Vec<Slot>
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `items` (array)
+    pub rest: Remaining<Bytes>,
 }
 
 /// This packet is used to inform the client that part of a GUI window should be updated.
 ///
 /// TODO: add table
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x31)]
 pub struct WindowPropertyPacket {
     /// The ID of a window.
     pub id: u8,
@@ -1044,70 +1125,63 @@ pub struct WindowPropertyPacket {
 
 /// A packet from the server indicating whether a request from the client was accepted, or whether
 /// there was a conflict (due to lag).
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x32, since = "V1_8")]
 pub struct ConfirmTransactionPacket {
     /// The ID of the window that the action occurred in.
     pub id: i8,
 
     /// Every action that is to be accepted has a unique number. This field corresponds to that number.
     pub action_number: i16,
-    // /// Whether the action was accepted.
-    // pub accepted: bool,
+
+    /// Whether the action was accepted.
+    pub accepted: bool,
 }
 
 /// This message is sent from the server to the client whenever a sign is discovered or created.
 /// This message is NOT sent when a sign is destroyed or unloaded.
-// #[derive(PacketDef)]
-// pub struct UpdateSignPacket<'a> {
-//     /// Location of the sign.
-//     pub location: Position,
-//
-//     /// First line of text in the sign.
-//     pub line1: ChatComponent<'a>,
-//
-//     /// Second line of text in the sign.
-//     pub line2: ChatComponent<'a>,
-//
-//     /// Third line of text in the sign.
-//     pub line3: ChatComponent<'a>,
-//
-//     /// Fourth line of text in the sign.
-//     pub line4: ChatComponent<'a>,
-// }
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x33, since = "V1_8")]
+pub struct UpdateSignPacket<'a> {
+    /// Location of the sign.
+    pub location: Position,
+
+    /// First line of text in the sign.
+    pub text1: ChatComponent<'a>,
+
+    /// Second line of text in the sign.
+    pub text2: ChatComponent<'a>,
+
+    /// Third line of text in the sign.
+    pub text3: ChatComponent<'a>,
+
+    /// Fourth line of text in the sign.
+    pub text4: ChatComponent<'a>,
+}
 
 /// Updates a rectangular area on a map.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x34, since = "V1_8")]
 pub struct MapPacket {
     /// The damage value (map ID) of the map being modified.
     pub item_damage: VarInt,
 
     pub scale: i8,
 
-    /// Number of elements in the following array.
-    pub icon_amount: VarInt,
-
-    // TODO: ??
-    /// Number of columns updated.
-    pub column: i8,
-    // /// Only if Columns is more than 0; number of rows updated.
-    // pub rows: Option<i8>,
-
-    // /// Only if Columns is more than 0; x offset of the westernmost column.
-    // pub x: Option<i8>,
-
-    // /// Only if Columns is more than 0; z offset of the northernmost row.
-    // pub z: Option<i8>,
-
-    // /// Only if Columns is more than 0; length of the following array.
-    // pub length: Option<VarInt>,
-
-    // /// Only if Columns is more than 0; see Map item format.
-    // pub data: Option<Vec<u8>>,
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `icons` (array): The icons shown on the map.
+    /// - `columns` (i8): Number of columns updated.
+    /// - `rows` (switch): Only if Columns is more than 0; number of rows updated.
+    /// - `x` (switch): Only if Columns is more than 0; x offset of the westernmost column.
+    /// - `y` (switch): Only if Columns is more than 0; z offset of the northernmost row.
+    /// - `data` (switch): Only if Columns is more than 0; see Map item format.
+    pub rest: Remaining<Bytes>,
 }
 
 /// Essentially a block update on a block entity.
 ///
-/// # Action Table                                                          |
+/// Action Table                                                          |
 /// | Action | Description                                                  |
 /// |--------|--------------------------------------------------------------|
 /// |    1   | Set SpawnPotentials of a mob spawner                         |
@@ -1116,48 +1190,58 @@ pub struct MapPacket {
 /// |    4   | Set rotation and skin of mob head                            |
 /// |    5   | Set type of flower in flower pot                             |
 /// |    6   | Set base color and patterns on a banner                      |
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x35, since = "V1_8")]
 pub struct UpdateBlockEntityPacket {
     /// TODO: think what to write here
     pub location: Position,
 
     /// The type of update to perform, see below.
     pub action: u8,
-    // /// If not present then it's a TAG_END (0)
-    // pub data: NBTTag
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `data` (optionalNbt): If not present then it's a TAG_END (0)
+    pub rest: Remaining<Bytes>,
 }
 
 /// Sent when the client has placed a sign and is allowed to send Update Sign.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x36, since = "V1_8")]
 pub struct OpenSignEditorPacket {
     /// TODO: think what to write here
     pub location: Position,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x37)]
 pub struct StatisticsPacket {
-    /// Number of elements in the following array.
-    pub count: VarInt,
-    // TODO: ??
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `entries` (array)
+    pub rest: Remaining<Bytes>,
 }
 
 /// Sent by the notchian server to update the user list (<tab> in the client.)
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x38, since = "V1_8")]
 pub struct PlayerListItemPacket {
     /// Determines the rest of the Player format after the UUID.
     pub action: VarInt,
 
-    /// Number of elements in the following array.
-    pub players_amount: VarInt,
-    // TODO: ??
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `data` (array)
+    pub rest: Remaining<Bytes>,
 }
 
 /// The latter 2 floats are used to indicate the field of view and flying speed respectively, while
 /// the first byte is used to determine the value of 4 booleans.
 ///
-/// # Flags
+/// Flags
 /// TODO
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x39)]
 pub struct PlayerAbilitiesPacket {
     /// Bit field.
     pub flags: i8,
@@ -1171,47 +1255,53 @@ pub struct PlayerAbilitiesPacket {
 
 /// The server responds with a list of auto-completions of the last word sent to it. In the case of
 /// regular chat, this is a player username. Command names and parameters are also supported.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x3A)]
 pub struct TabCompletePacket {
-    /// Number of elements in the following array.
-    pub flags: VarInt,
-    // /// One eligible command, note that each command is sent separately instead of in a single
-    // /// string, hence the need for Count
-    // pub matches: Vec<String>,
+    /// One eligible command, note that each command is sent separately instead of in a single
+    /// string, hence the need for Count
+    pub matches: Vec<String>,
 }
 
 /// This is sent to the client when it should create a new scoreboard objective or remove one.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x3B, since = "V1_8")]
 pub struct ScoreboardObjectivePacket {
     /// An unique name for the objective.
     pub name: String,
 
     /// 0 to create the scoreboard. 1 to remove the scoreboard. 2 to update the display text.
     pub mode: i8,
-    // /// Only if mode is 0 or 2. The text to be displayed for the score.
-    // pub value: Option<String>,
 
-    // /// Only if mode is 0 or 2. “integer” or “hearts”
-    // pub type_: Option<String>,
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `value` (switch): Only if mode is 0 or 2. The text to be displayed for the score.
+    /// - `type_` (switch): Only if mode is 0 or 2. “integer” or “hearts”
+    pub rest: Remaining<Bytes>,
 }
 
 /// This is sent to the client when it should update a scoreboard item.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x3C, since = "V1_8")]
 pub struct UpdateScorePacket {
     /// The name of the score to be updated or removed.
     pub name: String,
 
     /// 0 to create/update an item. 1 to remove an item.
-    pub action: i8,
+    pub action: VarInt,
 
     /// The name of the objective the score belongs to.
     pub objective_name: String,
-    // /// The score to be displayed next to the entry. Only sent when Action does not equal 1.
-    // pub value: Option<VarInt>,
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `value` (switch): The score to be displayed next to the entry. Only sent when Action does not equal 1.
+    pub rest: Remaining<Bytes>,
 }
 
 /// This is sent to the client when it should display a scoreboard.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x3D)]
 pub struct DisplayScoreboardPacket {
     /// The position of the scoreboard. 0: list, 1: sidebar, 2: below name.
     pub position: i8,
@@ -1221,7 +1311,8 @@ pub struct DisplayScoreboardPacket {
 }
 
 /// Creates and updates teams.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x3E, since = "V1_8")]
 pub struct TeamPacket {
     /// A unique name for the team. (Shared with scoreboard).
     pub name: String,
@@ -1232,30 +1323,18 @@ pub struct TeamPacket {
     /// If 3 then new players are added to the team.
     /// If 4 then players are removed from the team.
     pub mode: i8,
-    // /// Only if Mode = 0 or 2.
-    // pub display_name: Option<String>,
-
-    // /// Only if Mode = 0 or 2. Displayed before the players' name that are part of this team.
-    // pub prefix: Option<String>,
-
-    // /// Only if Mode = 0 or 2. Displayed after the players' name that are part of this team.
-    // pub suffix: Option<String>,
 
-    // /// Only if Mode = 0 or 2. 0 for off, 1 for on, 3 for seeing friendly invisibles.
-    // pub friendly_fire: Option<i8>,
-
-    // /// Only if Mode = 0 or 2. always, hideForOtherTeams, hideForOwnTeam, never.
-    // pub name_tag_visibility: Option<String>,
-
-    // /// Only if Mode = 0 or 2. Same as Chat colors.
-    // pub color: Option<i8>,
-
-    // /// Only if Mode = 0 or 3 or 4. Number of players in the array.
-    // pub players_amount: Option<VarInt>,
-
-    // /// Only if Mode = 0 or 3 or 4. Players to be added/remove from the team. Max 40 characters so
-    // /// may be uuid's later.
-    // pub players: Option<String>,
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `display_name` (switch): Only if Mode = 0 or 2.
+    /// - `prefix` (switch): Only if Mode = 0 or 2. Displayed before the players' name that are part of this team.
+    /// - `suffix` (switch): Only if Mode = 0 or 2. Displayed after the players' name that are part of this team.
+    /// - `friendly_fire` (switch): Only if Mode = 0 or 2. 0 for off, 1 for on, 3 for seeing friendly invisibles.
+    /// - `name_tag_visibility` (switch): Only if Mode = 0 or 2. always, hideForOtherTeams, hideForOwnTeam, never.
+    /// - `color` (switch): Only if Mode = 0 or 2. Same as Chat colors.
+    /// - `players` (switch): Only if Mode = 0 or 3 or 4. Players to be added/remove from the team. Max 40 characters so
+    ///   may be uuid's later.
+    pub rest: Remaining<Bytes>,
 }
 
 /// Mods and plugins can use this to send their data. Minecraft itself uses a number of plugin
@@ -1263,43 +1342,49 @@ pub struct TeamPacket {
 ///
 /// More documentation on this:
 /// http://dinnerbone.com/blog/2012/01/13/minecraft-plugin-channels-messaging/
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x3F, since = "V1_8")]
 pub struct PluginMessagePacket {
     /// Name of the plugin channel used to send the data.
     pub channel: String,
-    // /// Any data, depending on the channel. MC| channels are documented here.
-    // pub data: Vec<i8>,
+
+    /// Any data, depending on the channel. MC| channels are documented here.
+    ///
+    /// The length of Data is known only from the packet length, since the packet has no length
+    /// field of any kind. Decoding from a `Bytes` frame doesn't copy it.
+    pub data: Remaining<Bytes>,
 }
 
-// /// Sent by the server before it disconnects a client. The client assumes that the server has already closed the connection by the time the packet arrives.
-// #[derive(PacketDef)]
-// pub struct DisconnectPacket<'a> {
-//     /// Displayed to the client when the connection terminates.
-//     pub reason: ChatComponent<'a>,
-// }
+/// Sent by the server before it disconnects a client. The client assumes that the server has
+/// already closed the connection by the time the packet arrives.
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x40)]
+pub struct DisconnectPacket<'a> {
+    /// Displayed to the client when the connection terminates.
+    pub reason: ChatComponent<'a>,
+}
 
 /// Changes the difficulty setting in the client's option menu.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x41, since = "V1_8")]
 pub struct ServerDifficultyPacket {
     /// 0: peaceful, 1: easy, 2: normal, 3: hard.
     pub difficulty: u8,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x42, since = "V1_8")]
 pub struct CombatEventPacket {
     /// 0: enter combat, 1: end combat, 2: entity dead.
     pub event: VarInt,
 
-    // /// Only for end combat.
-    // pub duration: Option<VarInt>,
-
-    // /// Only for entity dead.
-    // pub player_id: Option<VarInt>,
-
-    // /// Only for end combat and entity dead.
-    // pub entity_id: Option<i32>,
-    /// Only for entity dead.
-    pub message: String,
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `duration` (switch): Only for end combat.
+    /// - `player_id` (switch): Only for entity dead.
+    /// - `entity_id` (switch): Only for end combat and entity dead.
+    /// - `message` (switch): Only for entity dead.
+    pub rest: Remaining<Bytes>,
 }
 
 /// Sets the entity that the player renders from. This is normally used when the left-clicks an
@@ -1316,34 +1401,67 @@ pub struct CombatEventPacket {
 /// entity is killed or the player sneaks, but only if they were spectating an entity. It also
 /// sends this packet whenever the player switches out of spectator mode (even if they weren't
 /// spectating an entity).
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x43, since = "V1_8")]
 pub struct CameraPacket {
     /// ID of the entity to set the client's camera to.
     pub id: VarInt,
 }
 
-/// TODO
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x44, since = "V1_8")]
 pub struct WorldBorderPacket {
     /// Determines the format of the rest of the packet
     pub action: VarInt,
-    // TODO: ??
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `radius` (switch)
+    /// - `x` (switch)
+    /// - `z` (switch)
+    /// - `old_radius` (switch)
+    /// - `new_radius` (switch)
+    /// - `speed` (switch)
+    /// - `portal_boundary` (switch)
+    /// - `warning_time` (switch)
+    /// - `warning_blocks` (switch)
+    pub rest: Remaining<Bytes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x45, since = "V1_8")]
+pub struct TitlePacket {
+    /// 0: set title, 1: set subtitle, 2: set times and display, 3: hide, 4: reset.
+    pub action: VarInt,
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `text` (switch)
+    /// - `fade_in` (switch)
+    /// - `stay` (switch)
+    /// - `fade_out` (switch)
+    pub rest: Remaining<Bytes>,
 }
 
 /// Warning: This packet is completely broken and has been removed in the 1.9 snapshots. The
 /// packet Set Compression (Login, 0x03, clientbound) should be used instead.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x46, since = "V1_8")]
 pub struct SetCompressionPacket {
     pub threshold: VarInt,
 }
 
-// #[derive(PacketDef)]
-// pub struct PlayerListHeaderAndFooterPacket<'a> {
-//     pub header: ChatComponent<'a>,
-//     pub footer: ChatComponent<'a>,
-// }
+/// Sets the text shown above and below the player list.
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x47, since = "V1_8")]
+pub struct PlayerListHeaderAndFooterPacket<'a> {
+    pub header: ChatComponent<'a>,
 
-#[derive(PacketDef)]
+    pub footer: ChatComponent<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x48, since = "V1_8")]
 pub struct ResourcePackSendPacket {
     /// The URL to the resource pack.
     pub url: String,
@@ -1356,8 +1474,93 @@ pub struct ResourcePackSendPacket {
     pub hash: String,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x49, since = "V1_8")]
 pub struct UpdateEntityNBTPacket {
     pub id: VarInt,
-    // pub tag: NBTTag,
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `tag` (nbt)
+    pub rest: Remaining<Bytes>,
+}
+
+packet_set! {
+    /// Every clientbound packet in the play state.
+    ClientboundPlayPacket<'a> (Play, Clientbound) {
+        KeepAlive(KeepAlivePacket),
+        JoinGame(JoinGamePacket),
+        ChatMessage(ChatMessagePacket<'a>),
+        TimeUpdate(TimeUpdatePacket),
+        EntityEquipment(EntityEquipmentPacket),
+        SpawnPosition(SpawnPositionPacket),
+        UpdateHealth(UpdateHealthPacket),
+        Respawn(RespawnPacket),
+        PlayerPositionAndLook(PlayerPositionAndLookPacket),
+        HeldItemChange(HeldItemChangePacket),
+        UseBed(UseBedPacket),
+        Animation(AnimationPacket),
+        SpawnPlayer(SpawnPlayerPacket),
+        CollectItem(CollectItemPacket),
+        SpawnObject(SpawnObjectPacket),
+        SpawnMob(SpawnMobPacket),
+        SpawnPainting(SpawnPaintingPacket),
+        SpawnExperienceOrb(SpawnExperienceOrbPacket),
+        EntityVelocity(EntityVelocityPacket),
+        DestroyEntities(DestroyEntitiesPacket),
+        Entity(EntityPacket),
+        EntityRelativeMove(EntityRelativeMovePacket),
+        EntityLook(EntityLookPacket),
+        EntityLookAndRelativeMove(EntityLookAndRelativeMovePacket),
+        EntityTeleport(EntityTeleportPacket),
+        EntityHeadLook(EntityHeadLookPacket),
+        EntityStatus(EntityStatusPacket),
+        AttachEntity(AttachEntityPacket),
+        EntityMetadata(EntityMetadataPacket),
+        EntityEffect(EntityEffectPacket),
+        RemoveEntityEffect(RemoveEntityEffectPacket),
+        SetExperience(SetExperiencePacket),
+        EntityProperties(EntityPropertiesPacket),
+        ChunkData(ChunkDataPacket),
+        MultiBlockChange(MultiBlockChangePacket),
+        BlockChange(BlockChangePacket),
+        BlockAction(BlockActionPacket),
+        BlockBreakAnimation(BlockBreakAnimationPacket),
+        MapChunkBulk(MapChunkBulkPacket),
+        Explosion(ExplosionPacket),
+        Effect(EffectPacket),
+        SoundEffect(SoundEffectPacket),
+        Particle(ParticlePacket),
+        ChangeGameState(ChangeGameStatePacket),
+        SpawnGlobalEntity(SpawnGlobalEntityPacket),
+        OpenWindow(OpenWindowPacket<'a>),
+        CloseWindow(CloseWindowPacket),
+        SetSlot(SetSlotPacket),
+        WindowItems(WindowItemsPacket),
+        WindowProperty(WindowPropertyPacket),
+        ConfirmTransaction(ConfirmTransactionPacket),
+        UpdateSign(UpdateSignPacket<'a>),
+        Map(MapPacket),
+        UpdateBlockEntity(UpdateBlockEntityPacket),
+        OpenSignEditor(OpenSignEditorPacket),
+        Statistics(StatisticsPacket),
+        PlayerListItem(PlayerListItemPacket),
+        PlayerAbilities(PlayerAbilitiesPacket),
+        TabComplete(TabCompletePacket),
+        ScoreboardObjective(ScoreboardObjectivePacket),
+        UpdateScore(UpdateScorePacket),
+        DisplayScoreboard(DisplayScoreboardPacket),
+        Team(TeamPacket),
+        PluginMessage(PluginMessagePacket),
+        Disconnect(DisconnectPacket<'a>),
+        ServerDifficulty(ServerDifficultyPacket),
+        CombatEvent(CombatEventPacket),
+        Camera(CameraPacket),
+        WorldBorder(WorldBorderPacket),
+        Title(TitlePacket),
+        SetCompression(SetCompressionPacket),
+        PlayerListHeaderAndFooter(PlayerListHeaderAndFooterPacket<'a>),
+        ResourcePackSend(ResourcePackSendPacket),
+        UpdateEntityNBT(UpdateEntityNBTPacket),
+    }
 }
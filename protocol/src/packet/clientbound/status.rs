@@ -1,4 +1,10 @@
+// Generated by protocol-codegen from minecraft-data's 1.8 protocol. Don't edit this file;
+// change protocol-codegen/data instead, and run `cargo run -p protocol-codegen`.
+
 use protocol_macro::PacketDef;
+use serde::Serialize;
+
+use crate::packet::packet_set;
 
 /// Status response packet.
 ///
@@ -52,14 +58,24 @@ use protocol_macro::PacketDef;
 ///
 /// If the client does not receive a properly formatted response, then it will instead attempt a
 /// legacy ping.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x00)]
 pub struct ResponsePacket {
     /// JSON data.
     pub response: String,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x01)]
 pub struct PongPacket {
     /// Long that was sent by the client.
     pub payload: i64,
 }
+
+packet_set! {
+    /// Every clientbound packet in the status state.
+    ClientboundStatusPacket (Status, Clientbound) {
+        Response(ResponsePacket),
+        Pong(PongPacket),
+    }
+}
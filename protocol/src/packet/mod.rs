@@ -1,65 +1,338 @@
 use bytes::{Buf, BufMut};
 
-use crate::encoding::Encodable;
+use crate::{
+    dissect::Dissection,
+    encoding::Encode,
+    state::State,
+    version::{ProtocolVersion, VersionedPacket},
+};
 
+// The packets of each state, and their sets, are generated by protocol-codegen.
 pub mod clientbound;
 pub mod serverbound;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PacketDirection {
     Clientbound,
     Serverbound,
 }
 
-pub trait Packet: Encodable
+/// Implemented by every packet through `#[derive(PacketDef)]`.
+///
+/// Packets are encoded through [Encode], and decoded through [Decode](crate::encoding::Decode).
+pub trait Packet: Encode {
+    /// The ID this packet is sent with, in protocol version 47.
+    const ID: i32;
+}
+
+/// All packets that can be sent in one state and direction, e.g. every serverbound play packet.
+///
+/// This is what turns a packet ID and body into a typed packet. The [Encode] implementation of a
+/// set only writes the body of the packet it holds, not its ID.
+pub trait PacketSet: Encode
 where
     Self: Sized,
 {
-    fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self>;
-    fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()>;
-}
+    const STATE: State;
+    const DIRECTION: PacketDirection;
 
-impl<P: Packet> Encodable for P {
-    fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self> {
-        return <Self as Packet>::decode(reader);
-    }
+    /// The ID of the packet this holds.
+    fn id(&self) -> i32;
+
+    /// The name of the packet this holds, e.g. `ChatMessage`.
+    fn name(&self) -> &'static str;
+
+    /// The ID of the packet this holds in `version`, or `None` if it doesn't exist there.
+    fn id_for(&self, version: ProtocolVersion) -> Option<i32>;
+
+    /// Encodes the body of the packet this holds, laid out as in `version`.
+    fn encode_for<B: BufMut + ?Sized>(
+        &self,
+        version: ProtocolVersion,
+        writer: &mut B,
+    ) -> anyhow::Result<()>;
+
+    /// Decodes the body of the packet with the given ID, as sent in `version`.
+    fn decode_for<B: Buf + ?Sized>(
+        version: ProtocolVersion,
+        id: i32,
+        reader: &mut B,
+    ) -> anyhow::Result<Self>;
+
+    /// Dissects the body of the packet with the given ID, as sent in `version`, and returns the
+    /// packet's name along with it. Returns `None` if there's no such packet.
+    fn dissect_for(
+        version: ProtocolVersion,
+        id: i32,
+        body: &[u8],
+    ) -> Option<(&'static str, Dissection)>;
 
-    fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()> {
-        <Self as Packet>::encode(self, writer)
+    /// Decodes the body of the packet with the given ID, as sent in the latest version.
+    fn decode_by_id<B: Buf + ?Sized>(id: i32, reader: &mut B) -> anyhow::Result<Self> {
+        Self::decode_for(ProtocolVersion::LATEST, id, reader)
     }
 }
 
-pub mod test {
-    use std::marker::PhantomData;
+/// A packet that belongs to a [PacketSet], i.e. to one state and direction.
+///
+/// This is implemented for every packet listed in a set, and allows checking (or restricting, at
+/// compile time) which state a packet can be sent in. `'a` is the lifetime of the set, as packets
+/// that borrow nothing can still be held by a set that has a lifetime.
+pub trait StatePacket<'a>: VersionedPacket
+where
+    Self: Sized,
+{
+    type Set: PacketSet + From<Self>;
+}
+
+/// A packet from any state, in one direction.
+///
+/// [ServerboundPacket](serverbound::ServerboundPacket) and
+/// [ClientboundPacket](clientbound::ClientboundPacket) implement this, wrapping the [PacketSet]s
+/// of their direction.
+pub trait AnyStatePacket: Encode
+where
+    Self: Sized,
+{
+    const DIRECTION: PacketDirection;
+
+    /// The state this packet belongs to.
+    fn state(&self) -> State;
+
+    /// The ID of the packet this holds.
+    fn id(&self) -> i32;
+
+    /// The name of the packet this holds, e.g. `ChatMessage`.
+    fn name(&self) -> &'static str;
+
+    /// The ID of the packet this holds in `version`, or `None` if it doesn't exist there.
+    fn id_for(&self, version: ProtocolVersion) -> Option<i32>;
+
+    /// Encodes the body of the packet this holds, laid out as in `version`.
+    fn encode_for<B: BufMut + ?Sized>(
+        &self,
+        version: ProtocolVersion,
+        writer: &mut B,
+    ) -> anyhow::Result<()>;
+
+    /// Decodes the body of the packet with the given ID, as sent in `state` and `version`.
+    fn decode_by_state<B: Buf + ?Sized>(
+        version: ProtocolVersion,
+        state: State,
+        id: i32,
+        reader: &mut B,
+    ) -> anyhow::Result<Self>;
+
+    /// Dissects the body of the packet with the given ID, as sent in `state` and `version`. See
+    /// [PacketSet::dissect_for].
+    fn dissect_by_state(
+        version: ProtocolVersion,
+        state: State,
+        id: i32,
+        body: &[u8],
+    ) -> Option<(&'static str, Dissection)>;
+}
+
+/// Defines an enum for a [PacketSet], with one variant per packet.
+///
+/// Every packet also gets a `From` implementation into the set.
+macro_rules! packet_set {
+    (
+        $(#[$meta:meta])*
+        $name:ident $(<$lt:lifetime>)? ($state:ident, $direction:ident) {
+            $($variant:ident($packet:ty)),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, serde::Serialize)]
+        #[allow(clippy::large_enum_variant)]
+        pub enum $name $(<$lt>)? {
+            $($variant($packet)),*
+        }
+
+        impl $(<$lt>)? $crate::packet::PacketSet for $name $(<$lt>)? {
+            const STATE: $crate::state::State = $crate::state::State::$state;
+            const DIRECTION: $crate::packet::PacketDirection =
+                $crate::packet::PacketDirection::$direction;
+
+            fn id(&self) -> i32 {
+                match self {
+                    $(Self::$variant(_) => <$packet as $crate::packet::Packet>::ID),*
+                }
+            }
+
+            fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant(_) => stringify!($variant)),*
+                }
+            }
+
+            fn id_for(&self, version: $crate::version::ProtocolVersion) -> Option<i32> {
+                match self {
+                    $(Self::$variant(_) => {
+                        <$packet as $crate::version::VersionedPacket>::id_for(version)
+                    }),*
+                }
+            }
+
+            fn encode_for<B: bytes::BufMut + ?Sized>(
+                &self,
+                version: $crate::version::ProtocolVersion,
+                writer: &mut B,
+            ) -> anyhow::Result<()> {
+                match self {
+                    $(Self::$variant(packet) => {
+                        $crate::version::VersionedPacket::encode_for(packet, version, writer)
+                    }),*
+                }
+            }
+
+            fn decode_for<B: bytes::Buf + ?Sized>(
+                version: $crate::version::ProtocolVersion,
+                id: i32,
+                reader: &mut B,
+            ) -> anyhow::Result<Self> {
+                $(
+                    if Some(id) == <$packet as $crate::version::VersionedPacket>::id_for(version) {
+                        let packet =
+                            <$packet as $crate::version::VersionedPacket>::decode_for(version, reader)?;
+                        return Ok(Self::$variant(packet));
+                    }
+                )*
+
+                anyhow::bail!(
+                    "unknown packet id {:#04x} ({:?}, {:?}, {:?})",
+                    id,
+                    <Self as $crate::packet::PacketSet>::STATE,
+                    <Self as $crate::packet::PacketSet>::DIRECTION,
+                    version,
+                )
+            }
+
+            fn dissect_for(
+                version: $crate::version::ProtocolVersion,
+                id: i32,
+                body: &[u8],
+            ) -> Option<(&'static str, $crate::dissect::Dissection)> {
+                $(
+                    if Some(id) == <$packet as $crate::version::VersionedPacket>::id_for(version) {
+                        let dissection =
+                            <$packet as $crate::version::VersionedPacket>::dissect_for(version, body);
+                        return Some((stringify!($variant), dissection));
+                    }
+                )*
+
+                None
+            }
+        }
+
+        impl $(<$lt>)? $crate::encoding::Encode for $name $(<$lt>)? {
+            fn encode<B: bytes::BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+                match self {
+                    $(Self::$variant(packet) => packet.encode(writer)),*
+                }
+            }
+
+            fn encoded_len(&self) -> usize {
+                match self {
+                    $(Self::$variant(packet) => packet.encoded_len()),*
+                }
+            }
+        }
+
+        #[cfg(test)]
+        impl $(<$lt>)? $name $(<$lt>)? {
+            /// One random packet of every kind in this set.
+            pub fn arbitrary_each(rng: &mut $crate::arbitrary::Rng) -> Vec<Self> {
+                vec![$(Self::$variant(<$packet as $crate::arbitrary::Arbitrary>::arbitrary(rng))),*]
+            }
+        }
+
+        $crate::packet::packet_set!(@from $name [$($lt)?] $($variant($packet)),*);
+    };
+
+    (@from $name:ident [] $($variant:ident($packet:ty)),*) => {
+        $(
+            impl From<$packet> for $name {
+                fn from(packet: $packet) -> Self {
+                    Self::$variant(packet)
+                }
+            }
+
+            impl<'a> $crate::packet::StatePacket<'a> for $packet {
+                type Set = $name;
+            }
+        )*
+    };
+
+    (@from $name:ident [$lt:lifetime] $($variant:ident($packet:ty)),*) => {
+        $(
+            impl<$lt> From<$packet> for $name<$lt> {
+                fn from(packet: $packet) -> Self {
+                    Self::$variant(packet)
+                }
+            }
+
+            impl<$lt> $crate::packet::StatePacket<$lt> for $packet {
+                type Set = $name<$lt>;
+            }
+        )*
+    };
+}
+
+pub(crate) use packet_set;
+
+#[cfg(test)]
+mod test {
+    use std::{fmt::Debug, marker::PhantomData};
 
     use bytes::{Buf, BufMut};
     use protocol_macro::PacketDef;
 
-    use crate::encoding::Encodable;
+    use super::{clientbound, serverbound, PacketSet};
+    use crate::{
+        arbitrary::{Arbitrary, Rng},
+        encoding::{Decode, Encode},
+        version::ProtocolVersion,
+    };
 
     #[derive(PacketDef)]
+    #[packet(id = 0x00)]
     pub struct TestPacket<'a> {
         test: Test<'a>,
     }
 
+    #[derive(Debug)]
     pub struct Test<'a> {
         val: String,
         phantom: PhantomData<&'a str>,
     }
 
-    impl<'a> Encodable for Test<'a> {
-        fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self> {
+    impl<'a> Decode for Test<'a> {
+        fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
             Ok(Test {
                 val: String::decode(reader)?,
                 phantom: PhantomData,
             })
         }
+    }
 
-        fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()> {
+    impl<'a> Encode for Test<'a> {
+        fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
             self.val.encode(writer)?;
             Ok(())
         }
     }
 
+    impl<'a> Arbitrary for Test<'a> {
+        fn arbitrary(rng: &mut Rng) -> Self {
+            Test {
+                val: String::arbitrary(rng),
+                phantom: PhantomData,
+            }
+        }
+    }
+
     #[test]
     pub fn encode() {
         let packet = TestPacket {
@@ -74,4 +347,47 @@ pub mod test {
 
         assert_eq!([2, 104, 105], data.as_slice());
     }
+
+    /// Encodes and decodes random packets of every kind, in every version they exist in.
+    fn round_trip<P: PacketSet + Debug + PartialEq>(arbitrary_each: fn(&mut Rng) -> Vec<P>) {
+        let mut rng = Rng::new(0x4A414D);
+
+        for _ in 0..64 {
+            for packet in arbitrary_each(&mut rng) {
+                for version in [ProtocolVersion::V1_7_10, ProtocolVersion::V1_8] {
+                    let Some(id) = packet.id_for(version) else {
+                        continue;
+                    };
+
+                    let mut data = Vec::<u8>::new();
+                    packet.encode_for(version, &mut data).unwrap();
+
+                    let mut reader = &data[..];
+                    let decoded = P::decode_for(version, id, &mut reader).unwrap();
+
+                    assert_eq!(packet, decoded, "{:?}", version);
+                    assert!(reader.is_empty(), "{:?} left {:?}", decoded, reader);
+
+                    if version == ProtocolVersion::LATEST {
+                        assert_eq!(data.len(), packet.encoded_len(), "{:?}", packet);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn round_trip_clientbound() {
+        round_trip(clientbound::ClientboundStatusPacket::arbitrary_each);
+        round_trip(clientbound::ClientboundLoginPacket::arbitrary_each);
+        round_trip(clientbound::ClientboundPlayPacket::arbitrary_each);
+    }
+
+    #[test]
+    pub fn round_trip_serverbound() {
+        round_trip(serverbound::ServerboundHandshakingPacket::arbitrary_each);
+        round_trip(serverbound::ServerboundStatusPacket::arbitrary_each);
+        round_trip(serverbound::ServerboundLoginPacket::arbitrary_each);
+        round_trip(serverbound::ServerboundPlayPacket::arbitrary_each);
+    }
 }
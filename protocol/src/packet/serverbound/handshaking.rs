@@ -1,11 +1,26 @@
+// Generated by protocol-codegen from minecraft-data's 1.8 protocol. Don't edit this file;
+// change protocol-codegen/data instead, and run `cargo run -p protocol-codegen`.
+
 use protocol_macro::PacketDef;
+use serde::Serialize;
 
-use crate::varint::VarInt;
+use crate::{packet::packet_set, varint::VarInt};
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x00)]
 pub struct HandshakePacket {
     pub protocol_version: VarInt,
+
     pub server_address: String,
+
     pub server_port: u16,
+
     pub next_state: VarInt,
 }
+
+packet_set! {
+    /// Every serverbound packet in the handshaking state.
+    ServerboundHandshakingPacket (Handshaking, Serverbound) {
+        Handshake(HandshakePacket),
+    }
+}
@@ -1,21 +1,32 @@
+// Generated by protocol-codegen from minecraft-data's 1.8 protocol. Don't edit this file;
+// change protocol-codegen/data instead, and run `cargo run -p protocol-codegen`.
+
+use bytes::Bytes;
 use protocol_macro::PacketDef;
+use serde::Serialize;
 
-use crate::varint::VarInt;
+use crate::packet::packet_set;
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x00)]
 pub struct LoginStartPacket {
     pub name: String,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x01, versioned)]
 pub struct EncryptionResponsePacket {
-    /// Length of shared secret.
-    pub shared_secret_length: VarInt,
+    /// The shared secret, encrypted with the public key of the server.
+    pub shared_secret: Bytes,
+
+    /// The verify token, encrypted with the same public key.
+    pub verify_token: Bytes,
+}
 
-    // /// Shared secret.
-    // pub shared_secret: Vec<i8>,
-    /// Length of verify token.
-    pub verify_token_length: VarInt,
-    // /// Verify token.
-    // pub verify_token: Vec<i8>,
+packet_set! {
+    /// Every serverbound packet in the login state.
+    ServerboundLoginPacket (Login, Serverbound) {
+        LoginStart(LoginStartPacket),
+        EncryptionResponse(EncryptionResponsePacket),
+    }
 }
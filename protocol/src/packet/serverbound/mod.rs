@@ -1,4 +1,159 @@
+use anyhow::bail;
+use bytes::{Buf, BufMut};
+use serde::Serialize;
+
+use crate::{
+    dissect::Dissection,
+    encoding::Encode,
+    packet::{AnyStatePacket, PacketDirection, PacketSet},
+    state::State,
+    version::ProtocolVersion,
+};
+
 pub mod handshaking;
 pub mod login;
 pub mod play;
 pub mod status;
+
+pub use handshaking::ServerboundHandshakingPacket;
+pub use login::ServerboundLoginPacket;
+pub use play::ServerboundPlayPacket;
+pub use status::ServerboundStatusPacket;
+
+/// A serverbound packet from any state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+pub enum ServerboundPacket<'a> {
+    Handshaking(ServerboundHandshakingPacket),
+    Status(ServerboundStatusPacket),
+    Login(ServerboundLoginPacket),
+    Play(ServerboundPlayPacket<'a>),
+}
+
+impl<'a> AnyStatePacket for ServerboundPacket<'a> {
+    const DIRECTION: PacketDirection = PacketDirection::Serverbound;
+
+    fn state(&self) -> State {
+        match self {
+            Self::Handshaking(_) => State::Handshaking,
+            Self::Status(_) => State::Status,
+            Self::Login(_) => State::Login,
+            Self::Play(_) => State::Play,
+        }
+    }
+
+    fn id(&self) -> i32 {
+        match self {
+            Self::Handshaking(packet) => packet.id(),
+            Self::Status(packet) => packet.id(),
+            Self::Login(packet) => packet.id(),
+            Self::Play(packet) => packet.id(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Handshaking(packet) => packet.name(),
+            Self::Status(packet) => packet.name(),
+            Self::Login(packet) => packet.name(),
+            Self::Play(packet) => packet.name(),
+        }
+    }
+
+    fn id_for(&self, version: ProtocolVersion) -> Option<i32> {
+        match self {
+            Self::Handshaking(packet) => packet.id_for(version),
+            Self::Status(packet) => packet.id_for(version),
+            Self::Login(packet) => packet.id_for(version),
+            Self::Play(packet) => packet.id_for(version),
+        }
+    }
+
+    fn encode_for<B: BufMut + ?Sized>(
+        &self,
+        version: ProtocolVersion,
+        writer: &mut B,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Handshaking(packet) => packet.encode_for(version, writer),
+            Self::Status(packet) => packet.encode_for(version, writer),
+            Self::Login(packet) => packet.encode_for(version, writer),
+            Self::Play(packet) => packet.encode_for(version, writer),
+        }
+    }
+
+    fn decode_by_state<B: Buf + ?Sized>(
+        version: ProtocolVersion,
+        state: State,
+        id: i32,
+        reader: &mut B,
+    ) -> anyhow::Result<Self> {
+        Ok(match state {
+            State::Handshaking => Self::Handshaking(PacketSet::decode_for(version, id, reader)?),
+            State::Status => Self::Status(PacketSet::decode_for(version, id, reader)?),
+            State::Login => Self::Login(PacketSet::decode_for(version, id, reader)?),
+            State::Play => Self::Play(PacketSet::decode_for(version, id, reader)?),
+            State::Closed => bail!("no packets can be received once closed"),
+        })
+    }
+
+    fn dissect_by_state(
+        version: ProtocolVersion,
+        state: State,
+        id: i32,
+        body: &[u8],
+    ) -> Option<(&'static str, Dissection)> {
+        match state {
+            State::Handshaking => ServerboundHandshakingPacket::dissect_for(version, id, body),
+            State::Status => ServerboundStatusPacket::dissect_for(version, id, body),
+            State::Login => ServerboundLoginPacket::dissect_for(version, id, body),
+            State::Play => ServerboundPlayPacket::dissect_for(version, id, body),
+            State::Closed => None,
+        }
+    }
+}
+
+impl<'a> Encode for ServerboundPacket<'a> {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        match self {
+            Self::Handshaking(packet) => packet.encode(writer),
+            Self::Status(packet) => packet.encode(writer),
+            Self::Login(packet) => packet.encode(writer),
+            Self::Play(packet) => packet.encode(writer),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            Self::Handshaking(packet) => packet.encoded_len(),
+            Self::Status(packet) => packet.encoded_len(),
+            Self::Login(packet) => packet.encoded_len(),
+            Self::Play(packet) => packet.encoded_len(),
+        }
+    }
+}
+
+impl From<ServerboundHandshakingPacket> for ServerboundPacket<'_> {
+    fn from(packet: ServerboundHandshakingPacket) -> Self {
+        Self::Handshaking(packet)
+    }
+}
+
+impl From<ServerboundStatusPacket> for ServerboundPacket<'_> {
+    fn from(packet: ServerboundStatusPacket) -> Self {
+        Self::Status(packet)
+    }
+}
+
+impl From<ServerboundLoginPacket> for ServerboundPacket<'_> {
+    fn from(packet: ServerboundLoginPacket) -> Self {
+        Self::Login(packet)
+    }
+}
+
+impl<'a> From<ServerboundPlayPacket<'a>> for ServerboundPacket<'a> {
+    fn from(packet: ServerboundPlayPacket<'a>) -> Self {
+        Self::Play(packet)
+    }
+}
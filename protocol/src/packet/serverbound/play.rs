@@ -1,11 +1,20 @@
-use crate::chat::ChatComponent;
-use crate::position::Position;
-use crate::varint::VarInt;
+// Generated by protocol-codegen from minecraft-data's 1.8 protocol. Don't edit this file;
+// change protocol-codegen/data instead, and run `cargo run -p protocol-codegen`.
+
+use bytes::Bytes;
 use protocol_macro::PacketDef;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    chat::ChatComponent, encoding::byte_array::Remaining, packet::packet_set, position::Position,
+    varint::VarInt,
+};
 
 /// The server will frequently send out a keep-alive, each containing a random ID. The client must
 /// respond with the same packet.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x00, versioned)]
 pub struct KeepAlivePacket {
     /// The same random ID that was sent by the server.
     pub id: VarInt,
@@ -18,7 +27,8 @@ pub struct KeepAlivePacket {
 /// initially done by allowing the client to not slice the message up to 119 (the previous limit),
 /// without changes to the server. For this reason, the vanilla server kept the code to cut
 /// messages at 119, but this isn't a protocol limitation and can be ignored.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x01)]
 pub struct ChatMessagePacket {
     /// The client sends the raw input, not [`ChatComponent`]
     pub message: String,
@@ -32,7 +42,8 @@ pub struct ChatMessagePacket {
 ///
 /// Note that middle-click in creative mode is interpreted by the client and sent as a Creative
 /// Inventory Action packet instead.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x02, since = "V1_8")]
 pub struct UseEntityPacket {
     pub target: VarInt,
 
@@ -40,12 +51,13 @@ pub struct UseEntityPacket {
     /// 1: attack,
     /// 2: interact at
     pub type_: VarInt,
-    // /// only if type_ is interacted at (2)
-    // pub target_x: Option<f32>,
-    // /// only if type_ is interacted at (2)
-    // pub target_y: Option<f32>,
-    // /// only if type_ is interacted at (2)
-    // pub target_z: Option<f32>,
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `target_x` (switch): only if type_ is interacted at (2)
+    /// - `target_y` (switch): only if type_ is interacted at (2)
+    /// - `target_z` (switch): only if type_ is interacted at (2)
+    pub rest: Remaining<Bytes>,
 }
 
 /// This packet as well as Player Position (Play, 0x04, serverbound), Player Look (Play, 0x05,
@@ -60,10 +72,11 @@ pub struct UseEntityPacket {
 /// When dropping from sufficient height, fall damage is applied when this state goes from false to
 /// true. The amount of damage applied is based on the point where it last changed from true to
 /// false. Note that there are several movement related packets containing this state.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x03)]
 pub struct PlayerPacket {
-    // /// True if the client is on the ground, false otherwise.
-    // pub on_ground: bool,
+    /// True if the client is on the ground, false otherwise.
+    pub on_ground: bool,
 }
 
 /// Updates the player's XYZ position on the server.
@@ -78,7 +91,8 @@ pub struct PlayerPacket {
 ///
 /// Also if the fixed-point number of X or Z is set greater than 3.2×107 the client will be kicked
 /// for “Illegal position”.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x04, since = "V1_8")]
 pub struct PlayerPositionPacket {
     /// Absolute position.
     pub x: f64,
@@ -88,8 +102,9 @@ pub struct PlayerPositionPacket {
 
     /// Absolute position.
     pub z: f64,
-    // /// True if the client is on the ground, false otherwise.
-    // pub on_ground: bool,
+
+    /// True if the client is on the ground, false otherwise.
+    pub on_ground: bool,
 }
 
 /// Updates the direction the player is looking in.
@@ -122,19 +137,22 @@ pub struct PlayerPositionPacket {
 /// y = -sin(pitch)
 /// z =  cos(pitch) * cos(yaw)
 /// ```
-#[derive(PacketDef)]
-pub struct PlayerLook {
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x05)]
+pub struct PlayerLookPacket {
     /// Absolute rotation on the X Axis, in degrees
     pub yaw: f32,
 
     /// Absolute rotation on the Y Axis, in degrees
     pub pitch: f32,
-    // /// True if the client is on the ground, False otherwise
-    // pub on_ground: bool,
+
+    /// True if the client is on the ground, False otherwise
+    pub on_ground: bool,
 }
 
 /// A combination of Player Look and Player Position.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x06, since = "V1_8")]
 pub struct PlayerPositionAndLookPacket {
     /// Absolute position.
     pub x: f64,
@@ -150,14 +168,15 @@ pub struct PlayerPositionAndLookPacket {
 
     /// Absolute rotation on the Y Axis, in degrees.
     pub pitch: f32,
-    // /// True if the client is on the ground, false otherwise.
-    // pub on_ground: bool,
+
+    /// True if the client is on the ground, false otherwise.
+    pub on_ground: bool,
 }
 
 /// Sent when the player mines a block. A Notchian server only accepts digging packets with
 /// coordinates within a 6-unit radius of the player's position.
 ///
-/// # Status Table
+/// Status Table
 /// | Meaning                     | Value |
 /// |-----------------------------|-------|
 /// | Started digging             | 0     |
@@ -187,10 +206,11 @@ pub struct PlayerPositionAndLookPacket {
 /// |   3   |   +Z   |
 /// |   4   |   -X   |
 /// |   5   |   +X   |
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x07, since = "V1_8")]
 pub struct PlayerDiggingPacket {
     /// The action the player is taking against the block.
-    pub status: i8,
+    pub status: VarInt,
 
     /// Block position.
     pub location: Position,
@@ -219,7 +239,8 @@ pub struct PlayerDiggingPacket {
 /// anything with a Notchian server. The second, special case packet appears to perform the action
 /// — based on current position/orientation and with a distance check — it appears that buckets can
 /// only be used within a radius of 6 units.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x08, since = "V1_8")]
 pub struct PlayerBlockPlacementPacket {
     /// Block position.
     pub location: Position,
@@ -227,29 +248,33 @@ pub struct PlayerBlockPlacementPacket {
     /// The face on which the block is placed.
     pub face: i8,
 
-    // pub held_item: Slot,
-    /// The position of the crosshair on the block.
-    pub cursor_position_x: i8,
-    pub cursor_position_y: i8,
-    pub cursor_position_z: i8,
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `held_item` (slot)
+    /// - `cursor_position_x` (i8): The position of the crosshair on the block.
+    /// - `cursor_position_y` (i8)
+    /// - `cursor_position_z` (i8)
+    pub rest: Remaining<Bytes>,
 }
 
 /// Sent when the player changes the slot selection.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x09)]
 pub struct HeldItemChangePacket {
     /// The slot which the player has selected (0–8.)
     pub slot: i16,
 }
 
 /// Sent when the player's arm swings.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0A, since = "V1_8")]
 pub struct AnimationPacket {}
 
 /// Sent by the client to indicate that it has performed certain actions: sneaking (crouching),
 /// sprinting, exiting a bed, jumping with a horse, and opening a horse's inventory while riding
 /// it.
 ///
-/// # Action ID Table
+/// Action ID Table
 /// | ID | Action                      |
 /// |----|-----------------------------|
 /// | 0  | Start sneaking              |
@@ -266,7 +291,8 @@ pub struct AnimationPacket {}
 /// Open ridden horse inventory is only sent when pressing the inventory key on a horse - all other
 /// methods of opening a horse's inventory (involving right-clicking or shift-right-clicking it) do
 /// not use this packet.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0B, since = "V1_8")]
 pub struct EntityActionPacket {
     /// Player ID.
     pub entity_id: VarInt,
@@ -279,7 +305,8 @@ pub struct EntityActionPacket {
     pub action_parameter: VarInt,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0C, since = "V1_8")]
 pub struct SteerVehiclePacket {
     /// Positive to the left of the player.
     pub sideways: f32,
@@ -295,7 +322,8 @@ pub struct SteerVehiclePacket {
 ///
 /// Notchian clients send a Close Window packet with Window ID 0 to close their inventory even
 /// though there is never an Open Window packet for the inventory.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0D)]
 pub struct CloseWindowPacket {
     /// This is the ID of the window that was closed. 0 for player inventory.
     pub id: u8,
@@ -349,13 +377,14 @@ pub struct CloseWindowPacket {
 /// If any of the painting packets other than the “progress” ones are sent out of order (for
 /// example, a start, some slots, then another start; or a left-click in the middle) the painting
 /// status will be reset.
-
+///
 /// The server will send back a Confirm Transaction packet. If the click was not accepted, the
 /// client must reflect that packet before sending more Click Window packets, otherwise the server
 /// will reject them silently. The Notchian server also sends a Window Items packet for the open
 /// window and Set Slot packets for the clicked and cursor slot, but only when the click was not
 /// accepted, probably to resynchronize client and server.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0E, since = "V1_8")]
 pub struct ClickWindowPacket {
     /// The ID of the window which was clicked. 0 for player inventory.
     pub id: u8,
@@ -369,17 +398,21 @@ pub struct ClickWindowPacket {
     /// A unique number for the action, implemented by Notchian as a counter, starting at 1. Used
     /// by the server to send back a Confirm Transaction.
     pub action_number: i16,
-    // TODO: ??
 
-    // /// The clicked slot. Has to be empty (item ID = -1) for drop mode.
-    // pub clicked_item: Slot
+    pub mode: i8,
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `clicked_item` (slot): The clicked slot. Has to be empty (item ID = -1) for drop mode.
+    pub rest: Remaining<Bytes>,
 }
 
 /// If a transaction sent by the client was not accepted, the server will reply with a Confirm
 /// Transaction (Play, 0x32, clientbound) packet with the Accepted field set to false. When this
 /// happens, the client must reflect the packet to apologize (as with movement), otherwise the
 /// server ignores any successive transactions.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x0F)]
 pub struct ConfirmTransactionPacket {
     /// The ID of the window that the action occurred in.
     pub window_id: i8,
@@ -387,8 +420,9 @@ pub struct ConfirmTransactionPacket {
     /// Every action that is to be accepted has a unique number. This field corresponds to that
     /// number.
     pub action_number: i16,
-    // /// Whether the action was accepted.
-    // pub accepted: bool,
+
+    /// Whether the action was accepted.
+    pub accepted: bool,
 }
 
 /// While the user is in the standard inventory (i.e., not a crafting bench) in Creative mode, the
@@ -412,15 +446,21 @@ pub struct ConfirmTransactionPacket {
 /// causes the server to spawn the item entity, etc.. All other inventory slots are numbered the
 /// same as the non-creative inventory (including slots for the 2x2 crafting menu, even though they
 /// aren't visible in the vanilla client).
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x10)]
 pub struct CreativeInventoryActionPacket {
     /// Inventory slot.
     pub slot: i16,
-    // pub clicked_item: Slot,
+
+    /// The rest of the packet, which isn't typed yet:
+    ///
+    /// - `clicked_item` (slot)
+    pub rest: Remaining<Bytes>,
 }
 
 /// The ID of the enchantment table window sent by Open Window.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x11)]
 pub struct EnchantItemPacket {
     /// The ID of the enchantment table window sent by Open Window.
     pub window_id: i8,
@@ -435,7 +475,8 @@ pub struct EnchantItemPacket {
 ///
 /// The server only accepts this packet after Open Sign Editor, otherwise this packet is silently
 /// ignored.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x12, since = "V1_8")]
 pub struct UpdateSignPacket<'a> {
     /// Block Coordinates.
     pub location: Position,
@@ -458,7 +499,8 @@ pub struct UpdateSignPacket<'a> {
 ///
 /// The vanilla client sends this packet when the player starts/stops flying with the Flags
 /// parameter changed accordingly. All other parameters are ignored by the vanilla server.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x13)]
 pub struct PlayerAbilitiesPacket {
     /// Bit mask. 0x08: damage disabled (god mode),
     /// 0x04: can fly,
@@ -471,20 +513,19 @@ pub struct PlayerAbilitiesPacket {
 }
 
 /// Sent when the user presses tab while writing text.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x14, since = "V1_8")]
 pub struct TabCompletePacket {
     /// All text behind the cursor.
     pub text: String,
-    // pub has_position: bool,
 
-    // /// The position of the block being looked at. Only sent if Has Position is true.
-    // pub looked_at_block: Option<Position>,
+    /// The position of the block being looked at. Only sent if Has Position is true.
+    pub looked_at_block: Option<Position>,
 }
 
 /// Sent when the player connects, or when settings are changed.
-///
-///
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x15, since = "V1_8")]
 pub struct ClientSettingsPacket {
     /// e.g. en_GB.
     pub locale: String,
@@ -497,8 +538,9 @@ pub struct ClientSettingsPacket {
     /// 2: hidden.
     pub chat_mode: i8,
 
-    // /// “Colors” multiplayer setting.
-    // pub chat_colors: bool,
+    /// “Colors” multiplayer setting.
+    pub chat_colors: bool,
+
     /// Skin parts.
     ///
     /// Bit 0 (0x01): Cape enabled
@@ -517,7 +559,8 @@ pub struct ClientSettingsPacket {
 /// death.
 ///
 /// TODO: make table
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x16, since = "V1_8")]
 pub struct ClientStatusPacket {
     pub action_id: VarInt,
 }
@@ -530,14 +573,15 @@ pub struct ClientStatusPacket {
 ///
 /// Note that the length of Data is known only from the packet length, since the packet has no
 /// length field of any kind.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x17, since = "V1_8")]
 pub struct PluginMessagePacket {
-    // Name of the plugin channel used to send the data
     /// Any data, depending on the channel. MC| channels are documented here.
     pub channel: String,
-    // /// TODO: According to #mcdevs, the length of Data is known only from the packet length, since
-    // /// the packet has no length field of any kind.
-    // pub data: Vec<i8>,
+
+    /// The length of Data is known only from the packet length, since the packet has no length
+    /// field of any kind. Decoding from a `Bytes` frame doesn't copy it.
+    pub data: Remaining<Bytes>,
 }
 
 /// Teleports the player to the given entity. The player must be in spectator mode.
@@ -547,13 +591,15 @@ pub struct PluginMessagePacket {
 /// player will be respawned in the right world. If the given entity cannot be found (or isn't
 /// loaded), this packet will be ignored. It will also be ignored if the player attempts to
 /// teleport to themselves.
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x18, since = "V1_8")]
 pub struct SpectatePacket {
-    // /// UUID of the player to teleport to (can also be an entity UUID)
-    // pub target: UUID,
+    /// UUID of the player to teleport to (can also be an entity UUID)
+    pub target: Uuid,
 }
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x19, since = "V1_8")]
 pub struct ResourcePackStatusPacket {
     /// The hash sent in the Resource Pack Send packet.
     pub hash: String,
@@ -564,3 +610,35 @@ pub struct ResourcePackStatusPacket {
     /// 3: accepted.
     pub result: VarInt,
 }
+
+packet_set! {
+    /// Every serverbound packet in the play state.
+    ServerboundPlayPacket<'a> (Play, Serverbound) {
+        KeepAlive(KeepAlivePacket),
+        ChatMessage(ChatMessagePacket),
+        UseEntity(UseEntityPacket),
+        Player(PlayerPacket),
+        PlayerPosition(PlayerPositionPacket),
+        PlayerLook(PlayerLookPacket),
+        PlayerPositionAndLook(PlayerPositionAndLookPacket),
+        PlayerDigging(PlayerDiggingPacket),
+        PlayerBlockPlacement(PlayerBlockPlacementPacket),
+        HeldItemChange(HeldItemChangePacket),
+        Animation(AnimationPacket),
+        EntityAction(EntityActionPacket),
+        SteerVehicle(SteerVehiclePacket),
+        CloseWindow(CloseWindowPacket),
+        ClickWindow(ClickWindowPacket),
+        ConfirmTransaction(ConfirmTransactionPacket),
+        CreativeInventoryAction(CreativeInventoryActionPacket),
+        EnchantItem(EnchantItemPacket),
+        UpdateSign(UpdateSignPacket<'a>),
+        PlayerAbilities(PlayerAbilitiesPacket),
+        TabComplete(TabCompletePacket),
+        ClientSettings(ClientSettingsPacket),
+        ClientStatus(ClientStatusPacket),
+        PluginMessage(PluginMessagePacket),
+        Spectate(SpectatePacket),
+        ResourcePackStatus(ResourcePackStatusPacket),
+    }
+}
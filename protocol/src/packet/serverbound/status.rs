@@ -1,10 +1,26 @@
+// Generated by protocol-codegen from minecraft-data's 1.8 protocol. Don't edit this file;
+// change protocol-codegen/data instead, and run `cargo run -p protocol-codegen`.
+
 use protocol_macro::PacketDef;
+use serde::Serialize;
+
+use crate::packet::packet_set;
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x00)]
 pub struct RequestPacket {}
 
-#[derive(PacketDef)]
+#[derive(Debug, Clone, PartialEq, Serialize, PacketDef)]
+#[packet(id = 0x01)]
 pub struct PingPacket {
     /// Long that the server is supposed to respond with through a Pong packet.
     pub payload: i64,
 }
+
+packet_set! {
+    /// Every serverbound packet in the status state.
+    ServerboundStatusPacket (Status, Serverbound) {
+        Request(RequestPacket),
+        Ping(PingPacket),
+    }
+}
@@ -1,27 +1,58 @@
-use crate::encoding::Encodable;
-use byteorder::{BigEndian, ReadBytesExt};
+use crate::encoding::{ensure_remaining, ensure_remaining_mut, Decode, Encode};
 use bytes::{Buf, BufMut};
-use std::{io::Write, num::TryFromIntError};
+use serde::Serialize;
+use std::num::TryFromIntError;
 
-pub type Angle = f32;
+/// A rotation in steps of 1/256 of a full turn, as entity packets send yaw and pitch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Angle(pub u8);
 
-#[derive(Debug, Clone, Copy)]
+impl Angle {
+    pub fn from_degrees(degrees: f32) -> Self {
+        // wraps around, e.g. 360° is 0° again
+        Angle((degrees * 256.0 / 360.0).round() as i32 as u8)
+    }
+
+    pub fn degrees(self) -> f32 {
+        self.0 as f32 * 360.0 / 256.0
+    }
+}
+
+impl Decode for Angle {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
+        Ok(Angle(u8::decode(reader)?))
+    }
+}
+
+impl Encode for Angle {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        self.0.encode(writer)
+    }
+
+    fn encoded_len(&self) -> usize {
+        1
+    }
+}
+
+/// A block position, packed into a long as x (26 bits), y (12 bits) and z (26 bits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub struct Position {
     pub x: i32,
     pub y: i16,
     pub z: i32,
 }
 
-impl Encodable for Position {
-    fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self> {
-        let value = reader.reader().read_i64::<BigEndian>()?;
+impl Decode for Position {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
+        ensure_remaining(reader, 8)?;
+        let value = reader.get_i64();
 
         // we technically don't need this function here, but it's easier like this so we can handle the error mapping in a single call - instead of doing it for every single try_into() statement,
         // we also could implement From<TryFromIntError> into std::io::Error, but that's a lot of work, and I think this works just as fine.
         fn calc_x_y_z(value: i64) -> Result<(i32, i16, i32), TryFromIntError> {
             let x: i32 = (value >> 38).try_into()?;
-            let y: i16 = (value << 52 >> 52).try_into()?;
-            let z: i32 = (value << 26 >> 38).try_into()?;
+            let y: i16 = (value << 26 >> 52).try_into()?;
+            let z: i32 = (value << 38 >> 38).try_into()?;
 
             Ok((x, y, z))
         }
@@ -31,15 +62,33 @@ impl Encodable for Position {
 
         Ok(Position { x, y, z })
     }
+}
 
-    fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()> {
-        let value: i64 = (((self.x & 0x3FFFFFF) as i64) << 38) as i64
-            | ((self.z & 0x3FFFFFF) << 12) as i64
-            | (self.y as i32 & 0xFFF) as i64;
-
-        let buf = value.to_be_bytes();
+impl Encode for Position {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        let value: i64 = (((self.x & 0x3FFFFFF) as i64) << 38)
+            | (((self.y & 0xFFF) as i64) << 26)
+            | (self.z & 0x3FFFFFF) as i64;
 
-        writer.writer().write_all(&buf)?;
+        ensure_remaining_mut(writer, 8)?;
+        writer.put_i64(value);
         Ok(())
     }
+
+    fn encoded_len(&self) -> usize {
+        8
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Angle;
+
+    #[test]
+    pub fn angle_degrees() {
+        assert_eq!(Angle(64), Angle::from_degrees(90.0));
+        assert_eq!(Angle(192), Angle::from_degrees(-90.0));
+        assert_eq!(Angle(0), Angle::from_degrees(360.0));
+        assert_eq!(180.0, Angle(128).degrees());
+    }
 }
@@ -0,0 +1,632 @@
+//! A sans-IO connection state machine.
+//!
+//! A [ProtocolSession] is fed the bytes received from the other end, and hands back typed packets
+//! decoded for the current state. Packets sent through it are encoded into a buffer, ready to be
+//! written out. The state, compression and encryption are tracked along the way, so servers,
+//! clients and proxies don't each have to reimplement the handshake and login sequence.
+
+use std::marker::PhantomData;
+
+use anyhow::bail;
+use bytes::BytesMut;
+
+use crate::{
+    capture::CaptureRecord,
+    codec::{Frame, PacketDecoder, PacketEncoder},
+    packet::{
+        clientbound::{ClientboundLoginPacket, ClientboundPacket, ClientboundPlayPacket},
+        serverbound::{ServerboundHandshakingPacket, ServerboundPacket},
+        AnyStatePacket, StatePacket,
+    },
+    state::State,
+    varint::VarInt,
+    version::ProtocolVersion,
+};
+
+/// A change to a session, caused by a packet sent or received through it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// The handshake, with the next state and the client's protocol number.
+    Handshake(State, i32),
+    State(State),
+    Compression(Option<usize>),
+}
+
+/// Which end of a connection a session is, i.e. which packets it receives and sends.
+pub trait Side {
+    type Inbound: AnyStatePacket;
+    type Outbound: AnyStatePacket;
+
+    fn inbound_transition(packet: &Self::Inbound) -> anyhow::Result<Option<Transition>>;
+
+    fn outbound_transition(packet: &Self::Outbound) -> anyhow::Result<Option<Transition>>;
+}
+
+/// The server end, receiving serverbound packets.
+pub struct Server;
+
+/// The client end, receiving clientbound packets.
+pub struct Client;
+
+impl Side for Server {
+    type Inbound = ServerboundPacket<'static>;
+    type Outbound = ClientboundPacket<'static>;
+
+    fn inbound_transition(packet: &Self::Inbound) -> anyhow::Result<Option<Transition>> {
+        serverbound_transition(packet)
+    }
+
+    fn outbound_transition(packet: &Self::Outbound) -> anyhow::Result<Option<Transition>> {
+        clientbound_transition(packet)
+    }
+}
+
+impl Side for Client {
+    type Inbound = ClientboundPacket<'static>;
+    type Outbound = ServerboundPacket<'static>;
+
+    fn inbound_transition(packet: &Self::Inbound) -> anyhow::Result<Option<Transition>> {
+        clientbound_transition(packet)
+    }
+
+    fn outbound_transition(packet: &Self::Outbound) -> anyhow::Result<Option<Transition>> {
+        serverbound_transition(packet)
+    }
+}
+
+/// The transition caused by a serverbound packet, either way it goes.
+pub fn serverbound_transition(packet: &ServerboundPacket) -> anyhow::Result<Option<Transition>> {
+    match packet {
+        ServerboundPacket::Handshaking(ServerboundHandshakingPacket::Handshake(packet)) => {
+            let state = match packet.next_state {
+                VarInt(1) => State::Status,
+                VarInt(2) => State::Login,
+                VarInt(next_state) => bail!("invalid next state {} in handshake", next_state),
+            };
+
+            Ok(Some(Transition::Handshake(
+                state,
+                packet.protocol_version.0,
+            )))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The transition caused by a clientbound packet, either way it goes.
+pub fn clientbound_transition(packet: &ClientboundPacket) -> anyhow::Result<Option<Transition>> {
+    Ok(match packet {
+        ClientboundPacket::Login(ClientboundLoginPacket::Disconnect(_)) => {
+            Some(Transition::State(State::Closed))
+        }
+        ClientboundPacket::Login(ClientboundLoginPacket::LoginSuccess(_)) => {
+            Some(Transition::State(State::Play))
+        }
+        ClientboundPacket::Login(ClientboundLoginPacket::SetCompression(packet)) => {
+            Some(Transition::Compression(threshold(packet.threshold)))
+        }
+        ClientboundPacket::Play(ClientboundPlayPacket::SetCompression(packet)) => {
+            Some(Transition::Compression(threshold(packet.threshold)))
+        }
+        _ => None,
+    })
+}
+
+/// A negative threshold disables compression.
+fn threshold(threshold: VarInt) -> Option<usize> {
+    usize::try_from(threshold.0).ok()
+}
+
+/// The protocol state of one end of a connection, without any IO.
+pub struct ProtocolSession<S: Side> {
+    state: State,
+    protocol: i32,
+    encoder: PacketEncoder,
+    decoder: PacketDecoder,
+    captured: Option<Vec<CaptureRecord>>,
+    _side: PhantomData<S>,
+}
+
+pub type ServerSession = ProtocolSession<Server>;
+pub type ClientSession = ProtocolSession<Client>;
+
+impl<S: Side> Default for ProtocolSession<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Side> ProtocolSession<S> {
+    /// Creates a session in the handshaking state.
+    pub fn new() -> Self {
+        Self {
+            state: State::Handshaking,
+            protocol: ProtocolVersion::LATEST.protocol(),
+            encoder: PacketEncoder::new(),
+            decoder: PacketDecoder::new(),
+            captured: None,
+            _side: PhantomData,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// The protocol number from the handshake. Until then, the latest version's.
+    pub fn protocol(&self) -> i32 {
+        self.protocol
+    }
+
+    /// The version packets are sent and received in, or `None` if the other end is on an
+    /// unsupported version. In that case, packets are laid out as in the latest version, which is
+    /// enough for [outdated_disconnect](crate::version::outdated_disconnect).
+    pub fn version(&self) -> Option<ProtocolVersion> {
+        ProtocolVersion::from_protocol(self.protocol)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state == State::Closed
+    }
+
+    /// Marks the session as closed. Nothing can be sent or received afterwards.
+    pub fn close(&mut self) {
+        self.state = State::Closed;
+    }
+
+    /// Encrypts and decrypts everything from now on, once the shared secret is known (after the
+    /// encryption response on either end.)
+    pub fn enable_encryption(&mut self, key: &[u8; 16]) {
+        self.encoder.enable_encryption(key);
+        self.decoder.enable_encryption(key);
+    }
+
+    /// Queues bytes received from the other end.
+    pub fn receive(&mut self, bytes: &[u8]) {
+        self.decoder.queue_slice(bytes);
+    }
+
+    /// Queues bytes received from the other end, without copying them.
+    pub fn receive_bytes(&mut self, bytes: BytesMut) {
+        self.decoder.queue_bytes(bytes);
+    }
+
+    /// Decodes the next packet received, if a whole one was received yet.
+    ///
+    /// Fails if the packet is not valid in the current state.
+    pub fn next_packet(&mut self) -> anyhow::Result<Option<S::Inbound>> {
+        if self.is_closed() {
+            bail!("session is closed");
+        }
+
+        let frame = match self.decoder.try_next_frame()? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        if let Some(captured) = &mut self.captured {
+            captured.push(CaptureRecord::now(
+                S::Inbound::DIRECTION,
+                self.state,
+                self.protocol,
+                frame.clone(),
+            ));
+        }
+
+        let version = self.version().unwrap_or_default();
+        let packet =
+            S::Inbound::decode_by_state(version, self.state, frame.id, &mut frame.body.clone())?;
+
+        match S::inbound_transition(&packet)? {
+            Some(Transition::Handshake(state, protocol)) => self.handshake(state, protocol),
+            Some(Transition::State(state)) => self.state = state,
+            Some(Transition::Compression(threshold)) => self.set_compression(threshold),
+            None => {}
+        }
+
+        Ok(Some(packet))
+    }
+
+    /// Sends a packet. The direction is checked at compile time, and the state at runtime.
+    pub fn send<P>(&mut self, packet: P) -> anyhow::Result<()>
+    where
+        P: StatePacket<'static>,
+        S::Outbound: From<P::Set>,
+    {
+        self.send_any(S::Outbound::from(P::Set::from(packet)))
+    }
+
+    /// Sends a packet from any state. Fails if it is not valid in the current state.
+    pub fn send_any(&mut self, packet: S::Outbound) -> anyhow::Result<()> {
+        if packet.state() != self.state {
+            bail!(
+                "cannot send a {:?} packet (id {:#04x}) in the {:?} state",
+                packet.state(),
+                packet.id(),
+                self.state,
+            );
+        }
+
+        let transition = S::outbound_transition(&packet)?;
+        self.encoder.append_any(&packet)?;
+
+        if self.captured.is_some() {
+            self.capture_outbound(&packet)?;
+        }
+
+        // The packet enabling compression is itself sent uncompressed.
+        match transition {
+            Some(Transition::Handshake(state, protocol)) => self.handshake(state, protocol),
+            Some(Transition::State(state)) => self.state = state,
+            Some(Transition::Compression(threshold)) => self.set_compression(threshold),
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    /// Records every packet sent or received from now on, until the session is dropped. The records
+    /// are collected until they're taken through [ProtocolSession::take_captured].
+    pub fn start_capture(&mut self) {
+        self.captured.get_or_insert_with(Vec::new);
+    }
+
+    /// Takes the records captured since the last call.
+    pub fn take_captured(&mut self) -> Vec<CaptureRecord> {
+        self.captured
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn capture_outbound(&mut self, packet: &S::Outbound) -> anyhow::Result<()> {
+        let version = self.version().unwrap_or_default();
+        let mut body = BytesMut::new();
+        packet.encode_for(version, &mut body)?;
+
+        let frame = Frame {
+            id: packet.id_for(version).unwrap_or_else(|| packet.id()),
+            body: body.freeze(),
+        };
+        let record = CaptureRecord::now(S::Outbound::DIRECTION, self.state, self.protocol, frame);
+
+        if let Some(captured) = &mut self.captured {
+            captured.push(record);
+        }
+
+        Ok(())
+    }
+
+    /// Compression applies both ways, from the packet enabling it on.
+    fn set_compression(&mut self, threshold: Option<usize>) {
+        self.encoder.set_compression(threshold);
+        self.decoder.set_compression(threshold);
+    }
+
+    fn handshake(&mut self, state: State, protocol: i32) {
+        self.state = state;
+        self.protocol = protocol;
+        self.encoder.set_version(self.version().unwrap_or_default());
+    }
+
+    /// Whether there are bytes waiting to be written out.
+    pub fn has_outbound(&self) -> bool {
+        !self.encoder.is_empty()
+    }
+
+    /// Takes the bytes to write to the other end.
+    pub fn take_outbound(&mut self) -> BytesMut {
+        self.encoder.take()
+    }
+}
+
+/// Both directions of a connection, as seen by a proxy in between.
+///
+/// The bytes forwarded either way are fed in, and come out as frames, which can then be decoded.
+/// State changes and compression apply to both directions, whichever end caused them. Encrypted
+/// connections can't be followed, as the proxy doesn't know the shared secret.
+pub struct ProxySession {
+    state: State,
+    protocol: i32,
+    serverbound: PacketDecoder,
+    clientbound: PacketDecoder,
+}
+
+impl Default for ProxySession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProxySession {
+    /// Creates a session in the handshaking state.
+    pub fn new() -> Self {
+        Self {
+            state: State::Handshaking,
+            protocol: ProtocolVersion::LATEST.protocol(),
+            serverbound: PacketDecoder::new(),
+            clientbound: PacketDecoder::new(),
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// The protocol number from the handshake. Until then, the latest version's.
+    pub fn protocol(&self) -> i32 {
+        self.protocol
+    }
+
+    /// The version packets are laid out in, or `None` if the client is on an unsupported version.
+    pub fn version(&self) -> Option<ProtocolVersion> {
+        ProtocolVersion::from_protocol(self.protocol)
+    }
+
+    /// Queues bytes sent by the client.
+    pub fn receive_serverbound(&mut self, bytes: &[u8]) {
+        self.serverbound.queue_slice(bytes);
+    }
+
+    /// Queues bytes sent by the server.
+    pub fn receive_clientbound(&mut self, bytes: &[u8]) {
+        self.clientbound.queue_slice(bytes);
+    }
+
+    /// The next frame sent by the client, if a whole one was received yet.
+    ///
+    /// Every frame should be decoded before taking the next one, so changes to the state or
+    /// compression apply from the right frame on.
+    pub fn next_serverbound(&mut self) -> anyhow::Result<Option<Frame>> {
+        self.serverbound.try_next_frame()
+    }
+
+    /// The next frame sent by the server. See [ProxySession::next_serverbound].
+    pub fn next_clientbound(&mut self) -> anyhow::Result<Option<Frame>> {
+        self.clientbound.try_next_frame()
+    }
+
+    /// Decodes a frame sent by the client, in the current state.
+    pub fn decode_serverbound(
+        &mut self,
+        frame: &Frame,
+    ) -> anyhow::Result<ServerboundPacket<'static>> {
+        let packet = ServerboundPacket::decode_by_state(
+            self.version().unwrap_or_default(),
+            self.state,
+            frame.id,
+            &mut frame.body.clone(),
+        )?;
+
+        self.apply(serverbound_transition(&packet)?);
+        Ok(packet)
+    }
+
+    /// Decodes a frame sent by the server, in the current state.
+    pub fn decode_clientbound(
+        &mut self,
+        frame: &Frame,
+    ) -> anyhow::Result<ClientboundPacket<'static>> {
+        let packet = ClientboundPacket::decode_by_state(
+            self.version().unwrap_or_default(),
+            self.state,
+            frame.id,
+            &mut frame.body.clone(),
+        )?;
+
+        self.apply(clientbound_transition(&packet)?);
+        Ok(packet)
+    }
+
+    fn apply(&mut self, transition: Option<Transition>) {
+        match transition {
+            Some(Transition::Handshake(state, protocol)) => {
+                self.state = state;
+                self.protocol = protocol;
+            }
+            Some(Transition::State(state)) => self.state = state,
+            Some(Transition::Compression(threshold)) => {
+                self.serverbound.set_compression(threshold);
+                self.clientbound.set_compression(threshold);
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ClientSession, ProxySession, ServerSession};
+    use crate::{
+        packet::{
+            clientbound::{self, ClientboundLoginPacket, ClientboundPacket},
+            serverbound::{self, ServerboundLoginPacket, ServerboundPacket},
+            AnyStatePacket,
+        },
+        state::State,
+        varint::VarInt,
+        version::ProtocolVersion,
+    };
+
+    fn handshake(next_state: i32) -> serverbound::handshaking::HandshakePacket {
+        serverbound::handshaking::HandshakePacket {
+            protocol_version: VarInt(47),
+            server_address: "localhost".to_string(),
+            server_port: 25565,
+            next_state: VarInt(next_state),
+        }
+    }
+
+    #[test]
+    pub fn login() {
+        let mut client = ClientSession::new();
+        let mut server = ServerSession::new();
+
+        client.send(handshake(2)).unwrap();
+        client
+            .send(serverbound::login::LoginStartPacket {
+                name: "jam".to_string(),
+            })
+            .unwrap();
+        assert_eq!(State::Login, client.state());
+
+        server.receive(&client.take_outbound());
+        server.next_packet().unwrap().unwrap();
+        match server.next_packet().unwrap() {
+            Some(ServerboundPacket::Login(ServerboundLoginPacket::LoginStart(packet))) => {
+                assert_eq!("jam", packet.name)
+            }
+            _ => panic!("expected login start"),
+        }
+        assert_eq!(State::Login, server.state());
+
+        server
+            .send(clientbound::login::SetCompressionPacket {
+                threshold: VarInt(0),
+            })
+            .unwrap();
+        server
+            .send(clientbound::login::LoginSuccessPacket {
+                uuid: "00000000-0000-0000-0000-000000000000".to_string(),
+                username: "jam".to_string(),
+            })
+            .unwrap();
+        assert_eq!(State::Play, server.state());
+
+        client.receive(&server.take_outbound());
+        client.next_packet().unwrap().unwrap();
+        match client.next_packet().unwrap() {
+            Some(ClientboundPacket::Login(ClientboundLoginPacket::LoginSuccess(packet))) => {
+                assert_eq!("jam", packet.username)
+            }
+            _ => panic!("expected login success"),
+        }
+        assert_eq!(State::Play, client.state());
+        assert!(client.next_packet().unwrap().is_none());
+
+        // both ends compress from now on
+        client
+            .send(serverbound::play::ChatMessagePacket {
+                message: "hi".to_string(),
+            })
+            .unwrap();
+        server.receive(&client.take_outbound());
+        assert!(matches!(
+            server.next_packet().unwrap(),
+            Some(ServerboundPacket::Play(_))
+        ));
+    }
+
+    #[test]
+    pub fn reject_wrong_state() {
+        let mut client = ClientSession::new();
+        let mut server = ServerSession::new();
+
+        assert!(client.send(serverbound::status::RequestPacket {}).is_err());
+        assert!(!client.has_outbound());
+
+        client.send(handshake(1)).unwrap();
+        client.send(serverbound::status::RequestPacket {}).unwrap();
+
+        server.receive(&client.take_outbound());
+        server.next_packet().unwrap().unwrap();
+        assert_eq!(State::Status, server.state());
+        assert!(server
+            .send(clientbound::login::SetCompressionPacket {
+                threshold: VarInt(0),
+            })
+            .is_err());
+    }
+
+    #[test]
+    pub fn handshake_version() {
+        let mut client = ClientSession::new();
+        let mut server = ServerSession::new();
+
+        client
+            .send(serverbound::handshaking::HandshakePacket {
+                protocol_version: VarInt(5),
+                ..handshake(2)
+            })
+            .unwrap();
+
+        server.receive(&client.take_outbound());
+        server.next_packet().unwrap().unwrap();
+        assert_eq!(Some(ProtocolVersion::V1_7_10), server.version());
+
+        // 1.7 has no compression
+        assert!(server
+            .send(clientbound::login::SetCompressionPacket {
+                threshold: VarInt(0),
+            })
+            .is_err());
+    }
+
+    #[test]
+    pub fn reject_invalid_next_state() {
+        let mut client = ClientSession::new();
+        assert!(client.send(handshake(3)).is_err());
+        assert!(!client.has_outbound());
+    }
+
+    #[test]
+    pub fn proxy() {
+        let mut client = ClientSession::new();
+        let mut server = ServerSession::new();
+        let mut proxy = ProxySession::new();
+
+        client.send(handshake(2)).unwrap();
+        client
+            .send(serverbound::login::LoginStartPacket {
+                name: "jam".to_string(),
+            })
+            .unwrap();
+
+        let bytes = client.take_outbound();
+        proxy.receive_serverbound(&bytes);
+        server.receive(&bytes);
+        while server.next_packet().unwrap().is_some() {}
+
+        let mut names = Vec::new();
+        while let Some(frame) = proxy.next_serverbound().unwrap() {
+            names.push(proxy.decode_serverbound(&frame).unwrap().name());
+        }
+        assert_eq!(vec!["Handshake", "LoginStart"], names);
+        assert_eq!(State::Login, proxy.state());
+
+        server
+            .send(clientbound::login::SetCompressionPacket {
+                threshold: VarInt(0),
+            })
+            .unwrap();
+        server
+            .send(clientbound::login::LoginSuccessPacket {
+                uuid: "00000000-0000-0000-0000-000000000000".to_string(),
+                username: "jam".to_string(),
+            })
+            .unwrap();
+
+        // both frames arrive at once, the second one compressed
+        let bytes = server.take_outbound();
+        proxy.receive_clientbound(&bytes);
+        while let Some(frame) = proxy.next_clientbound().unwrap() {
+            proxy.decode_clientbound(&frame).unwrap();
+        }
+        assert_eq!(State::Play, proxy.state());
+
+        client.receive(&bytes);
+        while client.next_packet().unwrap().is_some() {}
+        client
+            .send(serverbound::play::ChatMessagePacket {
+                message: "hi".to_string(),
+            })
+            .unwrap();
+        proxy.receive_serverbound(&client.take_outbound());
+
+        let frame = proxy.next_serverbound().unwrap().unwrap();
+        assert_eq!(
+            "ChatMessage",
+            proxy.decode_serverbound(&frame).unwrap().name()
+        );
+    }
+}
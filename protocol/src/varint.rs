@@ -1,58 +1,174 @@
-use std::io::{Read, Write};
-
+use anyhow::bail;
 use bytes::{Buf, BufMut};
+use serde::Serialize;
 
-use crate::encoding::Encodable;
+use crate::encoding::{ensure_remaining, ensure_remaining_mut, Decode, Encode};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct VarInt(pub i32);
 
-impl Into<usize> for VarInt {
-    fn into(self) -> usize {
-        self.0 as usize
+impl VarInt {
+    /// The maximum amount of bytes a VarInt can take up on the wire.
+    pub const MAX_SIZE: usize = 5;
+
+    /// Returns the amount of bytes this VarInt takes up when encoded.
+    pub fn encoded_len(&self) -> usize {
+        match self.0 as u32 {
+            0..=0x7F => 1,
+            0x80..=0x3FFF => 2,
+            0x4000..=0x1FFFFF => 3,
+            0x200000..=0xFFFFFFF => 4,
+            _ => 5,
+        }
+    }
+
+    /// Decodes a VarInt from the start of `buf`, without consuming it.
+    ///
+    /// Returns the VarInt and its size in bytes, or `None` if `buf` ends before the VarInt does.
+    /// This is what frame decoding uses, as a frame's length may arrive in more than one read.
+    pub fn decode_partial(buf: &[u8]) -> anyhow::Result<Option<(VarInt, usize)>> {
+        let mut result = 0;
+
+        for (i, byte) in buf.iter().take(Self::MAX_SIZE).enumerate() {
+            result |= ((byte & 0b01111111) as i32) << (7 * i);
+
+            if byte & 0b10000000 == 0 {
+                return Ok(Some((VarInt(result), i + 1)));
+            }
+        }
+
+        if buf.len() >= Self::MAX_SIZE {
+            bail!("VarInt is longer than {} bytes", Self::MAX_SIZE);
+        }
+
+        Ok(None)
+    }
+}
+
+impl From<VarInt> for usize {
+    fn from(value: VarInt) -> Self {
+        value.0 as usize
     }
 }
 
-impl Into<i32> for VarInt {
-    fn into(self) -> i32 {
-        self.0 as i32
+impl From<VarInt> for i32 {
+    fn from(value: VarInt) -> Self {
+        value.0
     }
 }
 
-impl Encodable for VarInt {
-    fn decode(reader: &mut dyn Buf) -> anyhow::Result<Self> {
+impl Decode for VarInt {
+    fn decode<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Self> {
         let mut result = 0;
         let mut shift = 0;
 
         loop {
-            let mut byte = [0];
+            ensure_remaining(reader, 1)?;
+            let byte = reader.get_u8();
 
-            reader.reader().read_exact(&mut byte)?;
-
-            let value = (byte[0] & 0b01111111) as i32;
+            let value = (byte & 0b01111111) as i32;
 
             result |= value << shift;
             shift += 7;
 
-            if byte[0] & 0b10000000 == 0 {
+            if byte & 0b10000000 == 0 {
                 break;
             }
+
+            if shift >= 7 * Self::MAX_SIZE {
+                bail!("VarInt is longer than {} bytes", Self::MAX_SIZE);
+            }
         }
 
         Ok(VarInt(result))
     }
+}
 
-    fn encode(&self, writer: &mut dyn BufMut) -> anyhow::Result<()> {
-        let mut remaining = self.0;
-        while remaining >= 0b10000000 {
-            let byte = (remaining as u8) | 0b10000000;
+impl Encode for VarInt {
+    fn encode<B: BufMut + ?Sized>(&self, writer: &mut B) -> anyhow::Result<()> {
+        let mut buf = [0; Self::MAX_SIZE];
+        let mut len = 0;
 
-            writer.writer().write_all(&[byte])?;
+        // work on the unsigned value, so negative numbers are written as 5 bytes instead of a
+        // single one
+        let mut remaining = self.0 as u32;
+        while remaining >= 0b10000000 {
+            buf[len] = (remaining as u8) | 0b10000000;
             remaining >>= 7;
+            len += 1;
         }
-        let byte = remaining as u8;
 
-        writer.writer().write_all(&[byte])?;
+        buf[len] = remaining as u8;
+        len += 1;
+
+        // write everything at once, instead of one call per byte
+        ensure_remaining_mut(writer, len)?;
+        writer.put_slice(&buf[..len]);
         Ok(())
     }
+
+    fn encoded_len(&self) -> usize {
+        VarInt::encoded_len(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::BytesMut;
+
+    use super::VarInt;
+    use crate::encoding::{Decode, Encode};
+
+    /// Samples taken from wiki.vg.
+    const SAMPLES: &[(i32, &[u8])] = &[
+        (0, &[0x00]),
+        (1, &[0x01]),
+        (127, &[0x7f]),
+        (128, &[0x80, 0x01]),
+        (255, &[0xff, 0x01]),
+        (25565, &[0xdd, 0xc7, 0x01]),
+        (2097151, &[0xff, 0xff, 0x7f]),
+        (2147483647, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+        (-1, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+        (-2147483648, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+    ];
+
+    #[test]
+    pub fn encode() {
+        for (value, bytes) in SAMPLES {
+            let mut buf = BytesMut::new();
+            VarInt(*value).encode(&mut buf).unwrap();
+
+            assert_eq!(*bytes, &buf[..]);
+            assert_eq!(bytes.len(), VarInt(*value).encoded_len());
+        }
+    }
+
+    #[test]
+    pub fn decode() {
+        for (value, mut bytes) in SAMPLES {
+            assert_eq!(VarInt(*value), VarInt::decode(&mut bytes).unwrap());
+            assert!(bytes.is_empty());
+        }
+    }
+
+    #[test]
+    pub fn decode_short() {
+        assert!(VarInt::decode(&mut &[0x80, 0x80][..]).is_err());
+    }
+
+    #[test]
+    pub fn decode_partial() {
+        assert_eq!(None, VarInt::decode_partial(&[0xdd, 0xc7]).unwrap());
+        assert_eq!(
+            Some((VarInt(25565), 3)),
+            VarInt::decode_partial(&[0xdd, 0xc7, 0x01, 0x00]).unwrap()
+        );
+        assert!(VarInt::decode_partial(&[0xff; 5]).is_err());
+    }
+
+    #[test]
+    pub fn decode_too_long() {
+        assert!(VarInt::decode(&mut &[0xff, 0xff, 0xff, 0xff, 0xff, 0x01][..]).is_err());
+    }
 }
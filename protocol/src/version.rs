@@ -0,0 +1,377 @@
+//! Protocol versions, and how packets differ between them.
+//!
+//! Packets are defined once, laid out as in the latest version. A packet that has another ID or
+//! layout in an older version implements [VersionedPacket] by hand, and a packet that doesn't
+//! exist before some version is marked with `#[packet(since = "..")]`.
+//!
+//! The packet definitions are generated from minecraft-data's protocol specs, which mark every
+//! packet missing from 1.7.10 *or laid out differently there* with `since`, unless it is
+//! `versioned` in `protocol-codegen/data/packets.toml`. So far, only the packets below are ported
+//! by hand, which leaves most play packets 1.8-only, and 1.8 the only version that can play.
+
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::{
+    chat::ChatComponent,
+    dissect::{Dissect, Dissection, Dissector},
+    encoding::{ensure_remaining, ensure_remaining_mut, Decode, Encode},
+    packet::{clientbound, serverbound, Packet},
+    varint::VarInt,
+};
+
+/// A protocol version Jam can talk, picked from the handshake.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    /// 1.7.6 to 1.7.10, up to the login only.
+    ///
+    /// 1.7.10 clients aren't supported: the handshake, status and login packets are ported, so
+    /// they can be shown as incompatible and told to update, but the play state isn't (see the
+    /// module docs and [can_play](Self::can_play)).
+    V1_7_10,
+
+    /// 1.8 to 1.8.9.
+    #[default]
+    V1_8,
+}
+
+impl ProtocolVersion {
+    pub const LATEST: Self = Self::V1_8;
+    pub const OLDEST: Self = Self::V1_7_10;
+
+    /// The version with the given protocol number, if it is supported.
+    pub fn from_protocol(protocol: i32) -> Option<Self> {
+        match protocol {
+            5 => Some(Self::V1_7_10),
+            47 => Some(Self::V1_8),
+            _ => None,
+        }
+    }
+
+    /// The protocol number sent in the handshake.
+    pub fn protocol(self) -> i32 {
+        match self {
+            Self::V1_7_10 => 5,
+            Self::V1_8 => 47,
+        }
+    }
+
+    /// Whether every packet of the play state is ported to this version, so clients on it can get
+    /// past the login.
+    pub fn can_play(self) -> bool {
+        match self {
+            Self::V1_7_10 => false,
+            Self::V1_8 => true,
+        }
+    }
+
+    /// The name of the version, as shown to players.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::V1_7_10 => "1.7.10",
+            Self::V1_8 => "1.8",
+        }
+    }
+}
+
+/// A packet which can be sent in several protocol versions.
+///
+/// The default methods are for packets that have the same ID and layout in every version.
+pub trait VersionedPacket: Packet {
+    /// The ID of this packet in `version`, or `None` if it doesn't exist there.
+    fn id_for(_version: ProtocolVersion) -> Option<i32> {
+        Some(Self::ID)
+    }
+
+    fn encode_for<B: BufMut + ?Sized>(
+        &self,
+        _version: ProtocolVersion,
+        writer: &mut B,
+    ) -> anyhow::Result<()> {
+        self.encode(writer)
+    }
+
+    fn decode_for<B: Buf + ?Sized>(
+        _version: ProtocolVersion,
+        reader: &mut B,
+    ) -> anyhow::Result<Self>
+    where
+        Self: Sized + Decode,
+    {
+        Self::decode(reader)
+    }
+
+    /// Dissects the body of this packet, laid out as in `version`.
+    fn dissect_for(_version: ProtocolVersion, body: &[u8]) -> Dissection
+    where
+        Self: Dissect,
+    {
+        Self::dissect(body)
+    }
+}
+
+/// The login disconnect the vanilla server sends to clients on an unsupported version, or `None`
+/// if `protocol` is supported.
+pub fn outdated_disconnect(protocol: i32) -> Option<clientbound::login::DisconnectPacket<'static>> {
+    if ProtocolVersion::from_protocol(protocol).is_some() {
+        return None;
+    }
+
+    let text = if protocol > ProtocolVersion::LATEST.protocol() {
+        format!(
+            "Outdated server! I'm still on {}",
+            ProtocolVersion::LATEST.name()
+        )
+    } else {
+        format!(
+            "Outdated client! Please use {}",
+            ProtocolVersion::LATEST.name()
+        )
+    };
+
+    Some(clientbound::login::DisconnectPacket {
+        reason: ChatComponent::builder().text(text).build(),
+    })
+}
+
+/// A byte array prefixed with its length as a short, as 1.7 sends them.
+fn encode_short_bytes<B: BufMut + ?Sized>(bytes: &Bytes, writer: &mut B) -> anyhow::Result<()> {
+    i16::try_from(bytes.len())?.encode(writer)?;
+
+    ensure_remaining_mut(writer, bytes.len())?;
+    writer.put_slice(bytes);
+
+    Ok(())
+}
+
+fn decode_short_bytes<B: Buf + ?Sized>(reader: &mut B) -> anyhow::Result<Bytes> {
+    let len = usize::try_from(i16::decode(reader)?)?;
+    ensure_remaining(reader, len)?;
+
+    Ok(reader.copy_to_bytes(len))
+}
+
+/// 1.7 sends the lengths of the key and token as shorts.
+impl VersionedPacket for clientbound::login::EncryptionRequestPacket {
+    fn encode_for<B: BufMut + ?Sized>(
+        &self,
+        version: ProtocolVersion,
+        writer: &mut B,
+    ) -> anyhow::Result<()> {
+        match version {
+            ProtocolVersion::V1_7_10 => {
+                self.server_id.encode(writer)?;
+                encode_short_bytes(&self.public_key, writer)?;
+                encode_short_bytes(&self.verify_token, writer)
+            }
+            ProtocolVersion::V1_8 => self.encode(writer),
+        }
+    }
+
+    fn decode_for<B: Buf + ?Sized>(
+        version: ProtocolVersion,
+        reader: &mut B,
+    ) -> anyhow::Result<Self> {
+        match version {
+            ProtocolVersion::V1_7_10 => Ok(Self {
+                server_id: String::decode(reader)?,
+                public_key: decode_short_bytes(reader)?,
+                verify_token: decode_short_bytes(reader)?,
+            }),
+            ProtocolVersion::V1_8 => Self::decode(reader),
+        }
+    }
+
+    fn dissect_for(version: ProtocolVersion, body: &[u8]) -> Dissection {
+        match version {
+            ProtocolVersion::V1_7_10 => {
+                let mut dissector = Dissector::new(body);
+                dissector.field("server_id", "String", String::decode);
+                dissector.field("public_key", "short-prefixed Bytes", decode_short_bytes);
+                dissector.field("verify_token", "short-prefixed Bytes", decode_short_bytes);
+                dissector.finish()
+            }
+            ProtocolVersion::V1_8 => Self::dissect(body),
+        }
+    }
+}
+
+/// 1.7 sends the lengths of the secret and token as shorts.
+impl VersionedPacket for serverbound::login::EncryptionResponsePacket {
+    fn encode_for<B: BufMut + ?Sized>(
+        &self,
+        version: ProtocolVersion,
+        writer: &mut B,
+    ) -> anyhow::Result<()> {
+        match version {
+            ProtocolVersion::V1_7_10 => {
+                encode_short_bytes(&self.shared_secret, writer)?;
+                encode_short_bytes(&self.verify_token, writer)
+            }
+            ProtocolVersion::V1_8 => self.encode(writer),
+        }
+    }
+
+    fn decode_for<B: Buf + ?Sized>(
+        version: ProtocolVersion,
+        reader: &mut B,
+    ) -> anyhow::Result<Self> {
+        match version {
+            ProtocolVersion::V1_7_10 => Ok(Self {
+                shared_secret: decode_short_bytes(reader)?,
+                verify_token: decode_short_bytes(reader)?,
+            }),
+            ProtocolVersion::V1_8 => Self::decode(reader),
+        }
+    }
+
+    fn dissect_for(version: ProtocolVersion, body: &[u8]) -> Dissection {
+        match version {
+            ProtocolVersion::V1_7_10 => {
+                let mut dissector = Dissector::new(body);
+                dissector.field("shared_secret", "short-prefixed Bytes", decode_short_bytes);
+                dissector.field("verify_token", "short-prefixed Bytes", decode_short_bytes);
+                dissector.finish()
+            }
+            ProtocolVersion::V1_8 => Self::dissect(body),
+        }
+    }
+}
+
+/// 1.7 sends the keep-alive ID as an int.
+macro_rules! keep_alive {
+    ($packet:ty) => {
+        impl VersionedPacket for $packet {
+            fn encode_for<B: BufMut + ?Sized>(
+                &self,
+                version: ProtocolVersion,
+                writer: &mut B,
+            ) -> anyhow::Result<()> {
+                match version {
+                    ProtocolVersion::V1_7_10 => self.id.0.encode(writer),
+                    ProtocolVersion::V1_8 => self.encode(writer),
+                }
+            }
+
+            fn decode_for<B: Buf + ?Sized>(
+                version: ProtocolVersion,
+                reader: &mut B,
+            ) -> anyhow::Result<Self> {
+                match version {
+                    ProtocolVersion::V1_7_10 => Ok(Self {
+                        id: VarInt(i32::decode(reader)?),
+                    }),
+                    ProtocolVersion::V1_8 => Self::decode(reader),
+                }
+            }
+
+            fn dissect_for(version: ProtocolVersion, body: &[u8]) -> Dissection {
+                match version {
+                    ProtocolVersion::V1_7_10 => {
+                        let mut dissector = Dissector::new(body);
+                        dissector.field("id", "i32", i32::decode);
+                        dissector.finish()
+                    }
+                    ProtocolVersion::V1_8 => Self::dissect(body),
+                }
+            }
+        }
+    };
+}
+
+keep_alive!(clientbound::play::KeepAlivePacket);
+keep_alive!(serverbound::play::KeepAlivePacket);
+
+#[cfg(test)]
+mod test {
+    use bytes::{Bytes, BytesMut};
+
+    use super::{outdated_disconnect, ProtocolVersion, VersionedPacket};
+    use crate::{
+        packet::{
+            clientbound::{self, ClientboundLoginPacket, ClientboundPlayPacket},
+            serverbound, PacketSet,
+        },
+        varint::VarInt,
+    };
+
+    #[test]
+    pub fn from_protocol() {
+        for version in [ProtocolVersion::V1_7_10, ProtocolVersion::V1_8] {
+            assert_eq!(
+                Some(version),
+                ProtocolVersion::from_protocol(version.protocol())
+            );
+        }
+
+        assert_eq!(None, ProtocolVersion::from_protocol(4));
+        assert!(!ProtocolVersion::V1_7_10.can_play());
+        assert!(ProtocolVersion::LATEST.can_play());
+        assert!(outdated_disconnect(5).is_none());
+        assert!(outdated_disconnect(47).is_none());
+        assert!(outdated_disconnect(340).is_some());
+    }
+
+    #[test]
+    pub fn keep_alive_layout() {
+        let packet = clientbound::play::KeepAlivePacket { id: VarInt(1) };
+
+        let mut buf = BytesMut::new();
+        packet
+            .encode_for(ProtocolVersion::V1_7_10, &mut buf)
+            .unwrap();
+        assert_eq!(&[0, 0, 0, 1], &buf[..]);
+
+        let mut buf = BytesMut::new();
+        packet.encode_for(ProtocolVersion::V1_8, &mut buf).unwrap();
+        assert_eq!(&[1], &buf[..]);
+
+        match ClientboundPlayPacket::decode_for(
+            ProtocolVersion::V1_7_10,
+            0x00,
+            &mut &[0, 0, 1, 0][..],
+        )
+        .unwrap()
+        {
+            ClientboundPlayPacket::KeepAlive(packet) => assert_eq!(VarInt(256), packet.id),
+            _ => panic!("expected keep alive"),
+        }
+    }
+
+    #[test]
+    pub fn encryption_layout() {
+        let packet = serverbound::login::EncryptionResponsePacket {
+            shared_secret: Bytes::from_static(&[1, 2]),
+            verify_token: Bytes::from_static(&[3]),
+        };
+
+        let mut buf = BytesMut::new();
+        packet
+            .encode_for(ProtocolVersion::V1_7_10, &mut buf)
+            .unwrap();
+        assert_eq!(&[0, 2, 1, 2, 0, 1, 3], &buf[..]);
+
+        let decoded = serverbound::login::EncryptionResponsePacket::decode_for(
+            ProtocolVersion::V1_7_10,
+            &mut &buf[..],
+        )
+        .unwrap();
+        assert_eq!(packet.shared_secret, decoded.shared_secret);
+        assert_eq!(packet.verify_token, decoded.verify_token);
+    }
+
+    #[test]
+    pub fn missing_in_1_7() {
+        assert_eq!(
+            None,
+            clientbound::login::SetCompressionPacket::id_for(ProtocolVersion::V1_7_10)
+        );
+        assert_eq!(
+            Some(0x03),
+            clientbound::login::SetCompressionPacket::id_for(ProtocolVersion::V1_8)
+        );
+        assert!(
+            ClientboundLoginPacket::decode_for(ProtocolVersion::V1_7_10, 0x03, &mut &[0][..])
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! Decodes every frame of the captures in `captures/` and encodes the packet back into the same
+//! bytes.
+//!
+//! The captures are vanilla sessions recorded through jam-proxy (see `captures/README.md`), so
+//! unlike `tests/reference.rs` they check this crate against what vanilla actually sends.
+
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use bytes::BytesMut;
+use protocol::{
+    capture::{CaptureReader, CapturedPacket},
+    packet::AnyStatePacket,
+    version::ProtocolVersion,
+};
+
+/// The `.jamcap` files in `captures/`, in order.
+fn captures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/captures");
+
+    let mut captures = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "jamcap")
+        })
+        .collect::<Vec<_>>();
+    captures.sort();
+
+    captures
+}
+
+#[test]
+pub fn round_trip() {
+    for path in captures() {
+        let reader = CaptureReader::new(BufReader::new(File::open(&path).unwrap())).unwrap();
+
+        for (index, record) in reader.enumerate() {
+            let record = record.unwrap();
+            let name = format!("{} (frame {})", path.display(), index);
+            let packet = record
+                .decode()
+                .unwrap_or_else(|error| panic!("{}: {:#}", name, error));
+
+            let version = ProtocolVersion::from_protocol(record.protocol).unwrap_or_default();
+            let mut body = BytesMut::new();
+            let id = match &packet {
+                CapturedPacket::Serverbound(packet) => {
+                    packet.encode_for(version, &mut body).unwrap();
+                    packet.id_for(version)
+                }
+                CapturedPacket::Clientbound(packet) => {
+                    packet.encode_for(version, &mut body).unwrap();
+                    packet.id_for(version)
+                }
+            };
+
+            assert_eq!(Some(record.frame.id), id, "{}", name);
+            assert_eq!(record.frame.body, body, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Feeds random and mangled packet bodies through the decoders of every state.
+//!
+//! Decoding untrusted input must neither panic nor allocate more than the input could possibly
+//! hold, which a counting allocator checks for. This lives in its own test binary, as it replaces
+//! the global allocator.
+
+// the generator the property tests use
+#[path = "../src/rng.rs"]
+mod rng;
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use protocol::{
+    packet::{
+        clientbound::{self, ClientboundPacket},
+        serverbound::{self, ServerboundPacket},
+        AnyStatePacket, Packet,
+    },
+    state::State,
+    varint::VarInt,
+    version::ProtocolVersion,
+};
+use rng::Rng;
+
+/// Remembers the largest allocation made on each thread.
+struct CountingAlloc;
+
+thread_local! {
+    static LARGEST: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+fn record(size: usize) {
+    // `try_with`, as the thread local might already be gone while a thread shuts down
+    let _ = LARGEST.try_with(|largest| largest.set(largest.get().max(size)));
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+const STATES: [State; 5] = [
+    State::Handshaking,
+    State::Status,
+    State::Login,
+    State::Play,
+    State::Closed,
+];
+
+const VERSIONS: [ProtocolVersion; 2] = [ProtocolVersion::V1_7_10, ProtocolVersion::V1_8];
+
+/// Decodes `body` as every packet it could be, in both directions.
+fn decode_all(state: State, id: i32, body: &[u8]) {
+    for version in VERSIONS {
+        LARGEST.with(|largest| largest.set(0));
+
+        let _ = ServerboundPacket::decode_by_state(version, state, id, &mut &body[..]);
+        let _ = ClientboundPacket::decode_by_state(version, state, id, &mut &body[..]);
+
+        // anything allocated is either proportional to the input, or small; e.g. a `Vec` of
+        // `String`s reserves one `String` per remaining byte at most.
+        let largest = LARGEST.with(Cell::get);
+        assert!(
+            largest <= 4096 + 64 * body.len(),
+            "allocated {} bytes decoding {} bytes as {:#04x} ({:?}, {:?})",
+            largest,
+            body.len(),
+            id,
+            state,
+            version,
+        );
+    }
+}
+
+/// Valid bodies to mangle, so the fuzzer also gets past the first few fields.
+fn seeds() -> Vec<(State, i32, Vec<u8>)> {
+    fn seed<P: Packet>(state: State, packet: P) -> (State, i32, Vec<u8>) {
+        let mut body = Vec::new();
+        packet.encode(&mut body).unwrap();
+
+        (state, P::ID, body)
+    }
+
+    vec![
+        seed(
+            State::Handshaking,
+            serverbound::handshaking::HandshakePacket {
+                protocol_version: VarInt(47),
+                server_address: "localhost".to_string(),
+                server_port: 25565,
+                next_state: VarInt(2),
+            },
+        ),
+        seed(
+            State::Login,
+            serverbound::login::LoginStartPacket {
+                name: "jam".to_string(),
+            },
+        ),
+        seed(
+            State::Login,
+            clientbound::login::LoginSuccessPacket {
+                uuid: "00000000-0000-0000-0000-000000000000".to_string(),
+                username: "jam".to_string(),
+            },
+        ),
+        seed(
+            State::Play,
+            serverbound::play::ChatMessagePacket {
+                message: "hello".to_string(),
+            },
+        ),
+        seed(
+            State::Play,
+            serverbound::play::PlayerPositionPacket {
+                x: 0.5,
+                y: 64.0,
+                z: -0.5,
+                on_ground: true,
+            },
+        ),
+    ]
+}
+
+#[test]
+pub fn random_bodies() {
+    let mut rng = Rng::new(0x4A414D);
+
+    for _ in 0..2000 {
+        let id = rng.below(0x50) as i32;
+        let len = rng.below(64) as usize;
+        let body = rng.bytes(len);
+
+        for state in STATES {
+            decode_all(state, id, &body);
+        }
+    }
+}
+
+#[test]
+pub fn mangled_bodies() {
+    let mut rng = Rng::new(0x4A414D);
+
+    for (state, id, body) in seeds() {
+        for _ in 0..500 {
+            let mut body = body.clone();
+
+            match rng.below(4) {
+                // flip some bytes, which also turns lengths into huge or negative ones
+                0 => {
+                    for _ in 0..=rng.below(4) {
+                        let i = rng.below(body.len() as u64) as usize;
+                        body[i] = rng.next_u64() as u8;
+                    }
+                }
+                1 => body.truncate(rng.below(body.len() as u64) as usize),
+                2 => {
+                    let i = rng.below(body.len() as u64 + 1) as usize;
+                    let extra = rng.below(8) as usize;
+                    body.splice(i..i, rng.bytes(extra));
+                }
+                // a length prefix claiming far more than there is
+                _ => {
+                    body.splice(0..0, [0xff, 0xff, 0xff, 0xff, 0x07]);
+                }
+            }
+
+            decode_all(state, id, &body);
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! Decodes every frame of `reference/1.8.txt` into the packet it's expected to hold, and encodes
+//! that packet back into the same bytes.
+//!
+//! The frames are written by hand from the protocol documentation, so they only check this crate
+//! against that reading of it: a field laid out wrongly (e.g. the bits of a `Position`) fails here
+//! even if it round-trips fine. Sessions recorded from vanilla go in `captures/` instead (see
+//! `tests/captures.rs`).
+
+use bytes::Bytes;
+use protocol::{
+    auth::offline_uuid,
+    chat::ChatComponent,
+    codec::{PacketDecoder, PacketEncoder},
+    encoding::byte_array::Remaining,
+    packet::{
+        clientbound::{self, ClientboundPacket},
+        serverbound::{self, ServerboundPacket},
+        AnyStatePacket, StatePacket,
+    },
+    position::{Angle, Position},
+    state::State,
+    varint::VarInt,
+    version::ProtocolVersion,
+};
+
+const CORPUS: &str = include_str!("reference/1.8.txt");
+
+/// One frame of the corpus.
+struct Entry {
+    name: String,
+    direction: String,
+    state: State,
+    frame: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Expected {
+    Serverbound(ServerboundPacket<'static>),
+    Clientbound(ClientboundPacket<'static>),
+}
+
+fn corpus() -> Vec<Entry> {
+    let mut entries = Vec::<Entry>::new();
+
+    for line in CORPUS.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let (name, rest) = header.split_once("] ").expect("invalid header");
+            let (direction, state) = rest.split_once(' ').expect("invalid header");
+
+            entries.push(Entry {
+                name: name.to_string(),
+                direction: direction.to_string(),
+                state: match state {
+                    "handshaking" => State::Handshaking,
+                    "status" => State::Status,
+                    "login" => State::Login,
+                    "play" => State::Play,
+                    _ => panic!("unknown state {}", state),
+                },
+                frame: Vec::new(),
+            });
+            continue;
+        }
+
+        let entry = entries.last_mut().expect("frame without a header");
+        for byte in line.split_whitespace() {
+            entry.frame.push(u8::from_str_radix(byte, 16).unwrap());
+        }
+    }
+
+    entries
+}
+
+fn s<P: StatePacket<'static>>(packet: P) -> Expected
+where
+    ServerboundPacket<'static>: From<P::Set>,
+{
+    Expected::Serverbound(P::Set::from(packet).into())
+}
+
+fn c<P: StatePacket<'static>>(packet: P) -> Expected
+where
+    ClientboundPacket<'static>: From<P::Set>,
+{
+    Expected::Clientbound(P::Set::from(packet).into())
+}
+
+/// The packets the corpus holds, by name.
+fn expected() -> Vec<(&'static str, Expected)> {
+    // one section of bedrock at y = 0 and air above, followed by block light, sky light and biomes
+    let mut chunk = vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+    for i in 0..4096 {
+        chunk.extend_from_slice(if i < 256 { &[0x70, 0] } else { &[0, 0] });
+    }
+    chunk.extend_from_slice(&[0xff; 4096]);
+    chunk.extend_from_slice(&[1; 256]);
+
+    vec![
+        (
+            "handshake",
+            s(serverbound::handshaking::HandshakePacket {
+                protocol_version: VarInt(47),
+                server_address: "localhost".to_string(),
+                server_port: 25565,
+                next_state: VarInt(2),
+            }),
+        ),
+        (
+            "login_start",
+            s(serverbound::login::LoginStartPacket {
+                name: "jam".to_string(),
+            }),
+        ),
+        (
+            "login_success",
+            c(clientbound::login::LoginSuccessPacket {
+                uuid: offline_uuid("jam").hyphenated().to_string(),
+                username: "jam".to_string(),
+            }),
+        ),
+        (
+            "join_game",
+            c(clientbound::play::JoinGamePacket {
+                entity_id: 1,
+                gamemode: 1,
+                dimension: 0,
+                difficulty: 2,
+                max_players: 20,
+                level_type: "default".to_string(),
+                reduced_debug_info: false,
+            }),
+        ),
+        (
+            "spawn_position",
+            c(clientbound::play::SpawnPositionPacket {
+                location: Position {
+                    x: -123,
+                    y: 64,
+                    z: 456,
+                },
+            }),
+        ),
+        (
+            "player_position_and_look_clientbound",
+            c(clientbound::play::PlayerPositionAndLookPacket {
+                x: 0.5,
+                y: 65.0,
+                z: -0.5,
+                yaw: 90.0,
+                pitch: 0.0,
+                flags: 0,
+            }),
+        ),
+        (
+            "chunk_unload",
+            c(clientbound::play::ChunkDataPacket {
+                chunk_x: 3,
+                chunk_z: -2,
+                ground_up_continuous: true,
+                primary_bit_mask: 0,
+                data: Bytes::new(),
+            }),
+        ),
+        (
+            "map_chunk_bulk",
+            c(clientbound::play::MapChunkBulkPacket {
+                sky_light_sent: true,
+                rest: Remaining(Bytes::from(chunk)),
+            }),
+        ),
+        (
+            "player_position",
+            s(serverbound::play::PlayerPositionPacket {
+                x: 0.5,
+                y: 65.0,
+                z: -0.5,
+                on_ground: true,
+            }),
+        ),
+        (
+            "player_look",
+            s(serverbound::play::PlayerLookPacket {
+                yaw: -45.5,
+                pitch: 12.25,
+                on_ground: false,
+            }),
+        ),
+        (
+            "player_position_and_look_serverbound",
+            s(serverbound::play::PlayerPositionAndLookPacket {
+                x: 10.25,
+                y: 70.0,
+                z: -3.75,
+                yaw: 180.0,
+                pitch: -90.0,
+                on_ground: true,
+            }),
+        ),
+        (
+            "entity_look_and_relative_move",
+            c(clientbound::play::EntityLookAndRelativeMovePacket {
+                entity_id: VarInt(7),
+                delta_x: 32,
+                delta_y: 0,
+                delta_z: -64,
+                yaw: Angle::from_degrees(270.0),
+                pitch: Angle::from_degrees(-45.0),
+                on_ground: true,
+            }),
+        ),
+        (
+            "entity_teleport",
+            c(clientbound::play::EntityTeleportPacket {
+                entity_id: VarInt(7),
+                x: 16,
+                y: 2080,
+                z: -48,
+                yaw: Angle(0),
+                pitch: Angle::from_degrees(90.0),
+                on_ground: false,
+            }),
+        ),
+        (
+            "click_window",
+            s(serverbound::play::ClickWindowPacket {
+                id: 0,
+                slot: 36,
+                button: 0,
+                action_number: 1,
+                mode: 0,
+                // item 1 (stone), count 64, damage 0 and no NBT
+                rest: Remaining(Bytes::from_static(&[0, 1, 64, 0, 0, 0])),
+            }),
+        ),
+        (
+            "confirm_transaction",
+            c(clientbound::play::ConfirmTransactionPacket {
+                id: 0,
+                action_number: 1,
+                accepted: true,
+            }),
+        ),
+        (
+            "chat_serverbound",
+            s(serverbound::play::ChatMessagePacket {
+                message: "hello".to_string(),
+            }),
+        ),
+        (
+            "chat_clientbound",
+            c(clientbound::play::ChatMessagePacket {
+                message: ChatComponent::builder().text("hello".to_string()).build(),
+                position: 0,
+            }),
+        ),
+        (
+            "keep_alive_clientbound",
+            c(clientbound::play::KeepAlivePacket { id: VarInt(1337) }),
+        ),
+        (
+            "keep_alive_serverbound",
+            s(serverbound::play::KeepAlivePacket { id: VarInt(1337) }),
+        ),
+    ]
+}
+
+/// Pairs every frame of the corpus with the packet it should decode to.
+fn entries() -> Vec<(Entry, Expected)> {
+    let corpus = corpus();
+    let expected = expected();
+
+    assert_eq!(
+        corpus
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>(),
+        expected.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+        "the corpus and the expected packets are out of sync"
+    );
+
+    corpus
+        .into_iter()
+        .zip(expected.into_iter().map(|(_, packet)| packet))
+        .collect()
+}
+
+#[test]
+pub fn decode() {
+    for (entry, expected) in entries() {
+        let mut decoder = PacketDecoder::new();
+        decoder.queue_slice(&entry.frame);
+
+        let frame = decoder.try_next_frame().unwrap().unwrap();
+        assert!(
+            !decoder.has_remaining(),
+            "{} has trailing bytes",
+            entry.name
+        );
+
+        let version = ProtocolVersion::V1_8;
+        let mut body = frame.body.clone();
+        let decoded = match entry.direction.as_str() {
+            "serverbound" => Expected::Serverbound(
+                ServerboundPacket::decode_by_state(version, entry.state, frame.id, &mut body)
+                    .unwrap(),
+            ),
+            "clientbound" => Expected::Clientbound(
+                ClientboundPacket::decode_by_state(version, entry.state, frame.id, &mut body)
+                    .unwrap(),
+            ),
+            direction => panic!("unknown direction {}", direction),
+        };
+
+        assert_eq!(expected, decoded, "{}", entry.name);
+        assert!(body.is_empty(), "{} wasn't fully decoded", entry.name);
+    }
+}
+
+#[test]
+pub fn encode() {
+    for (entry, expected) in entries() {
+        let mut encoder = PacketEncoder::new();
+
+        match &expected {
+            Expected::Serverbound(packet) => encoder.append_any(packet).unwrap(),
+            Expected::Clientbound(packet) => encoder.append_any(packet).unwrap(),
+        }
+
+        assert_eq!(entry.frame, &encoder.take()[..], "{}", entry.name);
+    }
+}
@@ -0,0 +1,190 @@
+//! Formatting and filtering of logged packets.
+
+use protocol::{packet::PacketDirection, state::State};
+use serde_json::{json, Value};
+
+fn direction_name(direction: PacketDirection) -> &'static str {
+    match direction {
+        PacketDirection::Serverbound => "serverbound",
+        PacketDirection::Clientbound => "clientbound",
+    }
+}
+
+fn arrow(direction: PacketDirection) -> &'static str {
+    match direction {
+        PacketDirection::Serverbound => "C->S",
+        PacketDirection::Clientbound => "S->C",
+    }
+}
+
+/// One frame passing through the proxy.
+pub struct Record {
+    /// Milliseconds since the Unix epoch.
+    pub time: u64,
+    /// The connection's number, counting from 1.
+    pub connection: u64,
+    pub direction: PacketDirection,
+    /// The state the frame was sent in.
+    pub state: State,
+    pub id: i32,
+    /// The size of the packet ID and body, uncompressed.
+    pub size: usize,
+    /// The packet's name and fields, or why it couldn't be decoded.
+    pub packet: Result<(&'static str, Value), String>,
+}
+
+impl Record {
+    pub fn name(&self) -> Option<&'static str> {
+        self.packet.as_ref().ok().map(|(name, _)| *name)
+    }
+}
+
+pub struct Logger {
+    json: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Logger {
+    /// Creates a logger, which only logs the packets named in `include` (or all of them if it's
+    /// empty), except for those named in `exclude`. Names are matched ignoring case.
+    pub fn new(json: bool, include: Vec<String>, exclude: Vec<String>) -> Self {
+        Self {
+            json,
+            include,
+            exclude,
+        }
+    }
+
+    fn is_logged(&self, record: &Record) -> bool {
+        let matches = |names: &[String]| match record.name() {
+            Some(name) => names.iter().any(|n| n.eq_ignore_ascii_case(name)),
+            None => false,
+        };
+
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+
+    /// Formats a record as one line, or returns `None` if it's filtered out.
+    pub fn format(&self, record: &Record) -> Option<String> {
+        if !self.is_logged(record) {
+            return None;
+        }
+
+        Some(if self.json {
+            self.format_json(record)
+        } else {
+            self.format_text(record)
+        })
+    }
+
+    fn format_json(&self, record: &Record) -> String {
+        let mut line = json!({
+            "time": record.time,
+            "connection": record.connection,
+            "direction": direction_name(record.direction),
+            "state": format!("{:?}", record.state),
+            "id": record.id,
+            "size": record.size,
+        });
+
+        match &record.packet {
+            Ok((name, fields)) => {
+                line["name"] = json!(name);
+                line["packet"] = fields.clone();
+            }
+            Err(error) => line["error"] = json!(error),
+        }
+
+        line.to_string()
+    }
+
+    fn format_text(&self, record: &Record) -> String {
+        // UTC, as there's no time zone database to go by
+        let millis = record.time % 86_400_000;
+        let time = format!(
+            "{:02}:{:02}:{:02}.{:03}",
+            millis / 3_600_000,
+            millis / 60_000 % 60,
+            millis / 1000 % 60,
+            millis % 1000
+        );
+
+        let packet = match &record.packet {
+            Ok((name, fields)) => format!("{} {}", name, fields),
+            Err(error) => format!("? ({})", error),
+        };
+
+        format!(
+            "{} #{} {} {:?} {:#04x} {}B {}",
+            time,
+            record.connection,
+            arrow(record.direction),
+            record.state,
+            record.id,
+            record.size,
+            packet
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use protocol::{packet::PacketDirection, state::State};
+    use serde_json::json;
+
+    use super::{Logger, Record};
+
+    fn record(packet: Result<(&'static str, serde_json::Value), String>) -> Record {
+        Record {
+            // 1970-01-02 01:02:03.004
+            time: 90_123_004,
+            connection: 1,
+            direction: PacketDirection::Serverbound,
+            state: State::Play,
+            id: 0x01,
+            size: 4,
+            packet,
+        }
+    }
+
+    #[test]
+    pub fn format() {
+        let chat = record(Ok(("ChatMessage", json!({ "message": "hi" }))));
+
+        assert_eq!(
+            r#"01:02:03.004 #1 C->S Play 0x01 4B ChatMessage {"message":"hi"}"#,
+            Logger::new(false, vec![], vec![]).format(&chat).unwrap()
+        );
+        assert_eq!(
+            json!({
+                "time": 90_123_004,
+                "connection": 1,
+                "direction": "serverbound",
+                "state": "Play",
+                "id": 1,
+                "size": 4,
+                "name": "ChatMessage",
+                "packet": { "message": "hi" },
+            }),
+            serde_json::from_str::<serde_json::Value>(
+                &Logger::new(true, vec![], vec![]).format(&chat).unwrap()
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    pub fn filter() {
+        let chat = record(Ok(("ChatMessage", json!({ "message": "hi" }))));
+        let unknown = record(Err("unknown packet".to_string()));
+
+        let include = Logger::new(false, vec!["chatmessage".to_string()], vec![]);
+        assert!(include.format(&chat).is_some());
+        assert!(include.format(&unknown).is_none());
+
+        let exclude = Logger::new(false, vec![], vec!["ChatMessage".to_string()]);
+        assert!(exclude.format(&chat).is_none());
+        assert!(exclude.format(&unknown).is_some());
+    }
+}
@@ -0,0 +1,114 @@
+//! A proxy that sits between a client and an offline-mode server, and logs every packet passing
+//! through in either direction.
+
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use clap::Parser;
+use protocol::capture::CaptureWriter;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+
+use crate::{
+    log::Logger,
+    relay::{relay, Inspector},
+};
+
+pub mod log;
+pub mod relay;
+
+#[derive(Parser)]
+#[command(
+    name = "jam-proxy",
+    about = "Logs the packets between a client and a server"
+)]
+struct Args {
+    /// The address to accept clients on.
+    #[arg(short, long, default_value = "127.0.0.1:25566")]
+    listen: String,
+    /// The server to forward to, which has to be in offline mode.
+    #[arg(short, long, default_value = "127.0.0.1:25565")]
+    upstream: String,
+    /// Logs one JSON object per line, instead of text.
+    #[arg(long)]
+    json: bool,
+    /// Only logs packets with these names, e.g. `ChatMessage`.
+    #[arg(short, long, value_delimiter = ',')]
+    include: Vec<String>,
+    /// Doesn't log packets with these names, e.g. `KeepAlive`.
+    #[arg(short, long, value_delimiter = ',')]
+    exclude: Vec<String>,
+    /// Records every connection into a capture in this directory, named after its number.
+    #[arg(long)]
+    capture_dir: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let logger = Arc::new(Logger::new(args.json, args.include, args.exclude));
+
+    // one writer, so lines of different connections don't interleave
+    let (lines, mut logged) = mpsc::unbounded_channel::<String>();
+    tokio::spawn(async move {
+        while let Some(line) = logged.recv().await {
+            println!("{}", line);
+        }
+    });
+
+    let listener = TcpListener::bind(&args.listen).await?;
+    eprintln!("forwarding {} to {}", args.listen, args.upstream);
+
+    let connections = AtomicU64::new(0);
+    loop {
+        let (client, address) = listener.accept().await?;
+        let connection = connections.fetch_add(1, Ordering::Relaxed) + 1;
+        let upstream = args.upstream.clone();
+        let mut inspector = Inspector::new(connection, logger.clone(), lines.clone());
+
+        if let Some(dir) = &args.capture_dir {
+            let path = dir.join(format!("{}.jamcap", connection));
+            let (capture, records) = std::sync::mpsc::channel();
+            inspector = inspector.with_capture(capture);
+
+            // written on a thread of its own, so a slow disk doesn't hold up the relay
+            tokio::task::spawn_blocking(move || {
+                let result = File::create(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|file| CaptureWriter::new(BufWriter::new(file)))
+                    .and_then(|mut capture| capture.write_from(records));
+                if let Err(error) = result {
+                    eprintln!(
+                        "#{}: capture to {} failed: {:#}",
+                        connection,
+                        path.display(),
+                        error
+                    );
+                }
+            });
+        }
+
+        tokio::spawn(async move {
+            eprintln!("#{}: {} connected", connection, address);
+
+            let result = match TcpStream::connect(&upstream).await {
+                Ok(upstream) => relay(client, upstream, inspector).await,
+                Err(error) => Err(error.into()),
+            };
+
+            match result {
+                Ok(()) => eprintln!("#{}: closed", connection),
+                Err(error) => eprintln!("#{}: {:#}", connection, error),
+            }
+        });
+    }
+}
@@ -0,0 +1,308 @@
+//! Forwarding of one connection, and decoding of what passes through.
+
+use std::sync::{mpsc, Arc};
+
+use protocol::{
+    capture::CaptureRecord,
+    codec::Frame,
+    packet::{
+        clientbound::{ClientboundLoginPacket, ClientboundPacket},
+        AnyStatePacket, PacketDirection,
+    },
+    session::ProxySession,
+};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::mpsc::UnboundedSender,
+};
+
+use crate::log::{Logger, Record};
+
+/// Decodes the frames passing through a connection, and logs them.
+pub struct Inspector {
+    connection: u64,
+    session: ProxySession,
+    logger: Arc<Logger>,
+    lines: UnboundedSender<String>,
+    capture: Option<mpsc::Sender<CaptureRecord>>,
+    /// Cleared once the frames can't be followed anymore, e.g. after encryption was requested.
+    decoding: bool,
+}
+
+impl Inspector {
+    pub fn new(connection: u64, logger: Arc<Logger>, lines: UnboundedSender<String>) -> Self {
+        Self {
+            connection,
+            session: ProxySession::new(),
+            logger,
+            lines,
+            capture: None,
+            decoding: true,
+        }
+    }
+
+    /// Also records every frame into a capture, by sending it to the thread writing the capture.
+    pub fn with_capture(mut self, capture: mpsc::Sender<CaptureRecord>) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Decodes and logs every whole frame received in one direction.
+    pub fn inspect(&mut self, direction: PacketDirection, bytes: &[u8]) {
+        if !self.decoding {
+            return;
+        }
+
+        match direction {
+            PacketDirection::Serverbound => self.session.receive_serverbound(bytes),
+            PacketDirection::Clientbound => self.session.receive_clientbound(bytes),
+        }
+
+        loop {
+            let frame = match direction {
+                PacketDirection::Serverbound => self.session.next_serverbound(),
+                PacketDirection::Clientbound => self.session.next_clientbound(),
+            };
+
+            match frame {
+                Ok(Some(frame)) => self.log(direction, frame),
+                Ok(None) => break,
+                Err(error) => {
+                    self.stop(format!("invalid frame, no longer decoding: {}", error));
+                    break;
+                }
+            }
+
+            if !self.decoding {
+                break;
+            }
+        }
+    }
+
+    fn log(&mut self, direction: PacketDirection, frame: Frame) {
+        let captured = CaptureRecord::now(
+            direction,
+            self.session.state(),
+            self.session.protocol(),
+            frame,
+        );
+        if let Some(capture) = &self.capture {
+            // the capture only stops early if writing failed, which its thread reports
+            let _ = capture.send(captured.clone());
+        }
+
+        let frame = &captured.frame;
+        let packet = match direction {
+            PacketDirection::Serverbound => self
+                .session
+                .decode_serverbound(frame)
+                .map(|packet| (packet.name(), fields(&packet))),
+            PacketDirection::Clientbound => self.session.decode_clientbound(frame).map(|packet| {
+                if let ClientboundPacket::Login(ClientboundLoginPacket::EncryptionRequest(_)) =
+                    packet
+                {
+                    self.decoding = false;
+                }
+
+                (packet.name(), fields(&packet))
+            }),
+        };
+
+        let record = Record {
+            time: captured.time,
+            connection: self.connection,
+            direction,
+            state: captured.state,
+            id: frame.id,
+            size: captured.frame_len(),
+            packet: packet.map_err(|error| error.to_string()),
+        };
+
+        if let Some(line) = self.logger.format(&record) {
+            let _ = self.lines.send(line);
+        }
+
+        if !self.decoding {
+            self.stop("the server requested encryption, no longer decoding".to_string());
+        }
+    }
+
+    fn stop(&mut self, reason: String) {
+        self.decoding = false;
+        eprintln!("#{}: {}", self.connection, reason);
+    }
+}
+
+/// A packet's fields, without the name of its variant around them.
+fn fields<T: Serialize>(packet: &T) -> Value {
+    match serde_json::to_value(packet) {
+        Ok(Value::Object(mut map)) if map.len() == 1 => {
+            let name = map.keys().next().cloned().unwrap();
+            map.remove(&name).unwrap()
+        }
+        Ok(value) => value,
+        Err(_) => Value::Null,
+    }
+}
+
+/// Forwards everything between the client and the upstream server unchanged, until either end
+/// closes the connection.
+pub async fn relay(
+    client: TcpStream,
+    upstream: TcpStream,
+    mut inspector: Inspector,
+) -> anyhow::Result<()> {
+    let (mut client_reader, mut client_writer) = client.into_split();
+    let (mut upstream_reader, mut upstream_writer) = upstream.into_split();
+
+    let mut serverbound = vec![0; 8192];
+    let mut clientbound = vec![0; 8192];
+
+    loop {
+        tokio::select! {
+            read = client_reader.read(&mut serverbound) => {
+                let len = read?;
+                if len == 0 {
+                    break;
+                }
+
+                upstream_writer.write_all(&serverbound[..len]).await?;
+                inspector.inspect(PacketDirection::Serverbound, &serverbound[..len]);
+            }
+            read = upstream_reader.read(&mut clientbound) => {
+                let len = read?;
+                if len == 0 {
+                    break;
+                }
+
+                client_writer.write_all(&clientbound[..len]).await?;
+                inspector.inspect(PacketDirection::Clientbound, &clientbound[..len]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use client::{Client, ClientOptions};
+    use protocol::{
+        framed::{PacketReader, PacketWriter},
+        packet::{
+            clientbound::{self, ClientboundPlayPacket},
+            serverbound::{
+                self, ServerboundHandshakingPacket, ServerboundLoginPacket, ServerboundPlayPacket,
+            },
+        },
+        varint::VarInt,
+    };
+    use serde_json::Value;
+    use tokio::{net::TcpListener, sync::mpsc, time::timeout};
+
+    use super::{relay, Inspector};
+    use crate::log::Logger;
+
+    /// An offline server that compresses everything, and echoes one chat message.
+    async fn serve(listener: TcpListener) -> anyhow::Result<()> {
+        let (stream, _) = listener.accept().await?;
+        let (reader, writer) = stream.into_split();
+        let (mut reader, mut writer) = (PacketReader::new(reader), PacketWriter::new(writer));
+
+        reader.read_packet::<ServerboundHandshakingPacket>().await?;
+        reader.read_packet::<ServerboundLoginPacket>().await?;
+        writer
+            .send(&clientbound::login::SetCompressionPacket {
+                threshold: VarInt(0),
+            })
+            .await?;
+        reader.set_compression(Some(0));
+        writer.set_compression(Some(0));
+        writer
+            .send(&clientbound::login::LoginSuccessPacket {
+                uuid: "00000000-0000-0000-0000-000000000000".to_string(),
+                username: "jam".to_string(),
+            })
+            .await?;
+
+        let Some(ServerboundPlayPacket::ChatMessage(chat)) = reader.read_packet().await? else {
+            panic!("expected chat message");
+        };
+        writer
+            .send(&clientbound::play::ChatMessagePacket {
+                message: protocol::chat::ChatComponent::builder()
+                    .text(chat.message)
+                    .build(),
+                position: 0,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn decode_through_login() {
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_address = upstream.local_addr().unwrap();
+        let server = tokio::spawn(serve(upstream));
+
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_address = proxy.local_addr().unwrap().to_string();
+        let (lines, mut logged) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (stream, _) = proxy.accept().await.unwrap();
+            let upstream = tokio::net::TcpStream::connect(upstream_address)
+                .await
+                .unwrap();
+            let logger = Arc::new(Logger::new(true, vec![], vec![]));
+            relay(stream, upstream, Inspector::new(1, logger, lines)).await
+        });
+
+        let options = ClientOptions::builder().username("jam".to_string()).build();
+        let mut client = Client::connect(&proxy_address, options).await.unwrap();
+        client
+            .send(serverbound::play::ChatMessagePacket {
+                message: "hi".to_string(),
+            })
+            .unwrap();
+        assert!(matches!(
+            timeout(Duration::from_secs(5), client.next_packet())
+                .await
+                .unwrap(),
+            Some(ClientboundPlayPacket::ChatMessage(_))
+        ));
+        server.await.unwrap().unwrap();
+
+        let mut names = Vec::new();
+        while names.len() < 6 {
+            let line = timeout(Duration::from_secs(5), logged.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            let line: Value = serde_json::from_str(&line).unwrap();
+            names.push(format!(
+                "{} {}",
+                line["direction"].as_str().unwrap(),
+                line["name"].as_str().unwrap()
+            ));
+        }
+
+        // the compressed packets are decoded too
+        assert_eq!(
+            vec![
+                "serverbound Handshake",
+                "serverbound LoginStart",
+                "clientbound SetCompression",
+                "clientbound LoginSuccess",
+                "serverbound ChatMessage",
+                "clientbound ChatMessage",
+            ],
+            names
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! Works out who is logging in.
+//!
+//! In offline mode, players are who they say they are, and get the UUID vanilla would give them
+//! (see [offline_uuid]). In online mode, the client joins a session with the server hash (see
+//! [server_hash]) before answering the encryption request, and the server then asks the session
+//! server whether it did, which answers with the player's UUID and skin.
+
+use std::net::IpAddr;
+
+use anyhow::{bail, ensure};
+use bevy_ecs::system::Resource;
+use bytes::Bytes;
+use protocol::auth::{offline_uuid, server_hash};
+use reqwest::{Client, StatusCode, Url};
+use rsa::{pkcs8::EncodePublicKey, Pkcs1v15Encrypt, RsaPrivateKey};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Mojang's `hasJoined` endpoint.
+pub const DEFAULT_SESSION_SERVER: &str =
+    "https://sessionserver.mojang.com/session/minecraft/hasJoined";
+
+/// A property of a profile, such as the skin (`textures`).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProfileProperty {
+    pub name: String,
+    /// Base64 encoded.
+    pub value: String,
+    /// Signed with Mojang's key, base64 encoded.
+    pub signature: Option<String>,
+}
+
+/// Who a player is.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GameProfile {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<ProfileProperty>,
+}
+
+impl GameProfile {
+    /// The profile of a player in offline mode, which has no properties.
+    pub fn offline(username: &str) -> Self {
+        Self {
+            id: offline_uuid(username),
+            name: username.to_string(),
+            properties: Vec::new(),
+        }
+    }
+
+    /// The UUID as sent in the login success packet.
+    pub fn hyphenated_id(&self) -> String {
+        self.id.hyphenated().to_string()
+    }
+}
+
+/// The key pair clients encrypt the shared secret with in online mode. Like vanilla, the server
+/// generates a new one every time it starts.
+#[derive(Resource)]
+pub struct ServerKey {
+    private: RsaPrivateKey,
+    /// As sent in the encryption request: an X.509 `SubjectPublicKeyInfo` in DER.
+    public: Bytes,
+}
+
+impl ServerKey {
+    /// Generates a 1024 bit key pair, as vanilla does.
+    pub fn generate() -> anyhow::Result<Self> {
+        let private = RsaPrivateKey::new(&mut rand::thread_rng(), 1024)?;
+        let public = Bytes::from(private.to_public_key().to_public_key_der()?.into_vec());
+
+        Ok(Self { private, public })
+    }
+
+    pub fn public_key(&self) -> &Bytes {
+        &self.public
+    }
+
+    /// Decrypts what the client encrypted with the public key.
+    pub fn decrypt(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        Ok(self.private.decrypt(Pkcs1v15Encrypt, data)?)
+    }
+
+    /// Decrypts the shared secret of an encryption response, checking the verify token is the
+    /// one the client was sent.
+    pub fn shared_secret(
+        &self,
+        shared_secret: &[u8],
+        verify_token: &[u8],
+        expected_token: &[u8],
+    ) -> anyhow::Result<[u8; 16]> {
+        ensure!(
+            self.decrypt(verify_token)? == expected_token,
+            "wrong verify token"
+        );
+
+        self.decrypt(shared_secret)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("the shared secret isn't 16 bytes long"))
+    }
+}
+
+/// Checks with a session server whether players joined the session they claim to.
+#[derive(Resource, Debug, Clone)]
+pub struct SessionService {
+    client: Client,
+    url: Url,
+}
+
+impl SessionService {
+    /// Uses the `hasJoined` endpoint at `url`, e.g. [DEFAULT_SESSION_SERVER].
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            url: Url::parse(url)?,
+        })
+    }
+
+    /// Asks whether `username` joined the session with the hash of the given secret and key.
+    /// Without an answer (the session server responds with no content), the player isn't who they
+    /// claim to be.
+    ///
+    /// With `ip`, the session server also checks the player joined from that address, like
+    /// `prevent-proxy-connections` does in vanilla.
+    pub async fn has_joined(
+        &self,
+        username: &str,
+        shared_secret: &[u8],
+        public_key: &[u8],
+        ip: Option<IpAddr>,
+    ) -> anyhow::Result<Option<GameProfile>> {
+        // the server ID is always empty since 1.7
+        let hash = server_hash("", shared_secret, public_key);
+
+        let mut url = self.url.clone();
+        url.query_pairs_mut()
+            .append_pair("username", username)
+            .append_pair("serverId", &hash);
+        if let Some(ip) = ip {
+            url.query_pairs_mut().append_pair("ip", &ip.to_string());
+        }
+
+        let response = self.client.get(url).send().await?;
+        match response.status() {
+            StatusCode::OK => Ok(Some(response.json().await?)),
+            StatusCode::NO_CONTENT => Ok(None),
+            status => bail!("the session server responded with {}", status),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use protocol::auth::server_hash;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::{GameProfile, SessionService};
+
+    /// Answers one request like the session server would, and returns the request line.
+    pub(crate) async fn mock_session_server(
+        listener: TcpListener,
+        status: &str,
+        body: &str,
+    ) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            let len = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..len]);
+        }
+
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+
+        String::from_utf8(request)
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    pub async fn has_joined() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/session/minecraft/hasJoined",
+            listener.local_addr().unwrap()
+        );
+        let body = r#"{
+            "id": "069a79f444e94726a5befca90e38aaf5",
+            "name": "Notch",
+            "properties": [{"name": "textures", "value": "e30=", "signature": "c2ln"}]
+        }"#;
+        let server = tokio::spawn(mock_session_server(listener, "200 OK", body));
+
+        let service = SessionService::new(&url).unwrap();
+        let profile = service
+            .has_joined("Notch", b"secret", b"key", None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            "069a79f4-44e9-4726-a5be-fca90e38aaf5",
+            profile.hyphenated_id()
+        );
+        assert_eq!("textures", profile.properties[0].name);
+        assert_eq!(
+            format!(
+                "GET /session/minecraft/hasJoined?username=Notch&serverId={} HTTP/1.1",
+                server_hash("", b"secret", b"key")
+            ),
+            server.await.unwrap()
+        );
+
+        // a player that didn't join
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hasJoined", listener.local_addr().unwrap());
+        tokio::spawn(mock_session_server(listener, "204 No Content", ""));
+
+        let service = SessionService::new(&url).unwrap();
+        assert_eq!(
+            None,
+            service
+                .has_joined("Notch", b"secret", b"key", None)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[test]
+    pub fn offline() {
+        let profile = GameProfile::offline("Notch");
+        assert_eq!(
+            "b50ad385-829d-3141-a216-7e7d7539ba7f",
+            profile.hyphenated_id()
+        );
+        assert!(profile.properties.is_empty());
+    }
+}
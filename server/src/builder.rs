@@ -0,0 +1,281 @@
+//! Sets up a server to embed it, with systems of its own.
+//!
+//! A [ServerBuilder] wires up the [World] and [Schedule] everything runs in: the channels to the
+//! connection tasks, the stages and jam's own systems. Game modes add their systems, stages,
+//! resources and packet handlers on top, then [build](ServerBuilder::build) a [Server] to run.
+//! A [ServerHandle] controls the server while it runs, from anywhere.
+
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use bevy_ecs::{
+    prelude::Events,
+    schedule::{IntoSystemDescriptor, Schedule, Stage, StageLabel, SystemStage},
+    system::Resource,
+    world::World,
+};
+use crossbeam_channel::{unbounded, Sender};
+use tokio::{runtime::Handle, task::JoinHandle};
+
+use crate::{
+    auth::{ServerKey, SessionService},
+    connection::{flush_outboxes, Closed},
+    console::{apply_reloads, handle_console, ConsoleRes, Reloads},
+    disconnect::{self, handle_disconnects, Disconnect, Disconnected},
+    keep_alive::{receive_keep_alives, send_keep_alives},
+    login::{handle_encryption, handle_login, Verifications},
+    recording::{record_outboxes, save_recordings},
+    server::{self, accept_loop, handle_connections, ServerConfiguration},
+    status::handle_status,
+    sync::{sync_connections, ChannelsRes, ConnectionTracker, RuntimeRes},
+    tick::{self, run_tick, Shutdown, Tick, TickMetrics},
+};
+
+/// The stages every tick runs, in order.
+#[derive(StageLabel)]
+pub enum ServerStage {
+    /// Takes in what happened outside of the ECS: new connections, the packets clients sent and
+    /// console commands. Also clears the events of the tick before last.
+    Sync,
+    /// Handles the packets clients sent, and everything else the server does.
+    Network,
+    /// Disconnects clients. Their entities are only despawned once every other system is done
+    /// with them for the tick.
+    Disconnect,
+    /// Hands the packets queued during the tick to the connection tasks, which write them with a
+    /// single write per connection.
+    Flush,
+}
+
+/// Sets up a [Server].
+pub struct ServerBuilder {
+    world: World,
+    schedule: Schedule,
+}
+
+impl ServerBuilder {
+    /// A server with jam's own systems, set up with `config`.
+    pub fn new(config: ServerConfiguration) -> Self {
+        let mut builder = Self {
+            world: World::new(),
+            schedule: Schedule::default(),
+        };
+
+        builder
+            .schedule
+            .add_stage(
+                ServerStage::Sync,
+                SystemStage::parallel()
+                    .with_system(sync_connections)
+                    .with_system(handle_connections)
+                    .with_system(handle_console)
+                    .with_system(apply_reloads),
+            )
+            .add_stage(
+                ServerStage::Network,
+                SystemStage::parallel()
+                    .with_system(handle_status)
+                    .with_system(handle_login)
+                    .with_system(handle_encryption)
+                    .with_system(send_keep_alives)
+                    .with_system(receive_keep_alives),
+            )
+            .add_stage(
+                ServerStage::Disconnect,
+                SystemStage::parallel()
+                    .with_system(handle_disconnects)
+                    .with_system(save_recordings.after(handle_disconnects)),
+            )
+            .add_stage(
+                ServerStage::Flush,
+                SystemStage::parallel()
+                    .with_system(record_outboxes.before(flush_outboxes))
+                    .with_system(flush_outboxes),
+            );
+
+        builder
+            .insert_resource(config)
+            .insert_resource(ConnectionTracker::new())
+            .insert_resource(Tick::default())
+            .insert_resource(TickMetrics::default())
+            .insert_resource(Shutdown::default())
+            .insert_resource(Verifications::default())
+            .insert_resource(Reloads::default())
+            .add_packet_events()
+            .add_event::<Closed>()
+            .add_event::<Disconnect>()
+            .add_event::<Disconnected>()
+    }
+
+    /// Inserts a resource, replacing the one of the same type if there is one.
+    pub fn insert_resource<R: Resource>(mut self, resource: R) -> Self {
+        self.world.insert_resource(resource);
+        self
+    }
+
+    /// Adds an event type, which is cleared every other tick.
+    pub fn add_event<E: Send + Sync + 'static>(mut self) -> Self {
+        if !self.world.contains_resource::<Events<E>>() {
+            self.world.insert_resource(Events::<E>::default());
+            self.schedule
+                .add_system_to_stage(ServerStage::Sync, Events::<E>::update_system);
+        }
+        self
+    }
+
+    /// Adds a system to the [Network](ServerStage::Network) stage.
+    pub fn add_system<Params>(self, system: impl IntoSystemDescriptor<Params>) -> Self {
+        self.add_system_to_stage(ServerStage::Network, system)
+    }
+
+    /// Adds a system to a stage.
+    pub fn add_system_to_stage<Params>(
+        mut self,
+        stage: impl StageLabel,
+        system: impl IntoSystemDescriptor<Params>,
+    ) -> Self {
+        self.schedule.add_system_to_stage(stage, system);
+        self
+    }
+
+    /// Adds a system reading [PacketEvent](crate::packet::PacketEvent)s. It sees the packets
+    /// received since the last tick, as they're taken in during the [Sync](ServerStage::Sync)
+    /// stage.
+    pub fn add_packet_handler<Params>(self, system: impl IntoSystemDescriptor<Params>) -> Self {
+        self.add_system(system)
+    }
+
+    /// Adds a stage after the ones added before, but before clients are disconnected and packets
+    /// flushed.
+    pub fn add_stage<S: Stage>(mut self, label: impl StageLabel, stage: S) -> Self {
+        self.schedule
+            .add_stage_before(ServerStage::Disconnect, label, stage);
+        self
+    }
+
+    /// Adds a stage right after `target`.
+    pub fn add_stage_after<S: Stage>(
+        mut self,
+        target: impl StageLabel,
+        label: impl StageLabel,
+        stage: S,
+    ) -> Self {
+        self.schedule.add_stage_after(target, label, stage);
+        self
+    }
+
+    /// Adds a stage right before `target`.
+    pub fn add_stage_before<S: Stage>(
+        mut self,
+        target: impl StageLabel,
+        label: impl StageLabel,
+        stage: S,
+    ) -> Self {
+        self.schedule.add_stage_before(target, label, stage);
+        self
+    }
+
+    /// Binds the server on the configured host and port, and starts accepting connections on the
+    /// current tokio runtime. They're only handled once the server ticks.
+    ///
+    /// In online mode, this also generates the server's key pair.
+    pub async fn build(mut self) -> Result<Server> {
+        let config = self.world.resource::<ServerConfiguration>();
+        let listener = server::bind(config).await?;
+        let address = listener.local_addr()?;
+
+        if config.online_mode {
+            let service = SessionService::new(&config.session_server)?;
+            self.world.insert_resource(service);
+            self.world.insert_resource(ServerKey::generate()?);
+        }
+
+        let (connection_sender, connection_receiver) = unbounded();
+        let (event_sender, event_receiver) = unbounded();
+        let accept = tokio::spawn(accept_loop(listener, connection_sender.clone()));
+
+        let (console_sender, console_receiver) = unbounded();
+
+        self.world.insert_resource(ChannelsRes {
+            connection_sender,
+            connection_receiver,
+            event_sender,
+            event_receiver,
+        });
+        self.world.insert_resource(RuntimeRes(Handle::current()));
+        self.world.insert_resource(ConsoleRes {
+            receiver: console_receiver,
+        });
+
+        Ok(Server {
+            world: self.world,
+            schedule: self.schedule,
+            address,
+            accept,
+            handle: ServerHandle {
+                console: console_sender,
+            },
+        })
+    }
+}
+
+/// A server, ready to run.
+pub struct Server {
+    world: World,
+    schedule: Schedule,
+    address: SocketAddr,
+    accept: JoinHandle<Result<()>>,
+    handle: ServerHandle,
+}
+
+impl Server {
+    /// The address the server is bound on, e.g. to find the port it got when configured with 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Controls the server while it runs.
+    pub fn handle(&self) -> ServerHandle {
+        self.handle.clone()
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// Runs a single tick.
+    pub fn tick(&mut self) {
+        run_tick(&mut self.world, &mut self.schedule);
+    }
+
+    /// Runs the server 20 ticks per second until it's shut down, then kicks everyone. It can't be
+    /// run again once it returns, but its world can still be inspected.
+    pub async fn run(&mut self) {
+        tick::run(&mut self.world, &mut self.schedule).await;
+
+        self.accept.abort();
+        disconnect::shutdown(&mut self.world, &mut self.schedule).await;
+    }
+}
+
+/// Controls a running [Server].
+#[derive(Clone)]
+pub struct ServerHandle {
+    console: Sender<String>,
+}
+
+impl ServerHandle {
+    /// Runs a command as if it was typed into the console, during the next tick.
+    pub fn command(&self, command: &str) -> Result<()> {
+        Ok(self.console.send(command.to_string())?)
+    }
+
+    /// Shuts the server down at the end of the next tick, like the `stop` command.
+    pub fn shutdown(&self) -> Result<()> {
+        self.command("stop")
+    }
+}
@@ -0,0 +1,246 @@
+//! Loads the [ServerConfiguration] from a TOML file.
+//!
+//! The file is generated on the first run: from vanilla's `server.properties` if there's one next
+//! to it, or with the defaults otherwise. Only the MOTD and the max players can be changed without
+//! restarting the server, by reloading the file (see [ServerConfiguration::reload]).
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use bevy_ecs::system::Resource;
+
+use crate::{player::GameMode, server::ServerConfiguration};
+
+/// Where the configuration is loaded from, unless told otherwise.
+pub const DEFAULT_PATH: &str = "server.toml";
+
+/// Vanilla's configuration file, which is imported if there's no configuration yet.
+const PROPERTIES_FILE: &str = "server.properties";
+
+/// The file the configuration was loaded from, to reload it.
+#[derive(Resource, Debug, Clone)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+}
+
+/// Reads the configuration file. Settings it doesn't have are the defaults.
+pub fn read(path: &Path) -> Result<ServerConfiguration> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("couldn't read {}", path.display()))?;
+
+    toml::from_str(&text).with_context(|| format!("couldn't parse {}", path.display()))
+}
+
+/// Reads the configuration file, generating it first if it doesn't exist.
+pub fn load(path: &Path) -> Result<ServerConfiguration> {
+    if path.exists() {
+        return read(path);
+    }
+
+    let properties = path.with_file_name(PROPERTIES_FILE);
+    let config = match fs::read_to_string(&properties) {
+        Ok(text) => {
+            println!("importing {}", properties.display());
+            from_properties(&text)?
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => ServerConfiguration::default(),
+        Err(error) => {
+            return Err(error).with_context(|| format!("couldn't read {}", properties.display()))
+        }
+    };
+
+    fs::write(path, toml::to_string(&config)?)
+        .with_context(|| format!("couldn't write {}", path.display()))?;
+    println!("generated {}", path.display());
+
+    Ok(config)
+}
+
+/// Imports the settings of a `server.properties` file. The ones jam doesn't have are ignored.
+pub fn from_properties(properties: &str) -> Result<ServerConfiguration> {
+    let mut config = ServerConfiguration::default();
+
+    for line in properties.lines() {
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        let value = unescape(value.trim_start());
+        let invalid = || anyhow!("invalid {} in {}: {:?}", key, PROPERTIES_FILE, value);
+
+        match key {
+            "server-ip" if !value.is_empty() => config.host = value.clone(),
+            "server-port" => config.port = value.parse().map_err(|_| invalid())?,
+            "max-players" => config.max_players = value.parse().map_err(|_| invalid())?,
+            "gamemode" => config.gamemode = gamemode(&value).ok_or_else(invalid)?,
+            "difficulty" => config.difficulty = difficulty(&value).ok_or_else(invalid)?,
+            "level-type" => config.level_type = level_type(&value).ok_or_else(invalid)?.into(),
+            "view-distance" => config.view_distance = value.parse().map_err(|_| invalid())?,
+            "motd" => config.motd = value.clone(),
+            "online-mode" => config.online_mode = value.parse().map_err(|_| invalid())?,
+            _ => {}
+        }
+    }
+
+    Ok(config)
+}
+
+/// A game mode, by ID (as in 1.8) or name (as in later versions).
+fn gamemode(value: &str) -> Option<GameMode> {
+    match value.to_lowercase().as_str() {
+        "survival" => Some(GameMode::Survival),
+        "creative" => Some(GameMode::Creative),
+        "adventure" => Some(GameMode::Adventure),
+        "spectator" => Some(GameMode::Spectator),
+        id => GameMode::from_id(id.parse().ok()?),
+    }
+}
+
+/// A difficulty, by ID (as in 1.8) or name (as in later versions).
+fn difficulty(value: &str) -> Option<u8> {
+    match value.to_lowercase().as_str() {
+        "peaceful" => Some(0),
+        "easy" => Some(1),
+        "normal" => Some(2),
+        "hard" => Some(3),
+        id => id.parse().ok().filter(|id| *id <= 3),
+    }
+}
+
+/// A level type as sent to clients, from the upper case one vanilla writes.
+fn level_type(value: &str) -> Option<&'static str> {
+    match value.to_lowercase().as_str() {
+        "default" => Some("default"),
+        "flat" => Some("flat"),
+        "largebiomes" => Some("largeBiomes"),
+        "amplified" => Some("amplified"),
+        "default_1_1" => Some("default_1_1"),
+        _ => None,
+    }
+}
+
+/// Undoes the escaping of `.properties` values, where vanilla writes anything that isn't ASCII
+/// (like the `§` of formatting codes) as `\uXXXX`.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('f') => unescaped.push('\x0c'),
+            Some('u') => {
+                let code = chars.by_ref().take(4).collect::<String>();
+                match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                    Some(c) => unescaped.push(c),
+                    None => unescaped.push_str(&code),
+                }
+            }
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{from_properties, load, read};
+    use crate::{player::GameMode, server::ServerConfiguration};
+
+    #[test]
+    pub fn properties() {
+        let config = from_properties(
+            "#Minecraft server properties\n\
+             #Sun Feb 19 12:00:00 CET 2023\n\
+             server-ip=\n\
+             server-port=25566\n\
+             gamemode=1\n\
+             difficulty=hard\n\
+             level-type=LARGEBIOMES\n\
+             view-distance=8\n\
+             motd=\\u00A7aA Minecraft Server\\: jam\n\
+             online-mode=true\n\
+             enable-command-block=false\n",
+        )
+        .unwrap();
+
+        assert_eq!("0.0.0.0", config.host);
+        assert_eq!(25566, config.port);
+        assert_eq!(GameMode::Creative, config.gamemode);
+        assert_eq!(3, config.difficulty);
+        assert_eq!("largeBiomes", config.level_type);
+        assert_eq!(8, config.view_distance);
+        assert_eq!("§aA Minecraft Server: jam", config.motd);
+        assert_eq!(20, config.max_players);
+        assert!(config.online_mode);
+
+        assert!(from_properties("gamemode=4").is_err());
+        assert!(from_properties("server-port=big").is_err());
+    }
+
+    #[test]
+    pub fn toml() {
+        let config: ServerConfiguration = toml::from_str(
+            "motd = \"jam\"\n\
+             max_players = 5\n\
+             gamemode = \"adventure\"\n",
+        )
+        .unwrap();
+        assert_eq!("jam", config.motd);
+        assert_eq!(5, config.max_players);
+        assert_eq!(GameMode::Adventure, config.gamemode);
+        assert_eq!(25565, config.port);
+
+        let text = toml::to_string(&config).unwrap();
+        assert_eq!(config, toml::from_str(&text).unwrap());
+
+        // only some settings are reloaded
+        let mut reloaded = ServerConfiguration::default();
+        reloaded.reload(ServerConfiguration {
+            port: 1,
+            view_distance: 4,
+            ..config
+        });
+        assert_eq!("jam", reloaded.motd);
+        assert_eq!(5, reloaded.max_players);
+        assert_eq!(GameMode::Survival, reloaded.gamemode);
+        assert_eq!(25565, reloaded.port);
+        assert_eq!(10, reloaded.view_distance);
+    }
+
+    #[test]
+    pub fn generate() {
+        let dir = std::env::temp_dir().join(format!("jam-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("server.toml");
+
+        fs::write(dir.join("server.properties"), "max-players=7\n").unwrap();
+        assert_eq!(7, load(&path).unwrap().max_players);
+        assert_eq!(7, read(&path).unwrap().max_players);
+
+        // the configuration takes precedence once it exists
+        fs::write(dir.join("server.properties"), "max-players=8\n").unwrap();
+        assert_eq!(7, load(&path).unwrap().max_players);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
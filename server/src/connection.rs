@@ -1,164 +1,738 @@
+//! Connections, each driven by a reader and a writer task of its own.
+//!
+//! A connection's protocol state lives in a [ServerSession], which both tasks share. The reader
+//! task decodes what the client sends, and hands the packets to the ECS through a channel, tagged
+//! with the connection's entity. Systems send packets back through the [Outbox] component, which
+//! queues them up for the tick and hands them to the writer task at its end. State changes,
+//! compression and encryption are applied to the session before the bytes causing them are
+//! written, so whatever the client sends in response is decoded accordingly.
+//!
+//! Connections can also be captured (see [protocol::capture]): the session records every frame
+//! either way, and the tasks hand the records to a blocking task of their own, which writes them
+//! out, so a slow disk doesn't hold the connection up.
+
 use std::{
-    io::{Read, Write},
-    net::TcpStream,
+    fs::{self, File},
+    io::BufWriter,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
-use anyhow::{anyhow, Result};
-use bevy_ecs::prelude::Component;
+use bevy_ecs::{entity::Entity, prelude::Component, query::Changed, system::Query};
 use bytes::BytesMut;
-use protocol::{encoding::Encodable, state::State, varint::VarInt};
+use crossbeam_channel::Sender;
+use protocol::{
+    capture::{CaptureRecord, CaptureWriter},
+    chat::ChatComponent,
+    packet::{
+        clientbound::{login, play, ClientboundPacket},
+        serverbound::ServerboundPacket,
+        StatePacket,
+    },
+    session::ServerSession,
+    state::State,
+    version::ProtocolVersion,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+    runtime::Handle,
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use crate::sync::ConnectionTracker;
+
+/// How many bytes are read from a connection at once.
+const READ_BUF_SIZE: usize = 4096;
+
+/// A connection that was closed, by the client or because of an error. Its entity is despawned at
+/// the end of the tick.
+#[derive(Debug)]
+pub struct Closed {
+    pub entity: Entity,
+    pub error: Option<anyhow::Error>,
+}
+
+/// What the connection tasks report to the ECS.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum NetworkEvent {
+    Packet(Entity, ServerboundPacket<'static>),
+    /// The client sent a packet that couldn't be decoded, and nothing more is read from it.
+    Malformed(Entity, anyhow::Error),
+    /// The connection was closed, by the client or because of an error.
+    Closed(Entity, Option<anyhow::Error>),
+}
+
+/// What the writer task of a connection is asked to do, in order.
+#[allow(clippy::large_enum_variant)]
+enum Outgoing {
+    Packet(ClientboundPacket<'static>),
+    EnableEncryption([u8; 16]),
+    /// Writes a disconnect with the reason if the state has one, and closes the connection.
+    Close(Option<ChatComponent<'static>>),
+}
+
+/// Where the frames of a connection are captured to, shared by its tasks.
+#[derive(Clone)]
+pub struct Capture(std::sync::mpsc::Sender<CaptureRecord>);
+
+impl Capture {
+    /// Starts writing the capture to `path` on a blocking task of `runtime`, which runs until the
+    /// connection's tasks are done. The server waits for it before it exits.
+    pub fn start(path: PathBuf, runtime: &Handle, tracker: &ConnectionTracker) -> Self {
+        let (sender, records) = std::sync::mpsc::channel();
+        let open = tracker.open();
+
+        runtime.spawn_blocking(move || {
+            if let Err(error) = write_capture(&path, records) {
+                println!("capture to {} failed: {:#}", path.display(), error);
+            }
+            drop(open);
+        });
+
+        Self(sender)
+    }
 
+    /// Hands what the session captured so far to the capture's task. If writing failed, the
+    /// records are dropped.
+    fn write(&self, session: &mut ServerSession) {
+        for record in session.take_captured() {
+            let _ = self.0.send(record);
+        }
+    }
+}
+
+/// Creates the capture file, and writes the records to it as they come.
+fn write_capture(
+    path: &Path,
+    records: std::sync::mpsc::Receiver<CaptureRecord>,
+) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    CaptureWriter::new(BufWriter::new(File::create(path)?))?.write_from(records)
+}
+
+/// A client's connection, attached to its entity along with its [Outbox].
+///
+/// Despawning the entity closes the connection once everything queued is written. To kick a
+/// client with a reason, use a [Disconnect](crate::disconnect::Disconnect) event.
 #[derive(Component)]
 pub struct Connection {
-    pub state: State,
-    pub stream: TcpStream,
-    pub buf: Vec<u8>,
+    pub address: SocketAddr,
+    session: Arc<Mutex<ServerSession>>,
+    reader: JoinHandle<()>,
 }
 
-#[derive(Component, Debug)]
-pub struct PacketContainer {
-    pub id: VarInt,
-    pub length: VarInt,
-    pub data: Vec<u8>,
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
 }
 
 impl Connection {
-    pub fn buf_prep() -> BytesMut {
-        BytesMut::new()
+    /// Starts the reader and writer tasks of the connection of `entity` on `runtime`, capturing
+    /// it if there's a `capture`.
+    pub fn start(
+        entity: Entity,
+        stream: TcpStream,
+        address: SocketAddr,
+        events: Sender<NetworkEvent>,
+        tracker: &ConnectionTracker,
+        runtime: &Handle,
+        capture: Option<Capture>,
+    ) -> (Self, Outbox) {
+        let mut session = ServerSession::new();
+        if capture.is_some() {
+            session.start_capture();
+        }
+        let session = Arc::new(Mutex::new(session));
+        let (reader, writer) = stream.into_split();
+        let (batches, queue) = mpsc::unbounded_channel();
+
+        let reader = runtime.spawn(read_loop(
+            entity,
+            reader,
+            session.clone(),
+            capture.clone(),
+            events.clone(),
+        ));
+        let writer_session = session.clone();
+        let open = tracker.open();
+        runtime.spawn(async move {
+            if let Err(error) = write_loop(writer, writer_session, capture, queue).await {
+                let _ = events.send(NetworkEvent::Closed(entity, Some(error)));
+            }
+            drop(open);
+        });
+
+        let connection = Self {
+            address,
+            session,
+            reader,
+        };
+        let outbox = Outbox {
+            queue: Vec::new(),
+            batches,
+        };
+
+        (connection, outbox)
+    }
+
+    /// The state of the connection. Packets queued by systems only change it once they're
+    /// written.
+    pub fn state(&self) -> State {
+        self.session.lock().unwrap().state()
+    }
+
+    /// The protocol number from the handshake, or 0 before the handshake.
+    pub fn protocol(&self) -> i32 {
+        self.session.lock().unwrap().protocol()
+    }
+
+    /// The version the client speaks, if it is supported.
+    pub fn version(&self) -> Option<ProtocolVersion> {
+        self.session.lock().unwrap().version()
+    }
+}
+
+/// The packets queued for a client during the tick.
+///
+/// They're handed to the writer task all at once when the outbox is flushed, at the end of the
+/// tick (see [flush_outboxes]), to be encoded and written with a single write. Dropping the
+/// outbox flushes it as well.
+#[derive(Component)]
+pub struct Outbox {
+    queue: Vec<Outgoing>,
+    batches: mpsc::UnboundedSender<Vec<Outgoing>>,
+}
+
+impl Drop for Outbox {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl Outbox {
+    /// Queues a packet to be sent. The direction is checked at compile time, and the state once
+    /// the writer task gets to it: packets that aren't valid by then close the connection.
+    pub fn send<P>(&mut self, packet: P)
+    where
+        P: StatePacket<'static>,
+        ClientboundPacket<'static>: From<P::Set>,
+    {
+        self.send_any(clientbound(packet))
+    }
+
+    /// Queues a packet from any state to be sent.
+    pub fn send_any(&mut self, packet: ClientboundPacket<'static>) {
+        self.queue.push(Outgoing::Packet(packet));
+    }
+
+    /// Encrypts the connection both ways with the shared secret, from the next packet sent on.
+    pub fn enable_encryption(&mut self, key: [u8; 16]) {
+        self.queue.push(Outgoing::EnableEncryption(key));
+    }
+
+    /// Closes the connection once what's queued is written, after sending a disconnect with the
+    /// reason. Which disconnect is sent depends on the state the connection is in by then: there
+    /// is none to send before the login state.
+    pub fn disconnect(&mut self, reason: ChatComponent<'static>) {
+        self.queue.push(Outgoing::Close(Some(reason)));
+    }
+
+    /// Closes the connection once what's queued is written.
+    pub fn close(&mut self) {
+        self.queue.push(Outgoing::Close(None));
+    }
+
+    /// The packets queued since the last flush.
+    pub fn packets(&self) -> impl Iterator<Item = &ClientboundPacket<'static>> {
+        self.queue.iter().filter_map(|outgoing| match outgoing {
+            Outgoing::Packet(packet) => Some(packet),
+            _ => None,
+        })
+    }
+
+    /// Hands what's queued to the writer task.
+    pub fn flush(&mut self) {
+        if !self.queue.is_empty() {
+            // the writer task only stops once the connection is closed
+            let _ = self.batches.send(std::mem::take(&mut self.queue));
+        }
+    }
+}
+
+/// System that flushes the outboxes packets were queued in during the tick.
+pub fn flush_outboxes(mut outboxes: Query<&mut Outbox, Changed<Outbox>>) {
+    for mut outbox in &mut outboxes {
+        outbox.flush();
+    }
+}
+
+/// Wraps a packet of any state.
+fn clientbound<P>(packet: P) -> ClientboundPacket<'static>
+where
+    P: StatePacket<'static>,
+    ClientboundPacket<'static>: From<P::Set>,
+{
+    ClientboundPacket::from(P::Set::from(packet))
+}
+
+/// Decodes the packets the client sends, until the connection is closed or a packet can't be
+/// decoded.
+async fn read_loop(
+    entity: Entity,
+    mut reader: OwnedReadHalf,
+    session: Arc<Mutex<ServerSession>>,
+    capture: Option<Capture>,
+    events: Sender<NetworkEvent>,
+) {
+    let result = async {
+        let mut buf = BytesMut::new();
+
+        loop {
+            buf.reserve(READ_BUF_SIZE);
+            if reader.read_buf(&mut buf).await? == 0 {
+                return Ok(NetworkEvent::Closed(entity, None));
+            }
+
+            let mut session = session.lock().unwrap();
+            session.receive_bytes(buf.split());
+
+            let malformed = loop {
+                match session.next_packet() {
+                    Ok(Some(packet)) => events.send(NetworkEvent::Packet(entity, packet))?,
+                    Ok(None) => break None,
+                    Err(error) => break Some(error),
+                }
+            };
+
+            // malformed packets are captured as well
+            if let Some(capture) = &capture {
+                capture.write(&mut session);
+            }
+            if let Some(error) = malformed {
+                return Ok(NetworkEvent::Malformed(entity, error));
+            }
+        }
+    }
+    .await;
+
+    let _ = events.send(result.unwrap_or_else(|error| NetworkEvent::Closed(entity, Some(error))));
+}
+
+/// Writes the batches of packets queued for the client, coalescing whatever is queued at once
+/// into a single write. Shuts the connection down once it's closed, or once the [Outbox] is
+/// dropped.
+///
+/// A packet that can't be sent (e.g. one that isn't valid in the state the connection is in)
+/// closes the connection: the packets before it are still written, followed by a disconnect.
+async fn write_loop(
+    mut writer: OwnedWriteHalf,
+    session: Arc<Mutex<ServerSession>>,
+    capture: Option<Capture>,
+    mut batches: mpsc::UnboundedReceiver<Vec<Outgoing>>,
+) -> anyhow::Result<()> {
+    let mut closed = false;
+    let mut failed = None;
+
+    while !closed {
+        let Some(batch) = batches.recv().await else {
+            break;
+        };
+
+        let bytes = {
+            let mut session = session.lock().unwrap();
+            let mut next = Some(batch);
+
+            // batches flushed while the last write was in progress are written along with this one
+            'batches: while let Some(batch) = next {
+                for outgoing in batch {
+                    match outgoing {
+                        Outgoing::Packet(packet) => {
+                            if let Err(error) = session.send_any(packet) {
+                                let reason = ChatComponent::builder()
+                                    .text("Internal server error".to_string())
+                                    .build();
+                                send_disconnect(&mut session, reason)?;
+
+                                failed = Some(error);
+                                closed = true;
+                                break 'batches;
+                            }
+                        }
+                        Outgoing::EnableEncryption(key) => session.enable_encryption(&key),
+                        Outgoing::Close(reason) => {
+                            if let Some(reason) = reason {
+                                send_disconnect(&mut session, reason)?;
+                            }
+
+                            // anything queued after closing is dropped
+                            closed = true;
+                            break 'batches;
+                        }
+                    }
+                }
+                next = batches.try_recv().ok();
+            }
+
+            if let Some(capture) = &capture {
+                capture.write(&mut session);
+            }
+            session.take_outbound()
+        };
+
+        writer.write_all(&bytes).await?;
+    }
+
+    writer.shutdown().await?;
+    failed.map_or(Ok(()), Err)
+}
+
+/// Sends the disconnect of the state the connection is in, if it has one.
+fn send_disconnect(
+    session: &mut ServerSession,
+    reason: ChatComponent<'static>,
+) -> anyhow::Result<()> {
+    match session.state() {
+        State::Login => session.send(login::DisconnectPacket { reason }),
+        State::Play => session.send(play::DisconnectPacket { reason }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs::{self, File},
+        io::{ErrorKind, Read, Write},
+        net::TcpStream,
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use bevy_ecs::{
+        prelude::{EventReader, Events},
+        schedule::{Schedule, Stage, StageLabel, SystemStage},
+        system::Query,
+        world::World,
+    };
+    use client::{Client, ClientOptions};
+    use crossbeam_channel::unbounded;
+    use protocol::{
+        capture::CaptureReader,
+        chat::ChatComponent,
+        packet::{
+            clientbound::{self, ClientboundLoginPacket, ClientboundPacket, ClientboundPlayPacket},
+            serverbound,
+        },
+        session::{ClientSession, ServerSession},
+        varint::VarInt,
+    };
+    use tokio::{net::TcpListener, runtime::Runtime, sync::mpsc};
+
+    use super::{clientbound, flush_outboxes, write_loop, Closed, Connection, Outbox, Outgoing};
+    use crate::{
+        disconnect::{handle_disconnects, Disconnect, Disconnected},
+        packet::PacketEvent,
+        server::{accept_loop, handle_connections},
+        sync::{sync_connections, ChannelsRes, ConnectionTracker, RuntimeRes},
+        ServerBuilder, ServerConfiguration,
+    };
+
+    #[derive(StageLabel)]
+    struct Sync;
+
+    #[derive(StageLabel)]
+    struct Network;
+
+    /// Logs players in with compression, and echoes their chat messages back.
+    fn login(
+        mut logins: EventReader<PacketEvent<serverbound::login::LoginStartPacket>>,
+        mut messages: EventReader<PacketEvent<serverbound::play::ChatMessagePacket>>,
+        mut outboxes: Query<&mut Outbox>,
+    ) {
+        for PacketEvent { entity, packet } in logins.iter() {
+            let mut outbox = outboxes.get_mut(*entity).unwrap();
+            outbox.send(clientbound::login::SetCompressionPacket {
+                threshold: VarInt(0),
+            });
+            outbox.send(clientbound::login::LoginSuccessPacket {
+                uuid: "00000000-0000-0000-0000-000000000000".to_string(),
+                username: packet.name.clone(),
+            });
+        }
+
+        for PacketEvent { entity, packet } in messages.iter() {
+            outboxes
+                .get_mut(*entity)
+                .unwrap()
+                .send(clientbound::play::ChatMessagePacket {
+                    message: ChatComponent::builder()
+                        .text(packet.message.clone())
+                        .build(),
+                    position: 0,
+                });
+        }
     }
 
-    pub fn read(&mut self) -> Result<()> {
-        match self.stream.read_to_end(&mut self.buf) {
-            Ok(0) | Err(_) => Err(anyhow!("couldn't read")),
-            Ok(_) => Ok(()),
+    #[test]
+    pub fn login_and_close() {
+        let runtime = Runtime::new().unwrap();
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let (connection_sender, connection_receiver) = unbounded();
+        let (event_sender, event_receiver) = unbounded();
+        runtime.spawn(accept_loop(listener, connection_sender.clone()));
+
+        let mut world = World::new();
+        world.insert_resource(Events::<
+            PacketEvent<serverbound::handshaking::HandshakePacket>,
+        >::default());
+        world.insert_resource(
+            Events::<PacketEvent<serverbound::login::LoginStartPacket>>::default(),
+        );
+        world.insert_resource(
+            Events::<PacketEvent<serverbound::play::ChatMessagePacket>>::default(),
+        );
+        world.insert_resource(Events::<Closed>::default());
+        world.insert_resource(Events::<Disconnect>::default());
+        world.insert_resource(Events::<Disconnected>::default());
+        world.insert_resource(ConnectionTracker::new());
+        world.insert_resource(ChannelsRes {
+            connection_sender,
+            connection_receiver,
+            event_sender,
+            event_receiver,
+        });
+        world.insert_resource(RuntimeRes(runtime.handle().clone()));
+        world.insert_resource(ServerConfiguration::default());
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage(Sync, SystemStage::parallel().with_system(sync_connections));
+        schedule.add_stage(
+            Network,
+            SystemStage::single_threaded()
+                .with_system(
+                    Events::<PacketEvent<serverbound::login::LoginStartPacket>>::update_system,
+                )
+                .with_system(
+                    Events::<PacketEvent<serverbound::play::ChatMessagePacket>>::update_system,
+                )
+                .with_system(handle_connections)
+                .with_system(login)
+                .with_system(handle_disconnects)
+                .with_system(flush_outboxes),
+        );
+
+        let mut session = ClientSession::new();
+        session
+            .send(serverbound::handshaking::HandshakePacket {
+                protocol_version: VarInt(47),
+                server_address: "localhost".to_string(),
+                server_port: address.port(),
+                next_state: VarInt(2),
+            })
+            .unwrap();
+        session
+            .send(serverbound::login::LoginStartPacket {
+                name: "jam".to_string(),
+            })
+            .unwrap();
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.set_nonblocking(true).unwrap();
+        stream.write_all(&session.take_outbound()).unwrap();
+
+        // runs the server until the client received the packet `until` looks for
+        let mut run_until =
+            |stream: &mut TcpStream,
+             session: &mut ClientSession,
+             until: &dyn Fn(&ClientboundPacket) -> bool| {
+                let mut buf = [0; 1024];
+
+                for _ in 0..500 {
+                    schedule.run(&mut world);
+
+                    match stream.read(&mut buf) {
+                        Ok(0) => panic!("the server closed the connection"),
+                        Ok(len) => session.receive(&buf[..len]),
+                        Err(error) if error.kind() == ErrorKind::WouldBlock => {}
+                        Err(error) => panic!("{}", error),
+                    }
+                    while let Some(packet) = session.next_packet().unwrap() {
+                        if until(&packet) {
+                            return;
+                        }
+                    }
+
+                    thread::sleep(Duration::from_millis(5));
+                }
+
+                panic!("timed out");
+            };
+
+        run_until(&mut stream, &mut session, &|packet| {
+            matches!(
+                packet,
+                ClientboundPacket::Login(ClientboundLoginPacket::LoginSuccess(_))
+            )
+        });
+
+        // the chat message is compressed both ways
+        session
+            .send(serverbound::play::ChatMessagePacket {
+                message: "hi".to_string(),
+            })
+            .unwrap();
+        stream.write_all(&session.take_outbound()).unwrap();
+        run_until(&mut stream, &mut session, &|packet| {
+            matches!(
+                packet,
+                ClientboundPacket::Play(ClientboundPlayPacket::ChatMessage(_))
+            )
+        });
+
+        // the entity goes away along with the connection
+        assert_eq!(1, world.query::<&Connection>().iter(&world).count());
+        drop(stream);
+        for _ in 0..500 {
+            schedule.run(&mut world);
+            if world.query::<&Connection>().iter(&world).count() == 0 {
+                return;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        panic!("the connection's entity wasn't despawned");
+    }
+
+    #[test]
+    pub fn outbox() {
+        let (batches, mut queue) = mpsc::unbounded_channel();
+        let mut outbox = Outbox {
+            queue: Vec::new(),
+            batches,
+        };
+
+        for id in 0..3 {
+            outbox.send(clientbound::play::KeepAlivePacket { id: VarInt(id) });
+        }
+        assert!(queue.try_recv().is_err());
+
+        // everything queued is sent as one batch, and nothing once it's empty
+        outbox.flush();
+        outbox.flush();
+        let batch = queue.try_recv().unwrap();
+        assert_eq!(3, batch.len());
+        assert!(matches!(batch[0], Outgoing::Packet(_)));
+        assert!(queue.try_recv().is_err());
+
+        // what's left is flushed when the outbox is dropped
+        outbox.close();
+        drop(outbox);
+        let batch = queue.try_recv().unwrap();
+        assert!(matches!(batch[..], [Outgoing::Close(None)]));
+        assert!(queue.try_recv().is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn capture() {
+        let dir = std::env::temp_dir().join(format!("jam-capture-{}", std::process::id()));
+        let config = ServerConfiguration::builder()
+            .host("127.0.0.1")
+            .port(0)
+            .capture_dir(dir.to_string_lossy())
+            .build();
+        let mut server = ServerBuilder::new(config).build().await.unwrap();
+        let address = server.local_addr().to_string();
+        let handle = server.handle();
+        let running = tokio::spawn(async move { server.run().await });
+
+        let options = ClientOptions::builder().username("jam".to_string()).build();
+        let mut client = Client::connect(&address, options).await.unwrap();
+        while let Some(packet) = client.next_packet().await {
+            if let ClientboundPlayPacket::PlayerPositionAndLook(_) = packet {
+                break;
+            }
         }
+        handle.shutdown().unwrap();
+        running.await.unwrap();
+
+        let file = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let mut reader = CaptureReader::new(File::open(file).unwrap()).unwrap();
+        let mut names = Vec::new();
+        while let Some(record) = reader.next_record().unwrap() {
+            names.push(record.decode().unwrap().name());
+        }
+        assert_eq!(
+            ["Handshake", "LoginStart", "LoginSuccess", "JoinGame"],
+            names[..4]
+        );
+        assert_eq!(Some(&"Disconnect"), names.last());
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 
-    pub fn buf_write_id(buf: &mut BytesMut, id: i32) -> Result<()> {
-        VarInt(id).encode(buf)
-    }
-
-    pub async fn buf_send(&mut self, pkt_buf: &mut BytesMut) -> Result<()> {
-        // create a buffer for the length of the packet (sent first)
-        let mut len_buf = BytesMut::new();
-
-        // write length of the packet to the length buffer
-        VarInt(pkt_buf.len() as i32).encode(&mut len_buf)?;
-
-        // write length first, then the packet
-        self.stream.write(&len_buf)?;
-        self.stream.write(&pkt_buf)?;
-
-        Ok(())
-    }
-
-    //     pub async fn handle_loop(mut self) -> anyhow::Result<()> {
-    //         let mut buf = BytesMut::new();
-    //
-    //         loop {
-    //             let stream = &mut self.stream;
-    //
-    //             stream.read_buf(&mut buf)?;
-    //
-    //             while !buf.is_empty() {
-    //                 // read packet frame
-    //                 let _length = VarInt::decode(&mut buf)?;
-    //                 let id = VarInt::decode(&mut buf)?;
-    //
-    //                 self.handle_packet(id.0, &mut buf)?;
-    //             }
-    //         }
-    //     }
-    //
-    //
-    //     pub async fn handle_packet(
-    //         &mut self,
-    //         id: i32,
-    //         buf: &mut BytesMut,
-    //     ) -> anyhow::Result<(), anyhow::Error> {
-    //         match self.state {
-    //             State::Handshaking => {
-    //                 match id {
-    //                     0 => {
-    //                         // read handshake packet
-    //                         let packet = HandshakePacket::decode(buf)?;
-    //
-    //                         // switch to next state
-    //                         match packet.next_state.0 {
-    //                             1 => self.state = State::Status,
-    //                             2 => self.state = State::Login,
-    //
-    //                             _ => return Err(anyhow!("invalid next_state (must be either 1 or 2), are you reading the wrong packet?")),
-    //                         }
-    //                     }
-    //
-    //                     _ => println!("unimplemented packet: {}", id),
-    //                 }
-    //             }
-    //             State::Status => {
-    //                 match id {
-    //                     0 => {
-    //                         // read status request packet (and completely ignore it as it has no fields)
-    //                         RequestPacket::decode(buf)?;
-    //
-    //                         // craft response packet
-    //                         let response_pkt = ResponsePacket {
-    //                             response: serde_json::json!({
-    //                                 "version": {
-    //                                     "name": "1.8.9",
-    //                                     "protocol": 47
-    //                                 },
-    //                                 "players": {
-    //                                     "max": 20,
-    //                                     "online": 0,
-    //                                 },
-    //                                 "description":
-    //                             })
-    //                             .to_string(),
-    //                         };
-    //
-    //                         // prepare buffer
-    //                         let mut buf = Self::buf_prep();
-    //
-    //                         // write packet to buffer
-    //                         Self::buf_write_id(&mut buf, 0)?;
-    //                         response_pkt.encode(&mut buf)?;
-    //
-    //                         // send packet
-    //                         self.buf_send(&mut buf)?;
-    //                     }
-    //
-    //                     1 => {
-    //                         let pkt = PingPacket::decode(buf)?;
-    //
-    //                         // craft response packet
-    //                         let response_pkt = PongPacket {
-    //                             payload: pkt.payload,
-    //                         };
-    //
-    //                         // prepare buffer
-    //                         let mut buf = Self::buf_prep();
-    //
-    //                         // write packet to buffer
-    //                         Self::buf_write_id(&mut buf, 1)?;
-    //                         response_pkt.encode(&mut buf)?;
-    //
-    //                         // send packet
-    //                         self.buf_send(&mut buf)?;
-    //
-    //                         // close connection
-    //                         self.stream.shutdown(std::net::Shutdown::Write)?;
-    //                     }
-    //
-    //                     _ => println!("unimplemented packet: {}", id),
-    //                 }
-    //             }
-    //             State::Login => println!("login state"),
-    //             State::Play => println!("play state"),
-    //             State::Closed => println!("closed state"),
-    //         }
-    //
-    //         self.stream.flush()?;
-    //         Ok(())
-    //     }
+    #[test]
+    pub fn invalid_packet() {
+        let runtime = Runtime::new().unwrap();
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = runtime.block_on(listener.accept()).unwrap();
+
+        let mut client = ClientSession::new();
+        client
+            .send(serverbound::handshaking::HandshakePacket {
+                protocol_version: VarInt(47),
+                server_address: "localhost".to_string(),
+                server_port: 25565,
+                next_state: VarInt(2),
+            })
+            .unwrap();
+        let mut session = ServerSession::new();
+        session.receive_bytes(client.take_outbound());
+        session.next_packet().unwrap();
+
+        // the second login success isn't valid anymore, as the first one moves on to play
+        let success = || {
+            Outgoing::Packet(clientbound(clientbound::login::LoginSuccessPacket {
+                uuid: "00000000-0000-0000-0000-000000000000".to_string(),
+                username: "jam".to_string(),
+            }))
+        };
+        let (batches, queue) = mpsc::unbounded_channel();
+        batches.send(vec![success(), success()]).unwrap();
+
+        let (_, writer) = server.into_split();
+        let session = Arc::new(Mutex::new(session));
+        let result = runtime.block_on(write_loop(writer, session, None, queue));
+        assert!(result.is_err());
+
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).unwrap();
+        client.receive(&buf);
+        assert!(matches!(
+            client.next_packet().unwrap(),
+            Some(ClientboundPacket::Login(
+                ClientboundLoginPacket::LoginSuccess(_)
+            ))
+        ));
+        let Some(ClientboundPacket::Play(ClientboundPlayPacket::Disconnect(disconnect))) =
+            client.next_packet().unwrap()
+        else {
+            panic!("the client wasn't told why it was disconnected");
+        };
+        assert_eq!("Internal server error", disconnect.reason.plain_text());
+    }
 }
@@ -0,0 +1,148 @@
+//! Commands typed into the server's console.
+
+use std::path::PathBuf;
+
+use bevy_ecs::system::{Res, ResMut, Resource};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use tokio::io::{self, AsyncBufReadExt, BufReader};
+
+use crate::{
+    builder::ServerHandle,
+    config::{self, ConfigFile},
+    server::ServerConfiguration,
+    sync::RuntimeRes,
+    tick::{Shutdown, Tick, TickMetrics},
+};
+
+/// The lines typed into the console, waiting to be handled.
+#[derive(Resource, Clone)]
+pub struct ConsoleRes {
+    pub receiver: Receiver<String>,
+}
+
+/// A configuration file read for the `reload` command.
+pub struct Reload {
+    pub path: PathBuf,
+    pub result: anyhow::Result<ServerConfiguration>,
+}
+
+/// The files the `reload` command read, waiting to be applied. They're read on the runtime, as
+/// reading a file can take a while.
+#[derive(Resource)]
+pub struct Reloads {
+    sender: Sender<Reload>,
+    receiver: Receiver<Reload>,
+}
+
+impl Default for Reloads {
+    fn default() -> Self {
+        let (sender, receiver) = unbounded();
+        Self { sender, receiver }
+    }
+}
+
+/// Reads commands from stdin, one per line, until it's closed.
+pub async fn read_loop(server: ServerHandle) -> anyhow::Result<()> {
+    let mut lines = BufReader::new(io::stdin()).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let line = line.trim();
+        if !line.is_empty() {
+            server.command(line)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// System that runs the commands typed into the console.
+pub fn handle_console(
+    console: Res<ConsoleRes>,
+    tick: Res<Tick>,
+    metrics: Res<TickMetrics>,
+    mut shutdown: ResMut<Shutdown>,
+    file: Option<Res<ConfigFile>>,
+    reloads: Res<Reloads>,
+    runtime: Res<RuntimeRes>,
+) {
+    for line in console.receiver.try_iter() {
+        match line.split_whitespace().next() {
+            Some("tps") => println!(
+                "tick {}: {:.1} TPS, {:.2} MSPT",
+                tick.count,
+                metrics.tps(),
+                metrics.mspt()
+            ),
+            Some("reload") => match file.as_deref() {
+                Some(file) => {
+                    let path = file.path.clone();
+                    let sender = reloads.sender.clone();
+                    runtime.0.spawn_blocking(move || {
+                        let result = config::read(&path);
+                        let _ = sender.send(Reload { path, result });
+                    });
+                }
+                None => println!("the configuration wasn't loaded from a file"),
+            },
+            Some("stop") => {
+                println!("stopping the server");
+                shutdown.requested = true;
+            }
+            Some(command) => println!("unknown command: {}", command),
+            None => {}
+        }
+    }
+}
+
+/// System that applies the configuration files the `reload` command read.
+pub fn apply_reloads(reloads: Res<Reloads>, mut config: ResMut<ServerConfiguration>) {
+    for Reload { path, result } in reloads.receiver.try_iter() {
+        match result {
+            Ok(new) => {
+                config.reload(new);
+                println!("reloaded {}", path.display());
+            }
+            Err(error) => println!("couldn't reload: {:#}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, time::Duration};
+
+    use crate::{config::ConfigFile, ServerBuilder, ServerConfiguration};
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn reload() {
+        let path = std::env::temp_dir().join(format!("jam-reload-{}.toml", std::process::id()));
+        fs::write(&path, "motd = \"reloaded\"\nport = 1\n").unwrap();
+
+        let config = ServerConfiguration::builder()
+            .host("127.0.0.1")
+            .port(0)
+            .build();
+        let mut server = ServerBuilder::new(config)
+            .insert_resource(ConfigFile { path: path.clone() })
+            .build()
+            .await
+            .unwrap();
+
+        server.handle().command("reload").unwrap();
+
+        // the file is read in the background, and applied on a later tick
+        for _ in 0..100 {
+            server.tick();
+            if server.world().resource::<ServerConfiguration>().motd == "reloaded" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let config = server.world().resource::<ServerConfiguration>();
+        assert_eq!("reloaded", config.motd);
+        assert_eq!(0, config.port);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,266 @@
+//! Disconnects clients, with a reason.
+//!
+//! Systems kick a client by sending a [Disconnect] event. The client is sent the disconnect packet
+//! of the state it's in, then the connection is closed and its entity despawned. Whichever end
+//! closes the connection, plugins are told when a player leaves through a [Disconnected] event.
+
+use std::{collections::HashSet, net::SocketAddr, time::Duration};
+
+use bevy_ecs::{
+    entity::Entity,
+    prelude::{EventReader, EventWriter, Events},
+    query::With,
+    schedule::Schedule,
+    system::{Commands, Query, ResMut},
+    world::World,
+};
+use protocol::chat::ChatComponent;
+
+use crate::{
+    auth::GameProfile,
+    connection::{Closed, Connection, Outbox},
+    player::Player,
+    server::ServerConfiguration,
+    sync::ConnectionTracker,
+    tick::run_tick,
+};
+
+/// How long the server waits for the disconnects to be written when it shuts down.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Asks for a client to be kicked with the reason.
+#[derive(Debug, Clone)]
+pub struct Disconnect {
+    pub entity: Entity,
+    pub reason: ChatComponent<'static>,
+}
+
+impl Disconnect {
+    /// Kicks a client with a plain text reason.
+    pub fn new(entity: Entity, reason: &str) -> Self {
+        Self {
+            entity,
+            reason: ChatComponent::builder().text(reason.to_string()).build(),
+        }
+    }
+}
+
+/// Why a player left.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum DisconnectReason {
+    /// The server kicked them.
+    Kicked(ChatComponent<'static>),
+    /// Their client closed the connection.
+    Left,
+    /// The connection failed.
+    Error(anyhow::Error),
+}
+
+/// A player left. Their entity is despawned at the end of the
+/// [Disconnect](crate::ServerStage::Disconnect) stage, so only systems of that stage still see it.
+#[derive(Debug)]
+pub struct Disconnected {
+    pub entity: Entity,
+    pub address: SocketAddr,
+    pub profile: GameProfile,
+    pub reason: DisconnectReason,
+}
+
+/// System that kicks the clients [Disconnect] events are sent for, and despawns the entities of
+/// [Closed] connections.
+pub fn handle_disconnects(
+    mut events: EventReader<Disconnect>,
+    mut closed: ResMut<Events<Closed>>,
+    mut connections: Query<(&Connection, &mut Outbox, Option<&Player>)>,
+    mut disconnected: EventWriter<Disconnected>,
+    mut commands: Commands,
+) {
+    // a client may be kicked more than once in a tick, and both connection tasks may report it
+    // closing: only the first counts
+    let mut gone = HashSet::new();
+
+    for Disconnect { entity, reason } in events.iter() {
+        let Ok((connection, mut outbox, player)) = connections.get_mut(*entity) else {
+            continue;
+        };
+        if !gone.insert(*entity) {
+            continue;
+        }
+
+        // the disconnect is still written once the outbox is dropped
+        outbox.disconnect(reason.clone());
+        commands.entity(*entity).despawn();
+
+        if let Some(player) = player {
+            println!(
+                "{} was kicked: {} [entity id: {}]",
+                player.profile.name,
+                reason.plain_text(),
+                entity.index()
+            );
+
+            disconnected.send(Disconnected {
+                entity: *entity,
+                address: connection.address,
+                profile: player.profile.clone(),
+                reason: DisconnectReason::Kicked(reason.clone()),
+            });
+        }
+    }
+
+    for Closed { entity, error } in closed.drain() {
+        if let Some(error) = &error {
+            println!(
+                "connection error: [entity id: {}] {:#}",
+                entity.index(),
+                error
+            );
+        }
+
+        // connections the server closed are already gone
+        let Ok((connection, _, player)) = connections.get(entity) else {
+            continue;
+        };
+        if !gone.insert(entity) {
+            continue;
+        }
+        commands.entity(entity).despawn();
+
+        if let Some(player) = player {
+            println!(
+                "{} lost connection [entity id: {}]",
+                player.profile.name,
+                entity.index()
+            );
+
+            disconnected.send(Disconnected {
+                entity,
+                address: connection.address,
+                profile: player.profile.clone(),
+                reason: error.map_or(DisconnectReason::Left, DisconnectReason::Error),
+            });
+        }
+    }
+}
+
+/// Kicks every client with the configured shutdown message, and waits for the disconnects to be
+/// written (for a few seconds at most).
+pub async fn shutdown(world: &mut World, schedule: &mut Schedule) {
+    let message = world
+        .resource::<ServerConfiguration>()
+        .shutdown_message
+        .clone();
+    let entities = world
+        .query_filtered::<Entity, With<Connection>>()
+        .iter(world)
+        .collect::<Vec<_>>();
+
+    let mut events = world.resource_mut::<Events<Disconnect>>();
+    for entity in entities {
+        events.send(Disconnect::new(entity, &message));
+    }
+    run_tick(world, schedule);
+
+    // connections accepted during that tick are closed without a reason
+    let entities = world
+        .query_filtered::<Entity, With<Connection>>()
+        .iter(world)
+        .collect::<Vec<_>>();
+    for entity in entities {
+        world.despawn(entity);
+    }
+
+    if let Some(tracker) = world.remove_resource::<ConnectionTracker>() {
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, tracker.wait())
+            .await
+            .is_err()
+        {
+            println!("some connections didn't close in time");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy_ecs::{
+        entity::Entity,
+        prelude::{EventReader, EventWriter},
+        query::Added,
+        system::{Query, ResMut, Resource},
+    };
+    use client::{Client, ClientOptions};
+    use protocol::packet::clientbound::ClientboundPlayPacket;
+
+    use super::{Disconnect, DisconnectReason, Disconnected};
+    use crate::{connection::Connection, player::Player, ServerBuilder, ServerConfiguration};
+
+    /// The players that left.
+    #[derive(Resource, Default)]
+    struct Left(Vec<(String, String)>);
+
+    fn kick_on_join(
+        players: Query<Entity, Added<Player>>,
+        mut disconnects: EventWriter<Disconnect>,
+    ) {
+        for entity in &players {
+            disconnects.send(Disconnect::new(entity, "Go away"));
+        }
+    }
+
+    fn record_left(mut events: EventReader<Disconnected>, mut left: ResMut<Left>) {
+        for Disconnected {
+            profile, reason, ..
+        } in events.iter()
+        {
+            let DisconnectReason::Kicked(reason) = reason else {
+                panic!("{:?} wasn't kicked", reason);
+            };
+            left.0.push((profile.name.clone(), reason.plain_text()));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn kick() {
+        let config = ServerConfiguration::builder()
+            .host("127.0.0.1")
+            .port(0)
+            .build();
+        let mut server = ServerBuilder::new(config)
+            .insert_resource(Left::default())
+            .add_system(kick_on_join)
+            .add_system(record_left)
+            .build()
+            .await
+            .unwrap();
+        let address = server.local_addr().to_string();
+        let handle = server.handle();
+        let running = tokio::spawn(async move {
+            server.run().await;
+            server
+        });
+
+        let options = ClientOptions::builder()
+            .username("jeb_".to_string())
+            .build();
+        let mut client = Client::connect(&address, options).await.unwrap();
+
+        // the kick comes after what the login sent
+        let mut reason = None;
+        while let Some(packet) = client.next_packet().await {
+            if let ClientboundPlayPacket::Disconnect(disconnect) = packet {
+                reason = Some(disconnect.reason.plain_text());
+            }
+        }
+        assert_eq!(Some("Go away".to_string()), reason);
+
+        handle.shutdown().unwrap();
+        let mut server = running.await.unwrap();
+        let world = server.world_mut();
+        assert_eq!(0, world.query::<&Connection>().iter(world).count());
+        assert_eq!(
+            vec![("jeb_".to_string(), "Go away".to_string())],
+            world.resource::<Left>().0
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Keeps connections alive, and measures their latency.
+//!
+//! Every player is sent a keep alive with a random ID every few seconds, which the client echoes
+//! back. The time it took is the player's [Latency]. Players that don't answer within
+//! [KEEP_ALIVE_TIMEOUT] are disconnected.
+
+use std::time::{Duration, Instant};
+
+use bevy_ecs::{
+    entity::Entity,
+    prelude::{Component, EventReader, EventWriter},
+    query::With,
+    system::{Commands, Query, Res},
+};
+use protocol::{
+    packet::{clientbound::play, serverbound},
+    varint::VarInt,
+};
+
+use crate::{
+    connection::Outbox, disconnect::Disconnect, packet::PacketEvent, player::Player,
+    server::ServerConfiguration,
+};
+
+/// How often keep alives are sent.
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long clients have to answer a keep alive.
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The round trip time of the last keep alive.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latency(pub Duration);
+
+/// What a [KeepAlive] asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Poll {
+    Wait,
+    /// Send a keep alive with this ID.
+    Send(i32),
+    /// The client didn't answer in time.
+    TimedOut,
+}
+
+/// The keep alives of a player.
+#[derive(Component, Debug)]
+pub struct KeepAlive {
+    /// The ID of the keep alive waiting for an answer, and when it was sent.
+    pending: Option<(i32, Instant)>,
+    last_sent: Instant,
+}
+
+impl KeepAlive {
+    /// Keep alives for a player that just joined, who gets the first one right away.
+    pub fn new(now: Instant) -> Self {
+        Self {
+            pending: None,
+            last_sent: now.checked_sub(KEEP_ALIVE_INTERVAL).unwrap_or(now),
+        }
+    }
+
+    /// Whether a keep alive is due, or the client timed out. Keep alives that are due get the ID
+    /// `id`.
+    pub fn poll(&mut self, now: Instant, id: i32) -> Poll {
+        match self.pending {
+            Some((_, sent)) if now.duration_since(sent) >= KEEP_ALIVE_TIMEOUT => Poll::TimedOut,
+            Some(_) => Poll::Wait,
+            None if now.duration_since(self.last_sent) >= KEEP_ALIVE_INTERVAL => {
+                self.pending = Some((id, now));
+                self.last_sent = now;
+                Poll::Send(id)
+            }
+            None => Poll::Wait,
+        }
+    }
+
+    /// Takes the client's answer, returning the round trip time if it answers the pending keep
+    /// alive.
+    pub fn receive(&mut self, id: i32, now: Instant) -> Option<Duration> {
+        match self.pending {
+            Some((pending, sent)) if pending == id => {
+                self.pending = None;
+                Some(now.duration_since(sent))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// System that sends keep alives to players, and disconnects the ones that timed out.
+pub fn send_keep_alives(
+    config: Res<ServerConfiguration>,
+    mut players: Query<(Entity, &mut Outbox, Option<&mut KeepAlive>), With<Player>>,
+    mut disconnects: EventWriter<Disconnect>,
+    mut commands: Commands,
+) {
+    let now = Instant::now();
+
+    for (entity, mut outbox, keep_alive) in &mut players {
+        let Some(mut keep_alive) = keep_alive else {
+            commands.entity(entity).insert(KeepAlive::new(now));
+            continue;
+        };
+
+        match keep_alive.poll(now, rand::random()) {
+            Poll::Wait => {}
+            Poll::Send(id) => outbox.send(play::KeepAlivePacket { id: VarInt(id) }),
+            Poll::TimedOut => disconnects.send(Disconnect::new(entity, &config.timeout_message)),
+        }
+    }
+}
+
+/// System that matches the keep alives clients answer, and records their latency.
+pub fn receive_keep_alives(
+    mut events: EventReader<PacketEvent<serverbound::play::KeepAlivePacket>>,
+    mut players: Query<&mut KeepAlive>,
+    mut commands: Commands,
+) {
+    let now = Instant::now();
+
+    for PacketEvent { entity, packet } in events.iter() {
+        if let Ok(mut keep_alive) = players.get_mut(*entity) {
+            if let Some(latency) = keep_alive.receive(packet.id.0, now) {
+                commands.entity(*entity).insert(Latency(latency));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{KeepAlive, Poll, KEEP_ALIVE_INTERVAL, KEEP_ALIVE_TIMEOUT};
+
+    #[test]
+    pub fn keep_alive() {
+        // far enough from whenever the clock starts to go back an interval
+        let start = Instant::now() + KEEP_ALIVE_TIMEOUT;
+        let mut keep_alive = KeepAlive::new(start);
+
+        assert_eq!(Poll::Send(1), keep_alive.poll(start, 1));
+        assert_eq!(Poll::Wait, keep_alive.poll(start, 2));
+
+        // only the pending ID counts
+        let answered = start + Duration::from_millis(40);
+        assert_eq!(None, keep_alive.receive(2, answered));
+        assert_eq!(
+            Some(Duration::from_millis(40)),
+            keep_alive.receive(1, answered)
+        );
+        assert_eq!(None, keep_alive.receive(1, answered));
+
+        assert_eq!(Poll::Wait, keep_alive.poll(answered, 3));
+        let next = start + KEEP_ALIVE_INTERVAL;
+        assert_eq!(Poll::Send(3), keep_alive.poll(next, 3));
+
+        assert_eq!(
+            Poll::Wait,
+            keep_alive.poll(next + KEEP_ALIVE_TIMEOUT - Duration::from_millis(1), 4)
+        );
+        assert_eq!(
+            Poll::TimedOut,
+            keep_alive.poll(next + KEEP_ALIVE_TIMEOUT, 4)
+        );
+    }
+}
@@ -0,0 +1,22 @@
+//! jam, a lightweight Minecraft server, to run on its own or to embed (see [ServerBuilder]).
+
+pub use crate::{
+    builder::{Server, ServerBuilder, ServerHandle, ServerStage},
+    server::ServerConfiguration,
+};
+
+pub mod auth;
+pub mod builder;
+pub mod config;
+pub mod connection;
+pub mod console;
+pub mod disconnect;
+pub mod keep_alive;
+pub mod login;
+pub mod packet;
+pub mod player;
+pub mod recording;
+pub mod server;
+pub mod status;
+pub mod sync;
+pub mod tick;
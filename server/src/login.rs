@@ -0,0 +1,520 @@
+//! Logs players in, and spawns them into the world.
+//!
+//! In offline mode, the player gets the UUID vanilla would give them as soon as the login start
+//! arrives. In online mode, the server first sends an encryption request. Once the client answers
+//! it, the connection is encrypted, and the session server is asked whether the player joined the
+//! session (see [auth](crate::auth)), off the ECS. Its answer comes back through
+//! [Verifications], and holds the player's UUID and skin.
+//!
+//! Either way, the player then moves on to the play state. The join game, spawn position,
+//! abilities and position that follow are what the client needs to leave the loading screen.
+
+use std::{collections::HashSet, path::Path};
+
+use bevy_ecs::{
+    entity::Entity,
+    prelude::{Component, EventReader, EventWriter},
+    system::{Commands, Query, Res, Resource},
+};
+use bytes::Bytes;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use protocol::{
+    chat::ChatComponent,
+    packet::{
+        clientbound::{login, play},
+        serverbound::login::{EncryptionResponsePacket, LoginStartPacket},
+    },
+    position::Position,
+    version::{outdated_disconnect, ProtocolVersion},
+};
+use rand::RngCore;
+
+use crate::{
+    auth::{GameProfile, ServerKey, SessionService},
+    connection::{Connection, Outbox},
+    disconnect::Disconnect,
+    packet::PacketEvent,
+    player::{Location, Player},
+    recording::Recording,
+    server::ServerConfiguration,
+    sync::RuntimeRes,
+};
+
+/// Where players spawn.
+const SPAWN: Location = Location {
+    x: 0.5,
+    y: 64.0,
+    z: 0.5,
+    yaw: 0.0,
+    pitch: 0.0,
+    on_ground: false,
+};
+
+/// Whether a name is allowed: 1 to 16 letters, digits and underscores, like vanilla accounts.
+pub fn is_valid_username(username: &str) -> bool {
+    (1..=16).contains(&username.len())
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A client that was sent an encryption request, in online mode.
+#[derive(Component, Debug)]
+pub struct EncryptionRequested {
+    pub username: String,
+    verify_token: [u8; 4],
+}
+
+/// What the session server answered about a player logging in.
+pub struct Verification {
+    pub entity: Entity,
+    /// The player's profile, or `None` if they didn't join the session they claimed to.
+    pub result: anyhow::Result<Option<GameProfile>>,
+}
+
+/// The answers of the session server, sent back by the tasks asking it.
+#[derive(Resource, Clone)]
+pub struct Verifications {
+    sender: Sender<Verification>,
+    receiver: Receiver<Verification>,
+}
+
+impl Default for Verifications {
+    fn default() -> Self {
+        let (sender, receiver) = unbounded();
+        Self { sender, receiver }
+    }
+}
+
+/// Why a player can't log in, if they can't.
+fn reject(
+    config: &ServerConfiguration,
+    connection: &Connection,
+    username: &str,
+    online: &HashSet<String>,
+) -> Option<ChatComponent<'static>> {
+    if let Some(disconnect) = outdated_disconnect(connection.protocol()) {
+        return Some(disconnect.reason);
+    }
+
+    let reason = if !connection.version().is_some_and(ProtocolVersion::can_play) {
+        // vanilla 1.8.9 tells every older client to update
+        "Outdated client! Please use 1.8.9"
+    } else if !is_valid_username(username) {
+        "Invalid username"
+    } else if online.contains(&username.to_lowercase()) {
+        "A player with that name is already online"
+    } else if online.len() >= config.max_players.max(0) as usize {
+        "The server is full!"
+    } else {
+        return None;
+    };
+
+    Some(ChatComponent::builder().text(reason.to_string()).build())
+}
+
+/// System that logs players in: right away in offline mode, and once the session server answered
+/// in online mode. Then it spawns them.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_login(
+    mut events: EventReader<PacketEvent<LoginStartPacket>>,
+    verifications: Res<Verifications>,
+    config: Res<ServerConfiguration>,
+    key: Option<Res<ServerKey>>,
+    mut connections: Query<(&Connection, &mut Outbox)>,
+    players: Query<&Player>,
+    mut disconnects: EventWriter<Disconnect>,
+    mut commands: Commands,
+) {
+    // names are compared like vanilla does, ignoring case
+    let mut online = players
+        .iter()
+        .map(|player| player.profile.name.to_lowercase())
+        .collect::<HashSet<_>>();
+
+    for PacketEvent { entity, packet } in events.iter() {
+        let Ok((connection, mut outbox)) = connections.get_mut(*entity) else {
+            continue;
+        };
+
+        if let Some(reason) = reject(&config, connection, &packet.name, &online) {
+            disconnects.send(Disconnect {
+                entity: *entity,
+                reason,
+            });
+            continue;
+        }
+
+        match &key {
+            Some(key) if config.online_mode => {
+                let mut verify_token = [0; 4];
+                rand::thread_rng().fill_bytes(&mut verify_token);
+
+                // the server ID is always empty since 1.7
+                outbox.send(login::EncryptionRequestPacket {
+                    server_id: String::new(),
+                    public_key: key.public_key().clone(),
+                    verify_token: Bytes::copy_from_slice(&verify_token),
+                });
+                commands.entity(*entity).insert(EncryptionRequested {
+                    username: packet.name.clone(),
+                    verify_token,
+                });
+            }
+            _ => {
+                let profile = GameProfile::offline(&packet.name);
+                online.insert(profile.name.to_lowercase());
+                join(*entity, profile, &config, &mut outbox, &mut commands);
+            }
+        }
+    }
+
+    for Verification { entity, result } in verifications.receiver.try_iter() {
+        // the client may have left in the meantime
+        let Ok((connection, mut outbox)) = connections.get_mut(entity) else {
+            continue;
+        };
+
+        let profile = match result {
+            Ok(Some(profile)) => profile,
+            Ok(None) => {
+                disconnects.send(Disconnect::new(entity, "Failed to verify username!"));
+                continue;
+            }
+            Err(error) => {
+                println!(
+                    "couldn't verify a player: [entity id: {}] {:#}",
+                    entity.index(),
+                    error
+                );
+                disconnects.send(Disconnect::new(
+                    entity,
+                    "Authentication servers are down. Please try again later, sorry!",
+                ));
+                continue;
+            }
+        };
+
+        // someone else may have logged in with that name while the session server was asked
+        if let Some(reason) = reject(&config, connection, &profile.name, &online) {
+            disconnects.send(Disconnect { entity, reason });
+            continue;
+        }
+
+        online.insert(profile.name.to_lowercase());
+        commands.entity(entity).remove::<EncryptionRequested>();
+        join(entity, profile, &config, &mut outbox, &mut commands);
+    }
+}
+
+/// System that encrypts the connections of clients answering the encryption request, and asks the
+/// session server whether they are who they claim to be.
+pub fn handle_encryption(
+    mut events: EventReader<PacketEvent<EncryptionResponsePacket>>,
+    key: Option<Res<ServerKey>>,
+    service: Option<Res<SessionService>>,
+    verifications: Res<Verifications>,
+    runtime: Res<RuntimeRes>,
+    mut connections: Query<(&mut Outbox, &EncryptionRequested)>,
+    mut disconnects: EventWriter<Disconnect>,
+) {
+    let (Some(key), Some(service)) = (key, service) else {
+        return;
+    };
+
+    for PacketEvent { entity, packet } in events.iter() {
+        // only clients that were sent an encryption request answer it
+        let Ok((mut outbox, requested)) = connections.get_mut(*entity) else {
+            continue;
+        };
+
+        let secret = match key.shared_secret(
+            &packet.shared_secret,
+            &packet.verify_token,
+            &requested.verify_token,
+        ) {
+            Ok(secret) => secret,
+            Err(error) => {
+                println!(
+                    "invalid encryption response: [entity id: {}] {:#}",
+                    entity.index(),
+                    error
+                );
+                disconnects.send(Disconnect::new(*entity, "Invalid encryption response"));
+                continue;
+            }
+        };
+        outbox.enable_encryption(secret);
+
+        let entity = *entity;
+        let username = requested.username.clone();
+        let public_key = key.public_key().clone();
+        let service = service.clone();
+        let sender = verifications.sender.clone();
+        runtime.0.spawn(async move {
+            let result = service
+                .has_joined(&username, &secret, &public_key, None)
+                .await;
+            let _ = sender.send(Verification { entity, result });
+        });
+    }
+}
+
+/// Sends a player logging in what they need to spawn, and makes them a [Player].
+fn join(
+    entity: Entity,
+    profile: GameProfile,
+    config: &ServerConfiguration,
+    outbox: &mut Outbox,
+    commands: &mut Commands,
+) {
+    outbox.send(login::LoginSuccessPacket {
+        uuid: profile.hyphenated_id(),
+        username: profile.name.clone(),
+    });
+
+    let join_game = play::JoinGamePacket {
+        entity_id: entity.index() as i32,
+        gamemode: config.gamemode.id(),
+        dimension: 0,
+        difficulty: config.difficulty,
+        max_players: config.max_players.clamp(0, u8::MAX as i32) as u8,
+        level_type: config.level_type.clone(),
+        reduced_debug_info: false,
+    };
+    if let Some(dir) = &config.recording_dir {
+        match Recording::start(
+            Path::new(dir),
+            config.motd.clone(),
+            &profile,
+            join_game.clone(),
+        ) {
+            Ok(recording) => {
+                commands.entity(entity).insert(recording);
+            }
+            Err(error) => println!("can't record {}: {:#}", profile.name, error),
+        }
+    }
+    outbox.send(join_game);
+    outbox.send(play::SpawnPositionPacket {
+        location: Position {
+            x: SPAWN.x.floor() as i32,
+            y: SPAWN.y.floor() as i16,
+            z: SPAWN.z.floor() as i32,
+        },
+    });
+    outbox.send(play::PlayerAbilitiesPacket {
+        flags: config.gamemode.abilities(),
+        flying_speed: 0.05,
+        fov_modifier: 0.1,
+    });
+    outbox.send(play::PlayerPositionAndLookPacket {
+        x: SPAWN.x,
+        y: SPAWN.y,
+        z: SPAWN.z,
+        yaw: SPAWN.yaw,
+        pitch: SPAWN.pitch,
+        flags: 0,
+    });
+
+    println!(
+        "{} ({}) logged in: [entity id: {}]",
+        profile.name,
+        profile.hyphenated_id(),
+        entity.index()
+    );
+
+    commands
+        .entity(entity)
+        .insert((Player { profile }, SPAWN, config.gamemode));
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        future::Future,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use client::{Authenticator, Client, ClientOptions};
+    use protocol::{
+        packet::{clientbound::ClientboundPlayPacket, PacketSet},
+        version::ProtocolVersion,
+    };
+    use tokio::net::TcpListener;
+
+    use super::is_valid_username;
+    use crate::{
+        auth::{test::mock_session_server, GameProfile},
+        player::{GameMode, Player},
+        ServerBuilder, ServerConfiguration,
+    };
+
+    /// Remembers the hash of the session it joined, rather than asking Mojang.
+    #[derive(Default)]
+    struct Joined(Mutex<Option<String>>);
+
+    impl Authenticator for Joined {
+        fn join<'a>(
+            &'a self,
+            server_hash: &'a str,
+        ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+            *self.0.lock().unwrap() = Some(server_hash.to_string());
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[test]
+    pub fn usernames() {
+        assert!(is_valid_username("jeb_"));
+        assert!(is_valid_username("Notch123"));
+        assert!(!is_valid_username(""));
+        assert!(!is_valid_username("seventeen_chars__"));
+        assert!(!is_valid_username("no spaces"));
+        assert!(!is_valid_username("dé"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn join() {
+        let config = ServerConfiguration::builder()
+            .host("127.0.0.1")
+            .port(0)
+            .max_players(1)
+            .gamemode(GameMode::Creative)
+            .build();
+        let mut server = ServerBuilder::new(config).build().await.unwrap();
+        let address = server.local_addr().to_string();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let ticks = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    server.tick();
+                    thread::sleep(Duration::from_millis(5));
+                }
+                server
+            }
+        });
+
+        let options = ClientOptions::builder()
+            .username("jeb_".to_string())
+            .build();
+        let mut client = Client::connect(&address, options).await.unwrap();
+        assert_eq!(GameProfile::offline("jeb_").hyphenated_id(), client.uuid());
+
+        let mut names = Vec::new();
+        while let Some(packet) = client.next_packet().await {
+            match &packet {
+                ClientboundPlayPacket::JoinGame(packet) => assert_eq!(1, packet.gamemode),
+                ClientboundPlayPacket::PlayerAbilities(packet) => assert_eq!(0x0d, packet.flags),
+                ClientboundPlayPacket::PlayerPositionAndLook(_) => break,
+                _ => {}
+            }
+            names.push(packet.name());
+        }
+        assert_eq!(vec!["JoinGame", "SpawnPosition", "PlayerAbilities"], names);
+
+        // the server is full now
+        let options = ClientOptions::builder()
+            .username("Notch".to_string())
+            .build();
+        let error = Client::connect(&address, options).await.err().unwrap();
+        assert!(error.to_string().contains("The server is full!"));
+
+        // 1.7.10 clients are kicked at login, as they can't play
+        let options = ClientOptions::builder()
+            .username("Notch".to_string())
+            .version(ProtocolVersion::V1_7_10)
+            .build();
+        let error = Client::connect(&address, options).await.err().unwrap();
+        assert!(error
+            .to_string()
+            .contains("Outdated client! Please use 1.8.9"));
+
+        stop.store(true, Ordering::Relaxed);
+        let mut server = ticks.join().unwrap();
+        let world = server.world_mut();
+        let players = world
+            .query::<&Player>()
+            .iter(world)
+            .map(|player| player.profile.name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["jeb_"], players);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn online() {
+        let session_server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = ServerConfiguration::builder()
+            .host("127.0.0.1")
+            .port(0)
+            .online_mode(true)
+            .session_server(format!(
+                "http://{}/session/minecraft/hasJoined",
+                session_server.local_addr().unwrap()
+            ))
+            .build();
+        let mut server = ServerBuilder::new(config).build().await.unwrap();
+        let address = server.local_addr().to_string();
+        let handle = server.handle();
+        let running = tokio::spawn(async move { server.run().await });
+
+        let body = r#"{"id": "069a79f444e94726a5befca90e38aaf5", "name": "Notch"}"#;
+        let request = tokio::spawn(mock_session_server(session_server, "200 OK", body));
+
+        // the session server decides who the player is
+        let joined = Arc::new(Joined::default());
+        let options = ClientOptions::builder()
+            .username("notch".to_string())
+            .authenticator(joined.clone())
+            .build();
+        let client = Client::connect(&address, options).await.unwrap();
+        assert_eq!("069a79f4-44e9-4726-a5be-fca90e38aaf5", client.uuid());
+        assert_eq!("Notch", client.username());
+
+        let hash = joined.0.lock().unwrap().clone().unwrap();
+        assert_eq!(
+            format!(
+                "GET /session/minecraft/hasJoined?username=notch&serverId={} HTTP/1.1",
+                hash
+            ),
+            request.await.unwrap()
+        );
+
+        handle.shutdown().unwrap();
+        running.await.unwrap();
+
+        // a player who didn't join the session
+        let session_server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hasJoined", session_server.local_addr().unwrap());
+
+        let config = ServerConfiguration::builder()
+            .host("127.0.0.1")
+            .port(0)
+            .online_mode(true)
+            .session_server(url)
+            .build();
+        let mut server = ServerBuilder::new(config).build().await.unwrap();
+        let address = server.local_addr().to_string();
+        let handle = server.handle();
+        let running = tokio::spawn(async move { server.run().await });
+        tokio::spawn(mock_session_server(session_server, "204 No Content", ""));
+
+        let options = ClientOptions::builder()
+            .username("Notch".to_string())
+            .authenticator(Arc::new(Joined::default()))
+            .build();
+        let error = Client::connect(&address, options).await.err().unwrap();
+        assert!(error.to_string().contains("Failed to verify username!"));
+
+        handle.shutdown().unwrap();
+        running.await.unwrap();
+    }
+}
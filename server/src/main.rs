@@ -1,79 +1,68 @@
-use bevy_ecs::{
-    prelude::Events,
-    schedule::{Schedule, Stage, StageLabel, SystemStage},
-    world::World,
-};
-use crossbeam_channel::unbounded;
-use protocol::chat::ChatComponent;
-use server::ServerConfiguration;
+use std::path::PathBuf;
 
-use crate::{
-    connection::{Connection, PacketContainer},
-    server::{accept_loop, handle_connections, handle_packet},
-    sync::{sync_connections, ChannelsRes},
+use clap::Parser;
+use server::{
+    config::{self, ConfigFile},
+    console, status, ServerBuilder,
 };
 
-pub mod connection;
-pub mod server;
-pub mod sync;
+#[derive(Parser)]
+#[command(name = "jam", about = "A lightweight Minecraft server")]
+struct Args {
+    /// The configuration file, which is generated if it doesn't exist.
+    #[arg(short, long, default_value = config::DEFAULT_PATH)]
+    config: PathBuf,
+    /// The host to bind on, instead of the configured one.
+    #[arg(long)]
+    host: Option<String>,
+    /// The port to bind on, instead of the configured one.
+    #[arg(short, long)]
+    port: Option<u16>,
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // create config
-    let motd = ChatComponent::builder()
-        .text("A Lightweight and High-Performance Minecraft Server".to_string())
-        .build();
-
-    let config = ServerConfiguration::builder()
-        .port(25565)
-        .motd(motd)
-        .build();
-
-    // bind server
-    let listener = server::bind(&config).await?;
-
-    // setup ECS
-    let mut world = World::new();
-
-    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-    pub struct FlushEvents;
-
-    world.insert_resource(Events::<PacketContainer>::default());
-    world.insert_resource(config);
+    let args = Args::parse();
 
-    let (connection_sender, connection_receiver) = unbounded::<Connection>();
-
-    let channels = ChannelsRes {
-        connection_sender: connection_sender.clone(),
-        connection_receiver: connection_receiver.clone(),
-    };
-
-    world.insert_resource(channels);
+    // load config
+    let mut config = config::load(&args.config)?;
+    if let Some(host) = args.host {
+        config.host = host;
+    }
+    if let Some(port) = args.port {
+        config.port = port;
+    }
 
-    // run schedule loop
-    let mut schedule = Schedule::default();
+    // vanilla's icon file, if there is one
+    if let Ok(png) = std::fs::read("server-icon.png") {
+        config.favicon = Some(status::favicon(&png)?);
+    }
 
-    // sync stage
-    #[derive(StageLabel)]
-    pub struct Sync;
+    // vanilla's warning, as anyone can join with any name
+    if !config.online_mode {
+        println!("**** SERVER IS RUNNING IN OFFLINE/INSECURE MODE!");
+        println!("The server will make no attempt to authenticate usernames. Beware.");
+        println!(
+            "To change this, set \"online_mode\" to \"true\" in {}",
+            args.config.display()
+        );
+    }
 
-    schedule.add_stage(Sync, SystemStage::parallel().with_system(sync_connections));
+    let mut server = ServerBuilder::new(config)
+        .insert_resource(ConfigFile { path: args.config })
+        .build()
+        .await?;
 
-    // network stage
-    #[derive(StageLabel)]
-    pub struct Network;
+    let handle = server.handle();
+    tokio::task::spawn(console::read_loop(handle.clone()));
 
-    schedule.add_stage(
-        Network,
-        SystemStage::parallel()
-            .with_system(Events::<PacketContainer>::update_system)
-            .with_system(handle_connections)
-            .with_system(handle_packet),
-    );
+    // ctrl-c stops the server like the stop command
+    tokio::task::spawn(async move {
+        tokio::signal::ctrl_c().await?;
+        handle.shutdown()
+    });
 
-    tokio::task::spawn(accept_loop(listener, connection_sender.clone()));
+    server.run().await;
 
-    loop {
-        schedule.run(&mut world);
-    }
+    Ok(())
 }
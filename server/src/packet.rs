@@ -0,0 +1,165 @@
+//! Typed events for the packets clients send.
+//!
+//! Every serverbound packet has its own event, a [PacketEvent] tagged with the entity of the
+//! connection it came from, so systems only read the packets they care about:
+//!
+//! ```
+//! # use bevy_ecs::prelude::EventReader;
+//! # use protocol::packet::serverbound::play::ChatMessagePacket;
+//! # use server::packet::PacketEvent;
+//! fn chat(mut events: EventReader<PacketEvent<ChatMessagePacket>>) {
+//!     for PacketEvent { entity, packet } in events.iter() {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! Packets are decoded by the connection tasks. Clients sending packets that can't be decoded are
+//! kicked, before any system sees them.
+
+use bevy_ecs::{entity::Entity, world::World};
+use protocol::packet::serverbound::{
+    handshaking, login, play, status, ServerboundHandshakingPacket, ServerboundLoginPacket,
+    ServerboundPacket, ServerboundPlayPacket, ServerboundStatusPacket,
+};
+
+use crate::builder::ServerBuilder;
+
+/// A packet the client of `entity` sent.
+#[derive(Debug, Clone)]
+pub struct PacketEvent<P> {
+    pub entity: Entity,
+    pub packet: P,
+}
+
+/// Adds an event for every packet of every set, and dispatches packets to them. Listing a set's
+/// packets is exhaustive: a packet added to the protocol has to be added here as well.
+macro_rules! packet_events {
+    ($($state:ident($set:ident) { $($variant:ident($packet:ty)),* $(,)? }),* $(,)?) => {
+        impl ServerBuilder {
+            /// Adds the [PacketEvent] of every serverbound packet.
+            pub(crate) fn add_packet_events(self) -> Self {
+                self $($(.add_event::<PacketEvent<$packet>>())*)*
+            }
+        }
+
+        /// Sends the [PacketEvent] of a packet.
+        pub(crate) fn send_event(world: &mut World, entity: Entity, packet: ServerboundPacket<'static>) {
+            match packet {
+                $($(
+                    ServerboundPacket::$state($set::$variant(packet)) => {
+                        world.send_event(PacketEvent { entity, packet })
+                    }
+                )*)*
+            }
+        }
+    };
+}
+
+packet_events! {
+    Handshaking(ServerboundHandshakingPacket) {
+        Handshake(handshaking::HandshakePacket),
+    },
+    Status(ServerboundStatusPacket) {
+        Request(status::RequestPacket),
+        Ping(status::PingPacket),
+    },
+    Login(ServerboundLoginPacket) {
+        LoginStart(login::LoginStartPacket),
+        EncryptionResponse(login::EncryptionResponsePacket),
+    },
+    Play(ServerboundPlayPacket) {
+        KeepAlive(play::KeepAlivePacket),
+        ChatMessage(play::ChatMessagePacket),
+        UseEntity(play::UseEntityPacket),
+        Player(play::PlayerPacket),
+        PlayerPosition(play::PlayerPositionPacket),
+        PlayerLook(play::PlayerLookPacket),
+        PlayerPositionAndLook(play::PlayerPositionAndLookPacket),
+        PlayerDigging(play::PlayerDiggingPacket),
+        PlayerBlockPlacement(play::PlayerBlockPlacementPacket),
+        HeldItemChange(play::HeldItemChangePacket),
+        Animation(play::AnimationPacket),
+        EntityAction(play::EntityActionPacket),
+        SteerVehicle(play::SteerVehiclePacket),
+        CloseWindow(play::CloseWindowPacket),
+        ClickWindow(play::ClickWindowPacket),
+        ConfirmTransaction(play::ConfirmTransactionPacket),
+        CreativeInventoryAction(play::CreativeInventoryActionPacket),
+        EnchantItem(play::EnchantItemPacket),
+        UpdateSign(play::UpdateSignPacket<'static>),
+        PlayerAbilities(play::PlayerAbilitiesPacket),
+        TabComplete(play::TabCompletePacket),
+        ClientSettings(play::ClientSettingsPacket),
+        ClientStatus(play::ClientStatusPacket),
+        PluginMessage(play::PluginMessagePacket),
+        Spectate(play::SpectatePacket),
+        ResourcePackStatus(play::ResourcePackStatusPacket),
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use protocol::{
+        packet::{
+            clientbound::{ClientboundLoginPacket, ClientboundPacket},
+            serverbound,
+        },
+        session::ClientSession,
+        varint::VarInt,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    use crate::{ServerBuilder, ServerConfiguration};
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn malformed() {
+        let config = ServerConfiguration::builder()
+            .host("127.0.0.1")
+            .port(0)
+            .build();
+        let mut server = ServerBuilder::new(config).build().await.unwrap();
+        let address = server.local_addr();
+        let handle = server.handle();
+        let running = tokio::spawn(async move { server.run().await });
+
+        let mut session = ClientSession::new();
+        session
+            .send(serverbound::handshaking::HandshakePacket {
+                protocol_version: VarInt(47),
+                server_address: "localhost".to_string(),
+                server_port: address.port(),
+                next_state: VarInt(2),
+            })
+            .unwrap();
+
+        // a login packet with an ID that doesn't exist
+        let mut bytes = session.take_outbound().to_vec();
+        bytes.extend_from_slice(&[1, 0x7f]);
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(&bytes).await.unwrap();
+
+        let mut buf = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        session.receive(&buf);
+
+        let Some(ClientboundPacket::Login(ClientboundLoginPacket::Disconnect(disconnect))) =
+            session.next_packet().unwrap()
+        else {
+            panic!("the client wasn't kicked");
+        };
+        assert_eq!("Malformed packet", disconnect.reason.plain_text());
+
+        handle.shutdown().unwrap();
+        running.await.unwrap();
+    }
+}
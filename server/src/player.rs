@@ -0,0 +1,69 @@
+//! Players, once they're logged in.
+
+use bevy_ecs::prelude::Component;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::GameProfile;
+
+/// A player, attached to the entity of their connection.
+#[derive(Component, Debug, Clone)]
+pub struct Player {
+    pub profile: GameProfile,
+}
+
+/// Where a player is, and where they're looking.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub x: f64,
+    /// The feet of the player.
+    pub y: f64,
+    pub z: f64,
+    /// In degrees.
+    pub yaw: f32,
+    /// In degrees.
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(Component, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    #[default]
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl GameMode {
+    /// The game mode with an ID, as sent in packets.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::Survival),
+            1 => Some(Self::Creative),
+            2 => Some(Self::Adventure),
+            3 => Some(Self::Spectator),
+            _ => None,
+        }
+    }
+
+    /// The ID sent in packets.
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Survival => 0,
+            Self::Creative => 1,
+            Self::Adventure => 2,
+            Self::Spectator => 3,
+        }
+    }
+
+    /// The flags of the player abilities packet: invulnerable (0x01), flying (0x02), allow flying
+    /// (0x04) and creative mode (0x08).
+    pub fn abilities(self) -> i8 {
+        match self {
+            Self::Survival | Self::Adventure => 0,
+            Self::Creative => 0x01 | 0x04 | 0x08,
+            Self::Spectator => 0x01 | 0x02 | 0x04,
+        }
+    }
+}
@@ -0,0 +1,372 @@
+//! Records what a spectator would see into a ReplayMod (`.mcpr`) file.
+//!
+//! A `.mcpr` file is a zip holding `recording.tmcpr`, the clientbound play packets, and
+//! `metaData.json`, which describes the recording. Every packet in `recording.tmcpr` is stored as
+//! the milliseconds since the recording started and its length (both as big-endian ints),
+//! followed by the packet ID and body, uncompressed. Recordings start with a join game, which
+//! puts the viewer in the world; ReplayMod spawns its own camera from there.
+//!
+//! With a [recording_dir](crate::server::ServerConfiguration::recording_dir), every player is
+//! recorded from when they join: a [Recording] is fed what is flushed from their [Outbox], and
+//! written out on a blocking task once they leave.
+
+use std::{
+    fs::File,
+    io::{Seek, Write},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bevy_ecs::{
+    prelude::{Component, EventReader},
+    query::Changed,
+    system::{Query, Res},
+};
+use bytes::{BufMut, BytesMut};
+use protocol::{
+    encoding::Encode,
+    packet::{
+        clientbound::{play::JoinGamePacket, ClientboundPacket, ClientboundPlayPacket},
+        PacketSet,
+    },
+    varint::VarInt,
+    version::ProtocolVersion,
+};
+use serde_json::json;
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{
+    auth::GameProfile,
+    connection::Outbox,
+    disconnect::Disconnected,
+    sync::{ConnectionTracker, RuntimeRes},
+};
+
+/// The format ReplayMod for 1.8 writes, which later versions upgrade from.
+const FILE_FORMAT_VERSION: i32 = 1;
+
+/// Records a stream of clientbound play packets, as seen by a spectator.
+pub struct Recorder {
+    server_name: String,
+    started: Instant,
+    /// Milliseconds since the Unix epoch.
+    date: u64,
+    /// The entity ID of the viewer, from the join game.
+    self_id: i32,
+    /// The time of the last packet, in milliseconds since the start.
+    duration: u32,
+    players: Vec<String>,
+    packets: BytesMut,
+    body: BytesMut,
+}
+
+impl Recorder {
+    /// Starts a recording with the join game the viewer sees.
+    pub fn new(server_name: String, join_game: JoinGamePacket) -> anyhow::Result<Self> {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_millis() as u64)
+            .unwrap_or_default();
+
+        let mut recorder = Self {
+            server_name,
+            started: Instant::now(),
+            date,
+            self_id: join_game.entity_id,
+            duration: 0,
+            players: Vec::new(),
+            packets: BytesMut::new(),
+            body: BytesMut::new(),
+        };
+        recorder.record_at(0, &ClientboundPlayPacket::JoinGame(join_game))?;
+
+        Ok(recorder)
+    }
+
+    /// Lists a player (by their hyphenated UUID) as being in the recording.
+    pub fn add_player(&mut self, uuid: String) {
+        if !self.players.contains(&uuid) {
+            self.players.push(uuid);
+        }
+    }
+
+    /// Records a packet sent now.
+    pub fn record(&mut self, packet: &ClientboundPlayPacket) -> anyhow::Result<()> {
+        let time = u32::try_from(self.started.elapsed().as_millis()).unwrap_or(u32::MAX);
+        self.record_at(time, packet)
+    }
+
+    /// Records a packet sent `time` milliseconds after the recording started.
+    pub fn record_at(&mut self, time: u32, packet: &ClientboundPlayPacket) -> anyhow::Result<()> {
+        let version = ProtocolVersion::V1_8;
+        let id = packet
+            .id_for(version)
+            .ok_or_else(|| anyhow::anyhow!("{} doesn't exist in 1.8", packet.name()))?;
+
+        self.body.clear();
+        VarInt(id).encode(&mut self.body)?;
+        packet.encode_for(version, &mut self.body)?;
+
+        self.duration = self.duration.max(time);
+        self.packets.put_u32(time);
+        self.packets.put_u32(self.body.len() as u32);
+        self.packets.put_slice(&self.body);
+
+        Ok(())
+    }
+
+    /// Writes the recording out as a `.mcpr` file.
+    pub fn finish<W: Write + Seek>(self, writer: W) -> anyhow::Result<W> {
+        let meta = json!({
+            "singleplayer": false,
+            "serverName": self.server_name,
+            "duration": self.duration,
+            "date": self.date,
+            "mcversion": ProtocolVersion::V1_8.name(),
+            "fileFormat": "MCPR",
+            "fileFormatVersion": FILE_FORMAT_VERSION,
+            "protocol": ProtocolVersion::V1_8.protocol(),
+            "generator": "jam",
+            "selfId": self.self_id,
+            "players": self.players,
+        });
+
+        let mut zip = ZipWriter::new(writer);
+        let options = FileOptions::default();
+
+        zip.start_file("recording.tmcpr", options)?;
+        zip.write_all(&self.packets)?;
+        zip.start_file("metaData.json", options)?;
+        zip.write_all(meta.to_string().as_bytes())?;
+
+        Ok(zip.finish()?)
+    }
+}
+
+/// The recording of what a player sees, written to its file by [save_recordings] once they
+/// leave.
+#[derive(Component)]
+pub struct Recording {
+    recorder: Option<Recorder>,
+    path: PathBuf,
+}
+
+impl Recording {
+    /// Starts recording a player who joined with `join_game`, into a file in `dir` named after
+    /// them. Nothing is written until they leave.
+    pub fn start(
+        dir: &Path,
+        server_name: String,
+        profile: &GameProfile,
+        join_game: JoinGamePacket,
+    ) -> anyhow::Result<Self> {
+        let mut recorder = Recorder::new(server_name, join_game)?;
+        recorder.add_player(profile.hyphenated_id());
+        let path = dir.join(format!("{}-{}.mcpr", profile.name, recorder.date));
+
+        Ok(Self {
+            recorder: Some(recorder),
+            path,
+        })
+    }
+
+    /// Where the recording is written to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn record(&mut self, packet: &ClientboundPlayPacket) {
+        let Some(recorder) = &mut self.recorder else {
+            return;
+        };
+
+        if let Err(error) = recorder.record(packet) {
+            println!("recording {} failed: {:#}", self.path.display(), error);
+            self.recorder = None;
+        }
+    }
+}
+
+/// Creates the recording's file (and its directory), and writes the recording to it.
+fn save(path: &Path, recorder: Recorder) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    recorder.finish(File::create(path)?)?;
+    Ok(())
+}
+
+/// System that writes out the [Recording]s of the players who left, on a blocking task. It runs
+/// before their entities are despawned.
+pub fn save_recordings(
+    mut events: EventReader<Disconnected>,
+    mut recordings: Query<&mut Recording>,
+    runtime: Res<RuntimeRes>,
+    tracker: Res<ConnectionTracker>,
+) {
+    for Disconnected { entity, .. } in events.iter() {
+        let Ok(mut recording) = recordings.get_mut(*entity) else {
+            continue;
+        };
+        let Some(recorder) = recording.recorder.take() else {
+            continue;
+        };
+
+        let path = recording.path.clone();
+        let open = tracker.open();
+        runtime.0.spawn_blocking(move || {
+            match save(&path, recorder) {
+                Ok(()) => println!("saved recording {}", path.display()),
+                Err(error) => println!("couldn't save {}: {:#}", path.display(), error),
+            }
+            drop(open);
+        });
+    }
+}
+
+/// System that feeds [Recording]s the play packets queued for their player, before they're
+/// flushed.
+pub fn record_outboxes(mut recordings: Query<(&Outbox, &mut Recording), Changed<Outbox>>) {
+    for (outbox, mut recording) in &mut recordings {
+        for packet in outbox.packets() {
+            match packet {
+                // the recording already starts with it
+                ClientboundPacket::Play(ClientboundPlayPacket::JoinGame(_)) => {}
+                ClientboundPacket::Play(packet) => recording.record(packet),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs::{self, File},
+        io::{Cursor, Read},
+    };
+
+    use client::{Client, ClientOptions};
+    use protocol::{
+        packet::clientbound::{play, ClientboundPlayPacket},
+        varint::VarInt,
+    };
+    use zip::ZipArchive;
+
+    use super::Recorder;
+    use crate::{ServerBuilder, ServerConfiguration};
+
+    #[test]
+    pub fn mcpr() {
+        let join_game = play::JoinGamePacket {
+            entity_id: 42,
+            gamemode: 3,
+            dimension: 0,
+            difficulty: 0,
+            max_players: 20,
+            level_type: "flat".to_string(),
+            reduced_debug_info: false,
+        };
+
+        let mut recorder = Recorder::new("jam".to_string(), join_game).unwrap();
+        recorder.add_player("00000000-0000-0000-0000-000000000001".to_string());
+        recorder
+            .record_at(
+                1500,
+                &ClientboundPlayPacket::KeepAlive(play::KeepAlivePacket { id: VarInt(7) }),
+            )
+            .unwrap();
+
+        let file = recorder.finish(Cursor::new(Vec::new())).unwrap();
+        let mut zip = ZipArchive::new(file).unwrap();
+
+        let mut meta = String::new();
+        zip.by_name("metaData.json")
+            .unwrap()
+            .read_to_string(&mut meta)
+            .unwrap();
+        let meta: serde_json::Value = serde_json::from_str(&meta).unwrap();
+        assert_eq!(1500, meta["duration"]);
+        assert_eq!(42, meta["selfId"]);
+        assert_eq!("1.8", meta["mcversion"]);
+        assert_eq!("00000000-0000-0000-0000-000000000001", meta["players"][0]);
+
+        let mut packets = Vec::new();
+        zip.by_name("recording.tmcpr")
+            .unwrap()
+            .read_to_end(&mut packets)
+            .unwrap();
+
+        // the join game (id 0x01) comes first, then the keep alive (id 0x00)
+        assert_eq!([0, 0, 0, 0], packets[0..4]);
+        let len = u32::from_be_bytes(packets[4..8].try_into().unwrap()) as usize;
+        assert_eq!(0x01, packets[8]);
+
+        let keep_alive = &packets[8 + len..];
+        assert_eq!([0, 0, 0x05, 0xdc, 0, 0, 0, 2, 0x00, 0x07], keep_alive);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub async fn login() {
+        let dir = std::env::temp_dir().join(format!("jam-recording-{}", std::process::id()));
+        let config = ServerConfiguration::builder()
+            .host("127.0.0.1")
+            .port(0)
+            .recording_dir(dir.to_string_lossy())
+            .build();
+        let mut server = ServerBuilder::new(config).build().await.unwrap();
+        let address = server.local_addr().to_string();
+        let handle = server.handle();
+        let running = tokio::spawn(async move { server.run().await });
+
+        let options = ClientOptions::builder()
+            .username("jeb_".to_string())
+            .build();
+        let mut client = Client::connect(&address, options).await.unwrap();
+        while let Some(packet) = client.next_packet().await {
+            if let ClientboundPlayPacket::PlayerPositionAndLook(_) = packet {
+                break;
+            }
+        }
+
+        // the recording is written once the player is gone
+        handle.shutdown().unwrap();
+        running.await.unwrap();
+
+        let file = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        assert!(file
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("jeb_-"));
+        let mut zip = ZipArchive::new(File::open(file).unwrap()).unwrap();
+
+        let mut meta = String::new();
+        zip.by_name("metaData.json")
+            .unwrap()
+            .read_to_string(&mut meta)
+            .unwrap();
+        let meta: serde_json::Value = serde_json::from_str(&meta).unwrap();
+        assert_eq!(client.uuid(), meta["players"][0]);
+
+        let mut packets = Vec::new();
+        zip.by_name("recording.tmcpr")
+            .unwrap()
+            .read_to_end(&mut packets)
+            .unwrap();
+        let mut ids = Vec::new();
+        let mut packets = &packets[..];
+        while !packets.is_empty() {
+            let len = u32::from_be_bytes(packets[4..8].try_into().unwrap()) as usize;
+            ids.push(packets[8]);
+            packets = &packets[8 + len..];
+        }
+
+        // join game, spawn position, player abilities and position, with the join game only once
+        assert_eq!([0x01, 0x05, 0x39, 0x08], ids[..4]);
+        assert_eq!(1, ids.iter().filter(|id| **id == 0x01).count());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
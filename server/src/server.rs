@@ -1,20 +1,28 @@
-use std::{io::BufRead, net::TcpListener};
-
-use crate::connection::{Connection, PacketContainer};
 use anyhow::Result;
-use bevy_ecs::{
-    prelude::{EventReader, EventWriter},
-    system::{Query, Resource},
-};
-use bytes::{Buf, BytesMut};
+use bevy_ecs::{system::Resource, world::World};
 use crossbeam_channel::Sender;
-use protocol::{chat::ChatComponent, encoding::Encodable, state::State, varint::VarInt};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
 use typed_builder::TypedBuilder;
 
-#[derive(Resource, TypedBuilder)]
-pub struct ServerConfiguration<'a> {
-    #[builder(default = "0.0.0.0")]
-    pub host: &'a str,
+use crate::{
+    auth::DEFAULT_SESSION_SERVER,
+    connection::{Closed, NetworkEvent},
+    disconnect::Disconnect,
+    packet,
+    player::GameMode,
+    sync::{ChannelsRes, NewConnection},
+};
+
+/// The default message of the day, shown in the server list.
+const DEFAULT_MOTD: &str = "A Lightweight and High-Performance Minecraft Server";
+
+/// How the server is set up, usually loaded from a file (see [config](crate::config)).
+#[derive(Resource, TypedBuilder, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ServerConfiguration {
+    #[builder(default = "0.0.0.0".to_string(), setter(into))]
+    pub host: String,
 
     #[builder(default = 25565)]
     pub port: u16,
@@ -22,72 +30,112 @@ pub struct ServerConfiguration<'a> {
     #[builder(default = 20)]
     pub max_players: i32,
 
-    #[builder]
-    pub motd: ChatComponent<'a>,
+    /// The game mode players join in.
+    #[builder(default)]
+    pub gamemode: GameMode,
+
+    /// 0: peaceful, 1: easy, 2: normal, 3: hard.
+    #[builder(default = 1)]
+    pub difficulty: u8,
+
+    /// Sent to clients, which render the sky and fog for it: `default`, `flat`, `largeBiomes`,
+    /// `amplified` or `default_1_1`.
+    #[builder(default = "default".to_string(), setter(into))]
+    pub level_type: String,
+
+    /// How many chunks around them players see. Unused for now, as jam doesn't send chunks yet.
+    #[builder(default = 10)]
+    pub view_distance: u8,
+
+    /// The message of the day, shown in the server list.
+    #[builder(default = DEFAULT_MOTD.to_string(), setter(into))]
+    pub motd: String,
+
+    /// The reason shown to players who didn't answer keep alives in time.
+    #[builder(default = "Timed out".to_string(), setter(into))]
+    pub timeout_message: String,
+
+    /// The reason shown to players when the server shuts down.
+    #[builder(default = "Server closed".to_string(), setter(into))]
+    pub shutdown_message: String,
+
+    /// The favicon shown in the server list, as a `data:` URI (see
+    /// [favicon](crate::status::favicon)). It's loaded from `server-icon.png`, rather than the
+    /// configuration file.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip)]
+    pub favicon: Option<String>,
+
+    /// Whether players are checked with the session server. In offline mode, anyone can log in
+    /// with any name.
+    #[builder(default = false)]
+    pub online_mode: bool,
+
+    /// The `hasJoined` endpoint players are checked with in online mode.
+    #[builder(default = DEFAULT_SESSION_SERVER.to_string(), setter(into))]
+    pub session_server: String,
+
+    /// Records what every player sees into a ReplayMod (`.mcpr`) file in this directory, which is
+    /// written once they leave.
+    #[builder(default, setter(strip_option, into))]
+    pub recording_dir: Option<String>,
+
+    /// Captures every connection (see [protocol::capture]) into a file in this directory, to be
+    /// inspected or replayed with `jam-capture`.
+    #[builder(default, setter(strip_option, into))]
+    pub capture_dir: Option<String>,
 }
 
-/// Binds the server on a set host and port (given by a [ServerConfiguration].)
-pub async fn bind<'a>(config: &ServerConfiguration<'a>) -> Result<TcpListener> {
-    let host = config.host;
-    let port = config.port;
-
-    Ok(TcpListener::bind(format!("{}:{}", host, port))?)
+impl Default for ServerConfiguration {
+    fn default() -> Self {
+        Self::builder().build()
+    }
 }
 
-/// Accepts connections from a [TcpListener].
-pub async fn accept_loop(listener: TcpListener, sender: Sender<Connection>) -> Result<()> {
-    loop {
-        // accept connection (this is very blocking)
-        let (stream, _) = listener.accept()?;
-
-        // create component for the connection
-        let connection = Connection {
-            state: State::default(),
-            stream,
-            buf: vec![],
-        };
-
-        sender.send(connection)?;
+impl ServerConfiguration {
+    /// Applies the settings of `new` that can change while the server runs: only the MOTD and
+    /// the max players. The others need a restart.
+    pub fn reload(&mut self, new: ServerConfiguration) {
+        self.motd = new.motd;
+        self.max_players = new.max_players;
     }
 }
 
-pub fn handle_connections(
-    mut query: Query<&mut Connection>,
-    mut writer: EventWriter<PacketContainer>,
-) {
-    for mut connection in &mut query {
-        // this is currently blocking the thread. any idea?
-        if let Err(_) = connection.read() {
-            continue;
-        }
-
-        let buf = &connection.buf;
-        let buf = &mut BytesMut::from(buf.as_slice());
-
-        while !buf.is_empty() {
-            // read packet frame
-            let length = VarInt::decode(buf).expect("unable to decode length as VarInt");
-            let id = VarInt::decode(buf).expect("unable to decode id as VarInt");
-
-            let mut buffer = Vec::<u8>::new();
+/// Binds the server on a set host and port (given by a [ServerConfiguration].)
+pub async fn bind(config: &ServerConfiguration) -> Result<TcpListener> {
+    let host = &config.host;
+    let port = config.port;
 
-            buf.reader()
-                .read_until(length.0 as u8, &mut buffer)
-                .expect("unable to read packet");
+    Ok(TcpListener::bind(format!("{}:{}", host, port)).await?)
+}
 
-            let container = PacketContainer {
-                id,
-                length,
-                data: buffer,
-            };
+/// Accepts connections from a [TcpListener], and hands them to the ECS.
+pub async fn accept_loop(listener: TcpListener, sender: Sender<NewConnection>) -> Result<()> {
+    loop {
+        let (stream, address) = listener.accept().await?;
+        stream.set_nodelay(true)?;
 
-            writer.send(container);
-        }
+        sender.send(NewConnection { stream, address })?;
     }
 }
 
-pub fn handle_packet(mut event: EventReader<PacketContainer>) {
-    for event in event.iter() {
-        dbg!(event);
+/// Turns what the connection tasks report into a [PacketEvent](crate::packet::PacketEvent) for
+/// every packet, and [Closed] events. Clients that sent a malformed packet are kicked.
+pub fn handle_connections(world: &mut World) {
+    let receiver = world.resource::<ChannelsRes>().event_receiver.clone();
+
+    for event in receiver.try_iter() {
+        match event {
+            NetworkEvent::Packet(entity, packet) => packet::send_event(world, entity, packet),
+            NetworkEvent::Malformed(entity, error) => {
+                println!(
+                    "malformed packet: [entity id: {}] {:#}",
+                    entity.index(),
+                    error
+                );
+                world.send_event(Disconnect::new(entity, "Malformed packet"));
+            }
+            NetworkEvent::Closed(entity, error) => world.send_event(Closed { entity, error }),
+        }
     }
 }
@@ -0,0 +1,178 @@
+//! Answers the server list ping.
+//!
+//! Clients ask for the status (the version, player count and MOTD shown in the server list), and
+//! may then ping the server to measure the latency. The connection is closed after the pong.
+
+use anyhow::bail;
+use base64::Engine;
+use bevy_ecs::{
+    prelude::EventReader,
+    system::{Commands, Query, Res},
+};
+use protocol::{
+    packet::{
+        clientbound::status::{PongPacket, ResponsePacket},
+        serverbound::status::{PingPacket, RequestPacket},
+    },
+    version::ProtocolVersion,
+};
+use serde_json::json;
+
+use crate::{
+    auth::GameProfile,
+    connection::{Connection, Outbox},
+    packet::PacketEvent,
+    player::Player,
+    server::ServerConfiguration,
+};
+
+/// How many of the online players are listed when hovering over the player count, like vanilla.
+const MAX_SAMPLE: usize = 12;
+
+/// The PNG signature, followed by the length and type of the `IHDR` chunk, which comes first.
+const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR";
+
+/// Encodes a PNG as a favicon for the status, which has to be 64x64 pixels.
+pub fn favicon(png: &[u8]) -> anyhow::Result<String> {
+    if png.len() < PNG_HEADER.len() + 8 || !png.starts_with(PNG_HEADER) {
+        bail!("the favicon isn't a PNG");
+    }
+
+    let size = &png[PNG_HEADER.len()..PNG_HEADER.len() + 8];
+    let width = u32::from_be_bytes(size[..4].try_into()?);
+    let height = u32::from_be_bytes(size[4..].try_into()?);
+    if (width, height) != (64, 64) {
+        bail!("the favicon is {}x{}, instead of 64x64", width, height);
+    }
+
+    Ok(format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(png)
+    ))
+}
+
+/// The status sent to a client with the given protocol number.
+///
+/// Clients with a version that can play are answered with their own version; any other client is
+/// shown the latest version, and that it's incompatible.
+pub fn response<'a>(
+    config: &ServerConfiguration,
+    protocol: i32,
+    online: usize,
+    sample: impl Iterator<Item = &'a GameProfile>,
+) -> String {
+    let version = ProtocolVersion::from_protocol(protocol)
+        .filter(|version| version.can_play())
+        .unwrap_or(ProtocolVersion::LATEST);
+    let sample = sample
+        .take(MAX_SAMPLE)
+        .map(|profile| {
+            json!({
+                "name": profile.name,
+                "id": profile.hyphenated_id(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut response = json!({
+        "version": {
+            "name": version.name(),
+            "protocol": version.protocol(),
+        },
+        "players": {
+            "max": config.max_players,
+            "online": online,
+            "sample": sample,
+        },
+        "description": {
+            "text": config.motd,
+        },
+    });
+
+    if let Some(favicon) = &config.favicon {
+        response["favicon"] = favicon.clone().into();
+    }
+
+    response.to_string()
+}
+
+/// System that answers status requests and pings.
+pub fn handle_status(
+    mut requests: EventReader<PacketEvent<RequestPacket>>,
+    mut pings: EventReader<PacketEvent<PingPacket>>,
+    config: Res<ServerConfiguration>,
+    mut connections: Query<(&Connection, &mut Outbox)>,
+    players: Query<&Player>,
+    mut commands: Commands,
+) {
+    for PacketEvent { entity, .. } in requests.iter() {
+        if let Ok((connection, mut outbox)) = connections.get_mut(*entity) {
+            outbox.send(ResponsePacket {
+                response: response(
+                    &config,
+                    connection.protocol(),
+                    players.iter().count(),
+                    players.iter().map(|player| &player.profile),
+                ),
+            });
+        }
+    }
+
+    for PacketEvent { entity, packet } in pings.iter() {
+        if let Ok((_, mut outbox)) = connections.get_mut(*entity) {
+            outbox.send(PongPacket {
+                payload: packet.payload,
+            });
+
+            // the pong is still written once the outbox is dropped
+            commands.entity(*entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{favicon, response};
+    use crate::{auth::GameProfile, server::ServerConfiguration};
+
+    #[test]
+    pub fn status() {
+        let config = ServerConfiguration::builder()
+            .max_players(10)
+            .motd("jam")
+            .build();
+        let players = ["Notch", "jeb_"].map(GameProfile::offline);
+
+        let status: serde_json::Value =
+            serde_json::from_str(&response(&config, 5, 2, players.iter())).unwrap();
+        assert_eq!("1.8", status["version"]["name"]);
+        assert_eq!(47, status["version"]["protocol"]);
+        assert_eq!(10, status["players"]["max"]);
+        assert_eq!(2, status["players"]["online"]);
+        assert_eq!("jeb_", status["players"]["sample"][1]["name"]);
+        assert_eq!(
+            "b50ad385-829d-3141-a216-7e7d7539ba7f",
+            status["players"]["sample"][0]["id"]
+        );
+        assert_eq!("jam", status["description"]["text"]);
+        assert!(status.get("favicon").is_none());
+
+        // unknown versions are shown the latest one too
+        let status: serde_json::Value =
+            serde_json::from_str(&response(&config, 1000, 0, [].iter())).unwrap();
+        assert_eq!(47, status["version"]["protocol"]);
+    }
+
+    #[test]
+    pub fn png_favicon() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 0, 64, 0, 0, 0, 64, 8, 6, 0, 0, 0]);
+        assert!(favicon(&png)
+            .unwrap()
+            .starts_with("data:image/png;base64,iVBORw0KGgo"));
+
+        png[19] = 32;
+        assert!(favicon(&png).is_err());
+        assert!(favicon(b"GIF89a").is_err());
+    }
+}
@@ -1,26 +1,116 @@
-use bevy_ecs::system::{Commands, Res, Resource};
+//! This module takes care of synchronizing the async parts with the ECS.
+
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy_ecs::{
+    entity::Entity,
+    system::{Commands, Res, Resource},
+};
 use crossbeam_channel::{Receiver, Sender};
+use tokio::{net::TcpStream, runtime::Handle, sync::mpsc};
 
-use crate::connection::Connection;
+use crate::{
+    connection::{Capture, Connection, NetworkEvent},
+    server::ServerConfiguration,
+};
 
-///! This module takes care of synchronizing the async parts with the ECS.
+/// A connection that was just accepted.
+pub struct NewConnection {
+    pub stream: TcpStream,
+    pub address: SocketAddr,
+}
 
 #[derive(Resource, Clone)]
 pub struct ChannelsRes {
-    pub connection_sender: Sender<Connection>,
-    pub connection_receiver: Receiver<Connection>,
+    pub connection_sender: Sender<NewConnection>,
+    pub connection_receiver: Receiver<NewConnection>,
+    pub event_sender: Sender<NetworkEvent>,
+    pub event_receiver: Receiver<NetworkEvent>,
+}
+
+/// The tokio runtime the connection tasks run on, as systems run outside of it.
+#[derive(Resource, Clone)]
+pub struct RuntimeRes(pub Handle);
+
+/// Keeps track of the connections whose writer task (or capture, or recording) is still running,
+/// so the server can wait for them to finish writing before it exits.
+#[derive(Resource)]
+pub struct ConnectionTracker {
+    open: mpsc::Sender<()>,
+    closed: mpsc::Receiver<()>,
+}
+
+impl ConnectionTracker {
+    pub fn new() -> Self {
+        let (open, closed) = mpsc::channel(1);
+        Self { open, closed }
+    }
+
+    /// A token held by a writer task (or a capture, or a recording) for as long as it runs.
+    pub fn open(&self) -> mpsc::Sender<()> {
+        self.open.clone()
+    }
+
+    /// Waits until every writer task, capture and recording is done.
+    pub async fn wait(self) {
+        let Self { open, mut closed } = self;
+        drop(open);
+
+        // nothing is ever sent, this returns once every token is dropped
+        let _ = closed.recv().await;
+    }
 }
 
-/// System that creates entities based on [Connection]s from [ChannelRes].
-pub fn sync_connections(channels: Res<ChannelsRes>, mut commands: Commands) {
-    // try to read an event from the channel. if not read, just return
-    match channels.connection_receiver.try_recv() {
-        Ok(connection) => {
-            // if an event is read, get the connection and attach it to an entity
-            let id = commands.spawn(connection).id().index();
-
-            println!("new connection: [entity id: {}]", id);
-        }
-        Err(_) => {}
+impl Default for ConnectionTracker {
+    fn default() -> Self {
+        Self::new()
     }
 }
+
+/// System that creates entities for [NewConnection]s from [ChannelsRes], and starts their tasks.
+/// With a [capture_dir](ServerConfiguration::capture_dir), every connection is captured.
+pub fn sync_connections(
+    channels: Res<ChannelsRes>,
+    runtime: Res<RuntimeRes>,
+    tracker: Res<ConnectionTracker>,
+    config: Res<ServerConfiguration>,
+    mut commands: Commands,
+) {
+    for NewConnection { stream, address } in channels.connection_receiver.try_iter() {
+        let entity = commands.spawn_empty().id();
+        let capture = config
+            .capture_dir
+            .as_ref()
+            .map(|dir| Capture::start(capture_path(Path::new(dir), entity), &runtime.0, &tracker));
+        let (connection, outbox) = Connection::start(
+            entity,
+            stream,
+            address,
+            channels.event_sender.clone(),
+            &tracker,
+            &runtime.0,
+            capture,
+        );
+        commands.entity(entity).insert((connection, outbox));
+
+        println!(
+            "new connection from {}: [entity id: {}]",
+            address,
+            entity.index()
+        );
+    }
+}
+
+/// Where the capture of the connection of `entity` goes in `dir`, named after when it started.
+fn capture_path(dir: &Path, entity: Entity) -> PathBuf {
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_millis())
+        .unwrap_or_default();
+
+    dir.join(format!("{}-{}.jamcap", started, entity.index()))
+}
@@ -0,0 +1,228 @@
+//! Runs the schedule at a fixed 20 ticks per second.
+//!
+//! Ticks are due every 50 ms. When ticks take too long, the ticks missed in the meantime run back
+//! to back to catch up, up to [MAX_CATCH_UP_TICKS]; any more are skipped, so the server doesn't
+//! try to fast-forward after stalling for a long time.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use bevy_ecs::{
+    schedule::{Schedule, Stage},
+    system::Resource,
+    world::World,
+};
+
+pub const TICKS_PER_SECOND: u32 = 20;
+
+pub const TICK_DURATION: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND as u64);
+
+/// How many ticks are run back to back when behind, before the rest are skipped.
+pub const MAX_CATCH_UP_TICKS: u32 = 10;
+
+/// How many ticks the [TickMetrics] are measured over (5 seconds' worth).
+const METRICS_WINDOW: usize = 100;
+
+/// The tick being run, counting from 1.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    pub count: u64,
+}
+
+/// Stops the tick loop once requested, at the end of the tick.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Shutdown {
+    pub requested: bool,
+}
+
+/// How fast the server has been ticking lately.
+#[derive(Resource, Debug, Default)]
+pub struct TickMetrics {
+    /// When the last ticks started.
+    starts: VecDeque<Instant>,
+    /// How long the last ticks took.
+    durations: VecDeque<Duration>,
+}
+
+impl TickMetrics {
+    fn record(&mut self, start: Instant, duration: Duration) {
+        if self.starts.len() == METRICS_WINDOW {
+            self.starts.pop_front();
+            self.durations.pop_front();
+        }
+
+        self.starts.push_back(start);
+        self.durations.push_back(duration);
+    }
+
+    /// The ticks per second, which is 20 unless the server can't keep up.
+    pub fn tps(&self) -> f64 {
+        match (self.starts.front(), self.starts.back()) {
+            (Some(first), Some(last)) if last > first => {
+                (self.starts.len() - 1) as f64 / (*last - *first).as_secs_f64()
+            }
+            _ => TICKS_PER_SECOND as f64,
+        }
+    }
+
+    /// The milliseconds a tick takes on average.
+    pub fn mspt(&self) -> f64 {
+        if self.durations.is_empty() {
+            return 0.0;
+        }
+
+        let total = self.durations.iter().sum::<Duration>();
+        total.as_secs_f64() * 1000.0 / self.durations.len() as f64
+    }
+}
+
+/// The ticks that are due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Due {
+    pub run: u32,
+    pub skipped: u32,
+}
+
+/// Keeps track of when the next tick is due.
+pub struct TickClock {
+    next: Instant,
+}
+
+impl TickClock {
+    /// A clock with the first tick due at `start`.
+    pub fn new(start: Instant) -> Self {
+        Self { next: start }
+    }
+
+    /// When the next tick is due.
+    pub fn next(&self) -> Instant {
+        self.next
+    }
+
+    /// Takes the ticks due at `now`. Past [MAX_CATCH_UP_TICKS], the rest are skipped, and the
+    /// clock starts over from `now`.
+    pub fn advance(&mut self, now: Instant) -> Due {
+        if now < self.next {
+            return Due { run: 0, skipped: 0 };
+        }
+
+        let due = ((now - self.next).as_nanos() / TICK_DURATION.as_nanos()) as u32 + 1;
+
+        if due > MAX_CATCH_UP_TICKS {
+            self.next = now + TICK_DURATION;
+            Due {
+                run: MAX_CATCH_UP_TICKS,
+                skipped: due - MAX_CATCH_UP_TICKS,
+            }
+        } else {
+            self.next += TICK_DURATION * due;
+            Due {
+                run: due,
+                skipped: 0,
+            }
+        }
+    }
+}
+
+/// Runs one tick of the schedule, counting and measuring it.
+pub fn run_tick(world: &mut World, schedule: &mut Schedule) {
+    let start = Instant::now();
+
+    world.get_resource_or_insert_with(Tick::default).count += 1;
+    schedule.run(world);
+
+    world
+        .get_resource_or_insert_with(TickMetrics::default)
+        .record(start, start.elapsed());
+}
+
+/// Runs the schedule 20 times per second, until a [Shutdown] is requested.
+pub async fn run(world: &mut World, schedule: &mut Schedule) {
+    let mut clock = TickClock::new(Instant::now());
+
+    while !world
+        .get_resource::<Shutdown>()
+        .is_some_and(|shutdown| shutdown.requested)
+    {
+        let due = clock.advance(Instant::now());
+        if due.skipped > 0 {
+            println!(
+                "can't keep up! skipping {} ticks ({} ms behind)",
+                due.skipped,
+                due.skipped * TICK_DURATION.as_millis() as u32
+            );
+        }
+
+        for _ in 0..due.run {
+            run_tick(world, schedule);
+        }
+
+        tokio::time::sleep_until(clock.next().into()).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{Due, TickClock, TickMetrics, MAX_CATCH_UP_TICKS, TICK_DURATION};
+
+    #[test]
+    pub fn clock() {
+        let start = Instant::now();
+        let mut clock = TickClock::new(start);
+
+        assert_eq!(Due { run: 1, skipped: 0 }, clock.advance(start));
+        assert_eq!(
+            Due { run: 0, skipped: 0 },
+            clock.advance(start + TICK_DURATION / 2)
+        );
+        assert_eq!(start + TICK_DURATION, clock.next());
+
+        // 3 ticks behind
+        assert_eq!(
+            Due { run: 3, skipped: 0 },
+            clock.advance(start + TICK_DURATION * 3 + TICK_DURATION / 2)
+        );
+        assert_eq!(start + TICK_DURATION * 4, clock.next());
+
+        // further behind than MAX_CATCH_UP_TICKS
+        let now = start + TICK_DURATION * 4 + Duration::from_secs(10);
+        assert_eq!(
+            Due {
+                run: MAX_CATCH_UP_TICKS,
+                skipped: 191
+            },
+            clock.advance(now)
+        );
+        assert_eq!(now + TICK_DURATION, clock.next());
+
+        // one tick further behind than that still catches up as much
+        let now = clock.next() + TICK_DURATION * MAX_CATCH_UP_TICKS;
+        assert_eq!(
+            Due {
+                run: MAX_CATCH_UP_TICKS,
+                skipped: 1
+            },
+            clock.advance(now)
+        );
+    }
+
+    #[test]
+    pub fn metrics() {
+        let mut metrics = TickMetrics::default();
+        assert_eq!(20.0, metrics.tps());
+
+        let start = Instant::now();
+        for tick in 0..200 {
+            // every other tick takes 30 ms instead of 10 ms, and ticks are 100 ms apart
+            let duration = Duration::from_millis(if tick % 2 == 0 { 10 } else { 30 });
+            metrics.record(start + Duration::from_millis(tick * 100), duration);
+        }
+
+        assert!((metrics.tps() - 10.0).abs() < 1e-9);
+        assert!((metrics.mspt() - 20.0).abs() < 1e-9);
+    }
+}